@@ -4,6 +4,8 @@ use bencher::*;
 use deno_core::error::generic_error;
 use deno_core::*;
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ffi::c_void;
 
 deno_core::extension!(
@@ -19,6 +21,8 @@ deno_core::extension!(
     op_string_onebyte,
     op_string_bytestring,
     op_string_option_u32,
+    op_string_echo,
+    op_string_echo_cow,
     op_local,
     op_local_scope,
     op_local_nofast,
@@ -35,6 +39,32 @@ deno_core::extension!(
     op_buffer_jsbuffer,
     op_buffer_nofast,
     op_arraybuffer,
+    op_vec_u8,
+    op_vec_u8_generic,
+    op_array_collect,
+    op_array_builder,
+    op_map_object_to_v8,
+    op_map_v8map_to_v8,
+    op_map_object_from_v8,
+    op_map_v8map_from_v8,
+    op_slice_to_v8,
+    op_slice_to_vec_to_v8,
+    op_probe_from_v8,
+    op_probe_try_from_v8,
+    op_vec_from_v8,
+    op_vec_from_v8_into,
+    op_latin1_to_v8,
+    op_utf8_to_v8,
+    op_latin1_from_v8,
+    op_utf8_from_v8,
+    op_nested_vec_from_v8,
+    op_object_builder_to_v8,
+    op_object_internalized_to_v8,
+    op_object_to_v8,
+    op_label_to_v8,
+    op_label_cached_to_v8,
+    op_format_string_to_v8,
+    op_display_string_to_v8,
   ],
   state = |state| {
     state.put(1234u32);
@@ -84,6 +114,22 @@ pub fn op_string_option_u32(#[string] s: &str) -> Option<u32> {
   Some(s.len() as _)
 }
 
+#[op2]
+#[string]
+pub fn op_string_echo(#[string] s: String) -> String {
+  s
+}
+
+/// `#[from_v8]` rejects `Cow<str>` in favour of `#[string]`, so we convert by hand.
+#[op2]
+pub fn op_string_echo_cow<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  s: v8::Local<'s, v8::Value>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  let s = Cow::<str>::from_v8(scope, s)?;
+  Ok(s.to_v8(scope)?)
+}
+
 #[op2(fast)]
 pub fn op_local(_s: v8::Local<v8::String>) {}
 
@@ -142,6 +188,344 @@ pub fn op_buffer_nofast(#[buffer] _buffer: &[u8]) {}
 #[op2(fast)]
 pub fn op_arraybuffer(#[arraybuffer] _buffer: &[u8]) {}
 
+#[op2]
+pub fn op_vec_u8(#[from_v8] _buffer: Vec<u8>) {}
+
+/// Converts element by element, like any other `Vec<T>`.
+#[op2]
+pub fn op_vec_u8_generic(#[from_v8] _buffer: Vec<convert::SafeNumber<u8>>) {}
+
+#[op2]
+#[to_v8]
+pub fn op_array_collect(len: u32) -> Vec<convert::Smi<u32>> {
+  (0..len).map(convert::Smi).collect()
+}
+
+#[op2]
+pub fn op_array_builder<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  len: u32,
+) -> Result<v8::Local<'s, v8::Array>, error::AnyError> {
+  Ok(convert::to_v8_array(scope, (0..len).map(convert::Smi))?)
+}
+
+#[op2]
+#[to_v8]
+pub fn op_map_object_to_v8(len: u32) -> HashMap<u32, convert::Smi<u32>> {
+  (0..len).map(|i| (i, convert::Smi(i))).collect()
+}
+
+#[op2]
+#[to_v8]
+pub fn op_map_v8map_to_v8(
+  len: u32,
+) -> convert::V8Map<convert::Smi<u32>, convert::Smi<u32>> {
+  convert::V8Map(
+    (0..len)
+      .map(|i| (convert::Smi(i), convert::Smi(i)))
+      .collect(),
+  )
+}
+
+#[op2]
+pub fn op_map_object_from_v8(
+  #[from_v8] map: HashMap<u32, convert::Smi<u32>>,
+) -> u32 {
+  map.len() as _
+}
+
+#[op2]
+pub fn op_map_v8map_from_v8(
+  #[from_v8] map: convert::V8Map<convert::Smi<u32>, convert::Smi<u32>>,
+) -> u32 {
+  map.0.len() as _
+}
+
+static SLICE_1000: [convert::Smi<u32>; 1000] = [convert::Smi(1); 1000];
+
+#[op2]
+pub fn op_slice_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  Ok(SLICE_1000.as_slice().to_v8(scope)?)
+}
+
+#[op2]
+pub fn op_slice_to_vec_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  Ok(SLICE_1000.to_vec().to_v8(scope)?)
+}
+
+/// Probes the value for several numeric types, discarding each error.
+#[op2]
+pub fn op_probe_from_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  value: v8::Local<'s, v8::Value>,
+) -> u32 {
+  use convert::*;
+  if let Ok(Smi(v)) = Smi::<u32>::from_v8(scope, value) {
+    return v;
+  }
+  if let Ok(CheckedSmi(v)) = CheckedSmi::<u32>::from_v8(scope, value) {
+    return v;
+  }
+  if let Ok(SafeNumber(v)) = SafeNumber::<u32>::from_v8(scope, value) {
+    return v;
+  }
+  if let Ok(BigInt(v)) = BigInt::<u64>::from_v8(scope, value) {
+    return v as _;
+  }
+  0
+}
+
+/// Probes the value for several numeric types without building any errors.
+#[op2]
+pub fn op_probe_try_from_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  value: v8::Local<'s, v8::Value>,
+) -> u32 {
+  use convert::*;
+  if let Some(Smi(v)) = Smi::<u32>::try_from_v8(scope, value) {
+    return v;
+  }
+  if let Some(CheckedSmi(v)) = CheckedSmi::<u32>::try_from_v8(scope, value) {
+    return v;
+  }
+  if let Some(SafeNumber(v)) = SafeNumber::<u32>::try_from_v8(scope, value) {
+    return v;
+  }
+  if let Some(BigInt(v)) = BigInt::<u64>::try_from_v8(scope, value) {
+    return v as _;
+  }
+  0
+}
+
+#[op2]
+pub fn op_vec_from_v8(#[from_v8] vec: Vec<convert::Smi<u32>>) -> u32 {
+  vec.len() as _
+}
+
+thread_local! {
+  static VEC: RefCell<Vec<convert::Smi<u32>>> = const { RefCell::new(Vec::new()) };
+}
+
+#[op2]
+pub fn op_vec_from_v8_into<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  value: v8::Local<'s, v8::Value>,
+) -> Result<u32, error::AnyError> {
+  VEC.with_borrow_mut(|vec| {
+    convert::from_v8_into(scope, value, vec)?;
+    Ok(vec.len() as _)
+  })
+}
+
+static ASCII_1000: [u8; 1000] = [b'*'; 1000];
+
+#[op2]
+pub fn op_latin1_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  Ok(convert::Latin1String(ASCII_1000.as_slice()).to_v8(scope)?)
+}
+
+#[op2]
+pub fn op_utf8_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  let string = std::str::from_utf8(&ASCII_1000)?;
+  Ok(Cow::Borrowed(string).to_v8(scope)?)
+}
+
+#[op2]
+pub fn op_latin1_from_v8(#[from_v8] s: convert::Latin1String) -> u32 {
+  s.0.len() as _
+}
+
+#[op2]
+pub fn op_utf8_from_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+  s: v8::Local<'s, v8::Value>,
+) -> Result<u32, error::AnyError> {
+  let s = Cow::<str>::from_v8(scope, s)?;
+  Ok(s.len() as _)
+}
+
+#[op2]
+pub fn op_nested_vec_from_v8(#[from_v8] matrix: Vec<Vec<u32>>) -> u32 {
+  matrix.len() as _
+}
+
+const OBJECT_FIELDS: [&str; 8] = [
+  "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+];
+
+static OBJECT_KEYS: convert::ObjectKeys =
+  convert::ObjectKeys::new(&OBJECT_FIELDS);
+
+#[op2]
+pub fn op_object_builder_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  let mut object = convert::ObjectBuilder::new(scope, &OBJECT_KEYS)?;
+  for (i, name) in OBJECT_FIELDS.into_iter().enumerate() {
+    object.field(scope, name, convert::Smi(i as u32))?;
+  }
+  Ok(object.build().into())
+}
+
+#[op2]
+pub fn op_object_internalized_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  let object = v8::Object::new(scope);
+  for (i, name) in OBJECT_FIELDS.into_iter().enumerate() {
+    let key = _ops::field_key(scope, name)?;
+    let value = v8::Integer::new(scope, i as i32);
+    object.create_data_property(scope, key.into(), value.into());
+  }
+  Ok(object.into())
+}
+
+#[op2]
+pub fn op_object_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> v8::Local<'s, v8::Value> {
+  let object = v8::Object::new(scope);
+  for (i, name) in OBJECT_FIELDS.into_iter().enumerate() {
+    let key = v8::String::new(scope, name).unwrap();
+    let value = v8::Integer::new(scope, i as i32);
+    object.create_data_property(scope, key.into(), value.into());
+  }
+  object.into()
+}
+
+#[op2]
+pub fn op_label_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  Ok("connected".to_v8(scope)?)
+}
+
+#[op2]
+pub fn op_label_cached_to_v8<'s>(
+  scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Value>, error::AnyError> {
+  Ok(convert::Cached("connected").to_v8(scope)?)
+}
+
+/// The kind of message a logging op would format.
+pub struct LogLine {
+  level: &'static str,
+  request: u32,
+  elapsed_ms: f64,
+}
+
+impl std::fmt::Display for LogLine {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "[{}] request {} completed in {:.2}ms",
+      self.level, self.request, self.elapsed_ms
+    )
+  }
+}
+
+const LOG_LINE: LogLine = LogLine {
+  level: "info",
+  request: 1234,
+  elapsed_ms: 56.78,
+};
+
+#[op2]
+#[string]
+pub fn op_format_string_to_v8() -> String {
+  LOG_LINE.to_string()
+}
+
+#[op2]
+#[to_v8]
+pub fn op_display_string_to_v8() -> convert::DisplayString<LogLine> {
+  convert::DisplayString(LOG_LINE)
+}
+
+#[cfg(feature = "json")]
+deno_core::extension!(
+  json_testing,
+  ops = [
+    op_json_value_from_v8,
+    op_json_string_from_v8,
+    op_json_value_to_v8,
+    op_json_string_to_v8,
+  ],
+);
+
+/// The same value as `NESTED_OBJECT` in the harness.
+#[cfg(feature = "json")]
+fn nested_json() -> &'static serde_json::Value {
+  static VALUE: std::sync::OnceLock<serde_json::Value> =
+    std::sync::OnceLock::new();
+  VALUE.get_or_init(|| {
+    let users = (0..100)
+      .map(|i| {
+        serde_json::json!({
+          "id": i,
+          "name": format!("user{i}"),
+          "active": i % 2 == 0,
+          "score": i as f64 / 3.0,
+          "tags": ["a", "b"],
+          "address": { "city": "City", "zip": 10000 + i },
+        })
+      })
+      .collect::<Vec<_>>();
+    serde_json::json!({ "users": users })
+  })
+}
+
+#[cfg(feature = "json")]
+#[op2]
+pub fn op_json_value_from_v8(#[from_v8] value: serde_json::Value) -> u32 {
+  value["users"]
+    .as_array()
+    .map_or(0, |users| users.len() as _)
+}
+
+#[cfg(feature = "json")]
+#[op2(fast)]
+pub fn op_json_string_from_v8(#[string] json: &str) -> u32 {
+  let value: serde_json::Value = serde_json::from_str(json).unwrap();
+  value["users"]
+    .as_array()
+    .map_or(0, |users| users.len() as _)
+}
+
+#[cfg(feature = "json")]
+#[op2]
+#[to_v8]
+pub fn op_json_value_to_v8() -> serde_json::Value {
+  nested_json().clone()
+}
+
+#[cfg(feature = "json")]
+#[op2]
+#[string]
+pub fn op_json_string_to_v8() -> String {
+  serde_json::to_string(nested_json()).unwrap()
+}
+
+#[cfg(feature = "smallvec")]
+deno_core::extension!(smallvec_testing, ops = [op_smallvec_from_v8]);
+
+/// Inline for up to 8 elements, so a 64 element array spills to the heap.
+#[cfg(feature = "smallvec")]
+#[op2]
+pub fn op_smallvec_from_v8(
+  #[from_v8] vec: smallvec::SmallVec<[convert::Smi<u32>; 8]>,
+) -> u32 {
+  vec.len() as _
+}
+
 fn bench_op(
   b: &mut Bencher,
   count: usize,
@@ -155,7 +539,13 @@ fn bench_op(
   );
 
   let mut runtime = JsRuntime::new(RuntimeOptions {
-    extensions: vec![testing::init_ops_and_esm()],
+    extensions: vec![
+      testing::init_ops_and_esm(),
+      #[cfg(feature = "json")]
+      json_testing::init_ops_and_esm(),
+      #[cfg(feature = "smallvec")]
+      smallvec_testing::init_ops_and_esm(),
+    ],
     // We need to feature gate this here to prevent IDE errors
     #[cfg(feature = "unsafe_runtime_options")]
     unsafe_expose_natives_and_gc: true,
@@ -348,6 +738,61 @@ fn bench_op_string_option_u32(b: &mut Bencher) {
   bench_op(b, BENCH_COUNT, "op_string_option_u32", 1, "accum += op_string_option_u32('this is a reasonably long string that we would like to get the length of!');");
 }
 
+/// A string function that returns its argument.
+fn bench_op_string_echo(b: &mut Bencher) {
+  bench_op(b, BENCH_COUNT, "op_string_echo", 1, "op_string_echo('this is a reasonably long string that we would like to get the length of!');");
+}
+
+/// A string function that returns its argument.
+fn bench_op_string_echo_large_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_string_echo",
+    1,
+    "op_string_echo(LARGE_STRING_1000000);",
+  );
+}
+
+/// A string function that returns its argument, which is latin1 but not ASCII.
+fn bench_op_string_echo_latin1_large_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_string_echo",
+    1,
+    "op_string_echo(LARGE_STRING_LATIN1_1000000);",
+  );
+}
+
+/// A string function that returns its argument, using `Cow<str>` conversions.
+fn bench_op_string_echo_cow(b: &mut Bencher) {
+  bench_op(b, BENCH_COUNT, "op_string_echo_cow", 1, "op_string_echo_cow('this is a reasonably long string that we would like to get the length of!');");
+}
+
+/// A string function that returns its argument, using `Cow<str>` conversions.
+fn bench_op_string_echo_cow_large_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_string_echo_cow",
+    1,
+    "op_string_echo_cow(LARGE_STRING_1000000);",
+  );
+}
+
+/// A string function that returns its argument, which is latin1 but not ASCII, using
+/// `Cow<str>` conversions.
+fn bench_op_string_echo_cow_latin1_large_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_string_echo_cow",
+    1,
+    "op_string_echo_cow(LARGE_STRING_LATIN1_1000000);",
+  );
+}
+
 /// A fast function that takes a v8::Local<String>
 fn bench_op_v8_local(b: &mut Bencher) {
   bench_op(b, BENCH_COUNT, "op_local", 1, "op_local('this is a reasonably long string that we would like to get the length of!');");
@@ -451,6 +896,452 @@ fn bench_op_arraybuffer(b: &mut Bencher) {
   );
 }
 
+/// A typed array, copied in bulk.
+fn bench_op_vec_u8_large_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_vec_u8",
+    1,
+    "op_vec_u8(LARGE_BUFFER_1000000)",
+  );
+}
+
+/// A plain array, converted element by element.
+fn bench_op_vec_u8_array_large_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_vec_u8",
+    1,
+    "op_vec_u8(LARGE_ARRAY_1000000)",
+  );
+}
+
+/// The same plain array, through the generic `Vec<T>` conversion.
+fn bench_op_vec_u8_generic_large_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_vec_u8_generic",
+    1,
+    "op_vec_u8_generic(LARGE_ARRAY_1000000)",
+  );
+}
+
+/// Collects the handles into a `Vec` and creates the array in one call.
+fn bench_op_array_collect_100(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_array_collect",
+    1,
+    "op_array_collect(100)",
+  );
+}
+
+/// Stores each element directly into a pre-sized array.
+fn bench_op_array_builder_100(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_array_builder",
+    1,
+    "op_array_builder(100)",
+  );
+}
+
+/// Like `bench_op_array_collect_100`, with 10,000 elements.
+fn bench_op_array_collect_10000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_array_collect",
+    1,
+    "op_array_collect(10000)",
+  );
+}
+
+/// Like `bench_op_array_builder_100`, with 10,000 elements.
+fn bench_op_array_builder_10000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_array_builder",
+    1,
+    "op_array_builder(10000)",
+  );
+}
+
+/// Like `bench_op_array_collect_100`, with 100,000 elements.
+fn bench_op_array_collect_100000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_array_collect",
+    1,
+    "op_array_collect(100000)",
+  );
+}
+
+/// Like `bench_op_array_builder_100`, with 100,000 elements.
+fn bench_op_array_builder_100000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_array_builder",
+    1,
+    "op_array_builder(100000)",
+  );
+}
+
+/// Like `bench_op_array_collect_100`, with 1,000,000 elements.
+fn bench_op_array_collect_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_array_collect",
+    1,
+    "op_array_collect(1000000)",
+  );
+}
+
+/// Like `bench_op_array_builder_100`, with 1,000,000 elements.
+fn bench_op_array_builder_1000000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_array_builder",
+    1,
+    "op_array_builder(1000000)",
+  );
+}
+
+/// Returns 10,000 integer keys as object properties.
+fn bench_op_map_object_to_v8_10000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_map_object_to_v8",
+    1,
+    "op_map_object_to_v8(10000)",
+  );
+}
+
+/// Returns 10,000 integer keys as a `Map`.
+fn bench_op_map_v8map_to_v8_10000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_map_v8map_to_v8",
+    1,
+    "op_map_v8map_to_v8(10000)",
+  );
+}
+
+/// Reads 10,000 integer keys from object properties.
+fn bench_op_map_object_from_v8_10000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_map_object_from_v8",
+    1,
+    "accum += op_map_object_from_v8(LARGE_OBJECT_10000)",
+  );
+}
+
+/// Reads 10,000 integer keys from a `Map`.
+fn bench_op_map_v8map_from_v8_10000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_map_v8map_from_v8",
+    1,
+    "accum += op_map_v8map_from_v8(LARGE_MAP_10000)",
+  );
+}
+
+/// Converts a borrowed slice of 1000 elements directly.
+fn bench_op_slice_to_v8_1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_slice_to_v8",
+    0,
+    "op_slice_to_v8()",
+  );
+}
+
+/// Copies a borrowed slice of 1000 elements into a `Vec` before converting it.
+fn bench_op_slice_to_vec_to_v8_1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_slice_to_vec_to_v8",
+    0,
+    "op_slice_to_vec_to_v8()",
+  );
+}
+
+/// Misses every probe, so each one builds and drops an error.
+fn bench_op_probe_from_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_probe_from_v8",
+    1,
+    "op_probe_from_v8('a')",
+  );
+}
+
+/// Misses every probe without building any errors.
+fn bench_op_probe_try_from_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_probe_try_from_v8",
+    1,
+    "op_probe_try_from_v8('a')",
+  );
+}
+
+/// Converts a 1000 element array into a new `Vec` on each call.
+fn bench_op_vec_from_v8_1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_vec_from_v8",
+    1,
+    "accum += op_vec_from_v8(ARRAY_1000)",
+  );
+}
+
+/// Converts a 2 element array into a new `Vec` on each call, to compare with
+/// `bench_op_smallvec_from_v8_2`.
+fn bench_op_vec_from_v8_2(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_vec_from_v8",
+    1,
+    "accum += op_vec_from_v8(ARRAY_2)",
+  );
+}
+
+/// Converts a 64 element array into a new `Vec` on each call, to compare with
+/// `bench_op_smallvec_from_v8_64`.
+fn bench_op_vec_from_v8_64(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_vec_from_v8",
+    1,
+    "accum += op_vec_from_v8(ARRAY_64)",
+  );
+}
+
+/// Converts a 1000 element array into the same `Vec` on each call.
+fn bench_op_vec_from_v8_into_1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_vec_from_v8_into",
+    1,
+    "accum += op_vec_from_v8_into(ARRAY_1000)",
+  );
+}
+
+/// Converts 1000 bytes of ASCII to a one-byte string directly.
+fn bench_op_latin1_to_v8_1000(b: &mut Bencher) {
+  bench_op(b, BENCH_COUNT, "op_latin1_to_v8", 0, "op_latin1_to_v8()");
+}
+
+/// Converts 1000 bytes of ASCII to a string by way of UTF-8.
+fn bench_op_utf8_to_v8_1000(b: &mut Bencher) {
+  bench_op(b, BENCH_COUNT, "op_utf8_to_v8", 0, "op_utf8_to_v8()");
+}
+
+/// Reads a 1000 character ASCII string as latin1 bytes.
+fn bench_op_latin1_from_v8_1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_latin1_from_v8",
+    1,
+    "accum += op_latin1_from_v8(LARGE_STRING_1000)",
+  );
+}
+
+/// Reads a 1000 character ASCII string as UTF-8.
+fn bench_op_utf8_from_v8_1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_utf8_from_v8",
+    1,
+    "accum += op_utf8_from_v8(LARGE_STRING_1000)",
+  );
+}
+
+/// Converts a 1000x1000 matrix of plain arrays.
+fn bench_op_nested_vec_from_v8_1000x1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_nested_vec_from_v8",
+    1,
+    "accum += op_nested_vec_from_v8(LARGE_MATRIX_1000)",
+  );
+}
+
+/// Converts a 1000x1000 matrix whose rows are typed arrays.
+fn bench_op_nested_vec_typed_from_v8_1000x1000(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_nested_vec_from_v8",
+    1,
+    "accum += op_nested_vec_from_v8(LARGE_TYPED_MATRIX_1000)",
+  );
+}
+
+/// Builds an object with 8 fields, using keys cached by `ObjectBuilder`.
+fn bench_op_object_builder_to_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_object_builder_to_v8",
+    0,
+    "op_object_builder_to_v8()",
+  );
+}
+
+/// Builds an object with 8 fields, looking up each internalized key per object.
+fn bench_op_object_internalized_to_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_object_internalized_to_v8",
+    0,
+    "op_object_internalized_to_v8()",
+  );
+}
+
+/// Builds an object with 8 fields, creating a new key string for each one.
+fn bench_op_object_to_v8(b: &mut Bencher) {
+  bench_op(b, BENCH_COUNT, "op_object_to_v8", 0, "op_object_to_v8()");
+}
+
+/// Formats a log line into a new `String`, which is then converted.
+fn bench_op_format_string_to_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_format_string_to_v8",
+    0,
+    "op_format_string_to_v8()",
+  );
+}
+
+/// Formats a log line into the reused buffer of `DisplayString`.
+fn bench_op_display_string_to_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_display_string_to_v8",
+    0,
+    "op_display_string_to_v8()",
+  );
+}
+
+/// Returns the same short label, as a new string each time.
+fn bench_op_label_to_v8(b: &mut Bencher) {
+  bench_op(b, BENCH_COUNT, "op_label_to_v8", 0, "op_label_to_v8()");
+}
+
+/// Returns the same short label, reusing the string cached by `Cached`.
+fn bench_op_label_cached_to_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_label_cached_to_v8",
+    0,
+    "op_label_cached_to_v8()",
+  );
+}
+
+/// Converts a nested object directly to a `serde_json::Value`.
+#[cfg(feature = "json")]
+fn bench_op_json_value_from_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_json_value_from_v8",
+    1,
+    "accum += op_json_value_from_v8(NESTED_OBJECT)",
+  );
+}
+
+/// Converts a nested object to a `serde_json::Value` through `JSON.stringify`.
+#[cfg(feature = "json")]
+fn bench_op_json_string_from_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_json_string_from_v8",
+    1,
+    "accum += op_json_string_from_v8(JSON.stringify(NESTED_OBJECT))",
+  );
+}
+
+/// Converts a nested `serde_json::Value` directly to an object.
+#[cfg(feature = "json")]
+fn bench_op_json_value_to_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_json_value_to_v8",
+    0,
+    "accum += op_json_value_to_v8().users.length",
+  );
+}
+
+/// Converts a nested `serde_json::Value` to an object through `JSON.parse`.
+#[cfg(feature = "json")]
+fn bench_op_json_string_to_v8(b: &mut Bencher) {
+  bench_op(
+    b,
+    LARGE_BENCH_COUNT,
+    "op_json_string_to_v8",
+    0,
+    "accum += JSON.parse(op_json_string_to_v8()).users.length",
+  );
+}
+
+/// Converts a 2 element array into a `SmallVec` without a heap allocation.
+#[cfg(feature = "smallvec")]
+fn bench_op_smallvec_from_v8_2(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_smallvec_from_v8",
+    1,
+    "accum += op_smallvec_from_v8(ARRAY_2)",
+  );
+}
+
+/// Converts a 64 element array into a `SmallVec` that spills to the heap.
+#[cfg(feature = "smallvec")]
+fn bench_op_smallvec_from_v8_64(b: &mut Bencher) {
+  bench_op(
+    b,
+    BENCH_COUNT,
+    "op_smallvec_from_v8",
+    1,
+    "accum += op_smallvec_from_v8(ARRAY_64)",
+  );
+}
+
 benchmark_group!(
   benches,
   baseline,
@@ -471,6 +1362,12 @@ benchmark_group!(
   bench_op_string_large_utf8_1000,
   bench_op_string_large_utf8_1000000,
   bench_op_string_option_u32,
+  bench_op_string_echo,
+  bench_op_string_echo_large_1000000,
+  bench_op_string_echo_latin1_large_1000000,
+  bench_op_string_echo_cow,
+  bench_op_string_echo_cow_large_1000000,
+  bench_op_string_echo_cow_latin1_large_1000000,
   bench_op_v8_local,
   bench_op_v8_local_scope,
   bench_op_v8_local_nofast,
@@ -486,6 +1383,65 @@ benchmark_group!(
   bench_op_buffer_jsbuffer,
   bench_op_buffer_nofast,
   bench_op_arraybuffer,
+  bench_op_vec_u8_large_1000000,
+  bench_op_vec_u8_array_large_1000000,
+  bench_op_vec_u8_generic_large_1000000,
+  bench_op_array_collect_100,
+  bench_op_array_builder_100,
+  bench_op_array_collect_10000,
+  bench_op_array_builder_10000,
+  bench_op_array_collect_100000,
+  bench_op_array_builder_100000,
+  bench_op_array_collect_1000000,
+  bench_op_array_builder_1000000,
+  bench_op_map_object_to_v8_10000,
+  bench_op_map_v8map_to_v8_10000,
+  bench_op_map_object_from_v8_10000,
+  bench_op_map_v8map_from_v8_10000,
+  bench_op_slice_to_v8_1000,
+  bench_op_slice_to_vec_to_v8_1000,
+  bench_op_probe_from_v8,
+  bench_op_probe_try_from_v8,
+  bench_op_vec_from_v8_2,
+  bench_op_vec_from_v8_64,
+  bench_op_vec_from_v8_1000,
+  bench_op_vec_from_v8_into_1000,
+  bench_op_latin1_to_v8_1000,
+  bench_op_utf8_to_v8_1000,
+  bench_op_latin1_from_v8_1000,
+  bench_op_utf8_from_v8_1000,
+  bench_op_nested_vec_from_v8_1000x1000,
+  bench_op_nested_vec_typed_from_v8_1000x1000,
+  bench_op_object_builder_to_v8,
+  bench_op_object_internalized_to_v8,
+  bench_op_object_to_v8,
+  bench_op_label_to_v8,
+  bench_op_label_cached_to_v8,
+  bench_op_format_string_to_v8,
+  bench_op_display_string_to_v8,
+);
+
+#[cfg(feature = "json")]
+benchmark_group!(
+  json_benches,
+  bench_op_json_value_from_v8,
+  bench_op_json_string_from_v8,
+  bench_op_json_value_to_v8,
+  bench_op_json_string_to_v8,
+);
+
+#[cfg(feature = "smallvec")]
+benchmark_group!(
+  smallvec_benches,
+  bench_op_smallvec_from_v8_2,
+  bench_op_smallvec_from_v8_64,
 );
 
+#[cfg(not(any(feature = "json", feature = "smallvec")))]
 benchmark_main!(benches);
+#[cfg(all(feature = "json", not(feature = "smallvec")))]
+benchmark_main!(benches, json_benches);
+#[cfg(all(feature = "smallvec", not(feature = "json")))]
+benchmark_main!(benches, smallvec_benches);
+#[cfg(all(feature = "json", feature = "smallvec"))]
+benchmark_main!(benches, json_benches, smallvec_benches);
@@ -4,10 +4,37 @@
 // This harness is dynamically generated for each individual bench run.
 const LARGE_STRING_1000000 = "*".repeat(1000000);
 const LARGE_STRING_1000 = "*".repeat(1000);
+const LARGE_STRING_LATIN1_1000000 = "\u00e9".repeat(1000000);
 const LARGE_STRING_UTF8_1000000 = "\u1000".repeat(1000000);
 const LARGE_STRING_UTF8_1000 = "\u1000".repeat(1000);
 const BUFFER = new Uint8Array(1024);
 const ARRAYBUFFER = new ArrayBuffer(1024);
+const LARGE_BUFFER_1000000 = new Uint8Array(1000000);
+const LARGE_ARRAY_1000000 = Array.from(LARGE_BUFFER_1000000);
+const ARRAY_2 = [0, 1];
+const ARRAY_64 = Array.from({ length: 64 }, (_, i) => i);
+const ARRAY_1000 = Array.from({ length: 1000 }, (_, i) => i);
+const LARGE_MATRIX_1000 = Array.from(
+  { length: 1000 },
+  () => Array.from({ length: 1000 }, (_, i) => i),
+);
+const LARGE_TYPED_MATRIX_1000 = LARGE_MATRIX_1000.map((row) =>
+  new Uint32Array(row)
+);
+const LARGE_OBJECT_10000 = Object.fromEntries(
+  Array.from({ length: 10000 }, (_, i) => [i, i]),
+);
+const LARGE_MAP_10000 = new Map(Array.from({ length: 10000 }, (_, i) => [i, i]));
+const NESTED_OBJECT = {
+  users: Array.from({ length: 100 }, (_, i) => ({
+    id: i,
+    name: `user${i}`,
+    active: i % 2 == 0,
+    score: i / 3,
+    tags: ["a", "b"],
+    address: { city: "City", zip: 10000 + i },
+  })),
+};
 const { __OP__: op } = Deno.core.ops;
 const { op_make_external } = Deno.core.ops;
 const EXTERNAL = op_make_external();
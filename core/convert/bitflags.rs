@@ -0,0 +1,117 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for [`bitflags`] flag sets, behind the `bitflags` feature.
+
+use super::ConvertError;
+use super::FromV8;
+use super::SafeInteger;
+use super::SafeNumber;
+use super::ToV8;
+use crate::error::StdAnyError;
+use bitflags::Flags;
+use std::fmt::LowerHex;
+
+/// A set of flags generated by the `bitflags!` macro, converted to and from its bits
+/// as a JS `number`, like the flag sets of OS APIs such as `open(2)`.
+///
+/// Converting from JS reads the number like a [`SafeNumber`] of the flags' bits type,
+/// and errors if any bit is set that isn't part of a defined flag. Use
+/// [`TruncatedBitFlags`] to ignore unknown bits instead:
+///
+/// ```ignore
+/// bitflags! {
+///   struct Mode: u32 {
+///     const READ = 1;
+///     const WRITE = 1 << 1;
+///   }
+/// }
+///
+/// #[op2]
+/// fn op_open(#[string] path: &str, #[from_v8] BitFlags(mode): BitFlags<Mode>) {
+///   // ...
+/// }
+/// ```
+///
+/// Converting to JS gives the bits, including any unknown ones that the value holds,
+/// and errors only if they are outside of the safe integer range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BitFlags<T>(pub T);
+
+/// Like [`BitFlags`], but unknown bits are cleared when converting from JS rather than
+/// rejected, like `Flags::from_bits_truncate`. This suits flags where JS may pass bits
+/// from a newer version of an API that are safe to ignore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TruncatedBitFlags<T>(pub T);
+
+fn flags_to_v8<'a, T>(
+  scope: &mut v8::HandleScope<'a>,
+  flags: T,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError>
+where
+  T: Flags,
+  T::Bits: SafeInteger,
+{
+  SafeNumber(flags.bits()).to_v8(scope)
+}
+
+impl<'a, T> ToV8<'a> for BitFlags<T>
+where
+  T: Flags,
+  T::Bits: SafeInteger,
+{
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    flags_to_v8(scope, self.0)
+  }
+}
+
+impl<'a, T> FromV8<'a> for BitFlags<T>
+where
+  T: Flags,
+  T::Bits: SafeInteger + LowerHex,
+{
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let SafeNumber(bits) = SafeNumber::<T::Bits>::from_v8(scope, value)?;
+    T::from_bits(bits).map(BitFlags).ok_or_else(|| {
+      let unknown = bits & !T::all().bits();
+      ConvertError::invalid(format!("Unknown flag bits {unknown:#x} are set"))
+        .into()
+    })
+  }
+}
+
+impl<'a, T> ToV8<'a> for TruncatedBitFlags<T>
+where
+  T: Flags,
+  T::Bits: SafeInteger,
+{
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    flags_to_v8(scope, self.0)
+  }
+}
+
+impl<'a, T> FromV8<'a> for TruncatedBitFlags<T>
+where
+  T: Flags,
+  T::Bits: SafeInteger,
+{
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let SafeNumber(bits) = SafeNumber::<T::Bits>::from_v8(scope, value)?;
+    Ok(TruncatedBitFlags(T::from_bits_truncate(bits)))
+  }
+}
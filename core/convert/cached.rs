@@ -0,0 +1,86 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Reusing the converted values of constants, cached per isolate.
+
+use super::trace;
+use super::ToV8;
+use std::any::Any;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Converts the wrapped value once per isolate, and returns the same V8 value every
+/// time an equal value is converted after that.
+///
+/// This is meant for ops that return one of a small set of constants, like the string
+/// label of an enum, where creating a new `v8::String` for every call only adds
+/// allocations and GC pressure:
+///
+/// ```ignore
+/// #[op2]
+/// fn op_state(#[state] state: &State) -> Cached<&'static str> {
+///   Cached(if state.open { "open" } else { "closed" })
+/// }
+/// ```
+///
+/// The values are kept alive for the lifetime of the isolate, since nothing is ever
+/// evicted, so only wrap values from a small, fixed set. Values that convert to objects
+/// aren't cached, because JS could modify an object that every call shares; they are
+/// converted again each time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Cached<T>(pub T);
+
+impl<'a, T> ToV8<'a> for Cached<T>
+where
+  T: ToV8<'a> + Clone + Eq + Hash + 'static,
+{
+  type Error = T::Error;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let cached = scope
+      .get_slot::<CachedValues>()
+      .and_then(|cache| cache.values::<T>())
+      .and_then(|values| values.get(&self.0))
+      .cloned();
+    if let Some(value) = cached {
+      return Ok(v8::Local::new(scope, value));
+    }
+    let value = trace::to_v8(scope, self.0.clone())?;
+    if !value.is_object() {
+      let global = v8::Global::new(scope, value);
+      if scope.get_slot::<CachedValues>().is_none() {
+        scope.set_slot(CachedValues::default());
+      }
+      let cache = scope.get_slot_mut::<CachedValues>().unwrap();
+      cache.values_mut::<T>().insert(self.0, global);
+    }
+    Ok(value)
+  }
+}
+
+/// The values converted by [`Cached`] in an isolate, for each wrapped type, stored in an
+/// isolate slot.
+///
+/// Like the [`ObjectKeyCache`](super::ObjectKeyCache), these are global handles, so the
+/// runtime removes the slot before it is torn down or snapshotted.
+#[derive(Default)]
+pub(crate) struct CachedValues(HashMap<TypeId, Box<dyn Any>>);
+
+type Values<T> = HashMap<T, v8::Global<v8::Value>>;
+
+impl CachedValues {
+  fn values<T: 'static>(&self) -> Option<&Values<T>> {
+    self.0.get(&TypeId::of::<T>())?.downcast_ref()
+  }
+
+  fn values_mut<T: Eq + Hash + 'static>(&mut self) -> &mut Values<T> {
+    self
+      .0
+      .entry(TypeId::of::<T>())
+      .or_insert_with(|| Box::<Values<T>>::default())
+      .downcast_mut()
+      .unwrap()
+  }
+}
@@ -0,0 +1,81 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Delivering the queued items of a channel to JS in batches.
+
+use super::iter_to_v8;
+use super::ToV8;
+use crate::error::StdAnyError;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+
+/// A batch of items taken from an [`mpsc::Receiver`] without waiting, converted to a
+/// JS array of the items in the order they were sent.
+///
+/// This lets an op deliver every item that is already queued in a single call, rather
+/// than JS calling an op once per item, which matters for event-queue style extensions
+/// that produce many small items:
+///
+/// ```ignore
+/// #[op2]
+/// #[to_v8]
+/// fn op_next_events(#[state] events: &mut EventQueue) -> Drained<Event> {
+///   Drained::from_receiver(&mut events.receiver, 64)
+/// }
+/// ```
+///
+/// An empty channel converts to an empty array. Once every sender is dropped, the
+/// remaining items are still delivered, and the first batch taken after the last of
+/// them converts to `null` instead, so JS can tell that no more items will arrive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drained<T> {
+  items: Vec<T>,
+  closed: bool,
+}
+
+impl<T> Drained<T> {
+  /// Takes up to `max` items that are already queued in `receiver`, without waiting for
+  /// more to be sent.
+  pub fn from_receiver(receiver: &mut mpsc::Receiver<T>, max: usize) -> Self {
+    let mut items = Vec::with_capacity(max.min(receiver.len()));
+    while items.len() < max {
+      match receiver.try_recv() {
+        Ok(item) => items.push(item),
+        Err(TryRecvError::Empty) => break,
+        Err(TryRecvError::Disconnected) => {
+          return Self {
+            items,
+            closed: true,
+          }
+        }
+      }
+    }
+    Self {
+      items,
+      closed: false,
+    }
+  }
+
+  /// Returns true if the channel is closed and this batch has no items, ie: it is
+  /// converted to `null`.
+  pub fn is_closed(&self) -> bool {
+    self.closed && self.items.is_empty()
+  }
+
+  /// Returns the items of this batch, in the order they were sent.
+  pub fn into_items(self) -> Vec<T> {
+    self.items
+  }
+}
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for Drained<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    if self.is_closed() {
+      return Ok(v8::null(scope).into());
+    }
+    Ok(iter_to_v8(scope, self.items.into_iter())?.into())
+  }
+}
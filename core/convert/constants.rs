@@ -0,0 +1,79 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Installing fieldless enums in JS as objects of named constants.
+
+use super::field_key;
+use crate::error::StdAnyError;
+
+/// A fieldless enum with a JS object of named constants for its variants, like the
+/// object TypeScript generates for a numeric `enum`.
+///
+/// This is implemented by `#[derive(ToV8)]` for enums marked
+/// `#[v8(discriminant, constants)]`, so that JS can refer to the values the enum
+/// converts to by name rather than by number:
+///
+/// ```ignore
+/// #[derive(ToV8, FromV8)]
+/// #[v8(discriminant, constants)]
+/// enum Level {
+///   Debug = 10,
+///   Info = 20,
+/// }
+///
+/// // In an extension's setup, or wherever `globalThis` is populated
+/// Level::install_constants(scope, target)?;
+/// ```
+///
+/// After which `Level.Info === 20` in JS, and, like TypeScript's reverse mapping,
+/// `Level[20] === 'Info'`.
+pub trait EnumConstants {
+  /// The name of the object, which is the name of the enum.
+  const NAME: &'static str;
+  /// The JS name and discriminant of each variant, in declaration order.
+  const VARIANTS: &'static [(&'static str, i32)];
+
+  /// Creates the constants object and defines it as the property [`NAME`](Self::NAME)
+  /// of `target`, returning the object.
+  ///
+  /// The object maps each variant's name to its discriminant and each discriminant
+  /// back to the name, and is frozen, so JS can't change the constants. The property
+  /// on `target` is an ordinary writable one.
+  fn install_constants<'a>(
+    scope: &mut v8::HandleScope<'a>,
+    target: v8::Local<'a, v8::Object>,
+  ) -> Result<v8::Local<'a, v8::Object>, StdAnyError> {
+    let object = v8::Object::new(scope);
+    for (name, discriminant) in Self::VARIANTS {
+      let name = field_key(scope, name)?;
+      let value = v8::Integer::new(scope, *discriminant);
+      object.create_data_property(scope, name.into(), value.into());
+      // The reverse mapping, keyed by the discriminant as a string like any property
+      let key = v8::String::new(scope, &discriminant.to_string()).unwrap();
+      object.create_data_property(scope, key.into(), name.into());
+    }
+    if object.set_integrity_level(scope, v8::IntegrityLevel::Frozen)
+      != Some(true)
+    {
+      return Err(
+        crate::error::type_error(format!(
+          "Failed to freeze the constants of {}",
+          Self::NAME
+        ))
+        .into(),
+      );
+    }
+    let key = field_key(scope, Self::NAME)?;
+    if target.create_data_property(scope, key.into(), object.into())
+      != Some(true)
+    {
+      return Err(
+        crate::error::type_error(format!(
+          "Failed to install the constants of {}",
+          Self::NAME
+        ))
+        .into(),
+      );
+    }
+    Ok(object)
+  }
+}
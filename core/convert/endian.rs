@@ -0,0 +1,94 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Integers converted to and from their bytes in a fixed byte order.
+
+use super::ConvertError;
+use super::FromV8;
+use super::ToV8;
+use crate::error::StdAnyError;
+
+/// Marks an integer as being converted to and from a `Uint8Array` of its bytes in
+/// big-endian (network) byte order, such as for a field of a binary protocol. For
+/// example, `Be(1u32)` converts to `Uint8Array [0, 0, 0, 1]`.
+///
+/// Converting from JS accepts the same values as `Vec<u8>`, including any
+/// `ArrayBufferView` and plain arrays of bytes, and fails unless there are exactly as
+/// many bytes as the integer has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Be<T>(pub T);
+
+/// Like [`Be`], but in little-endian byte order, so `Le(1u32)` converts to
+/// `Uint8Array [1, 0, 0, 0]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Le<T>(pub T);
+
+fn read_bytes<'a, const N: usize>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+  name: &str,
+) -> Result<[u8; N], StdAnyError> {
+  let bytes = Vec::<u8>::from_v8(scope, value)?;
+  bytes.as_slice().try_into().map_err(|_| {
+    ConvertError::invalid(format!(
+      "Expected {N} bytes for {name}, got {}",
+      bytes.len()
+    ))
+    .into()
+  })
+}
+
+macro_rules! impl_endian {
+  ($($t:ty),*) => {
+    $(
+      impl<'a> ToV8<'a> for Be<$t> {
+        type Error = StdAnyError;
+        #[inline]
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          self.0.to_be_bytes().as_slice().to_v8(scope)
+        }
+      }
+
+      impl<'a> FromV8<'a> for Be<$t> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let name = concat!("a big-endian ", stringify!($t));
+          read_bytes(scope, value, name).map(|bytes| Be(<$t>::from_be_bytes(bytes)))
+        }
+      }
+
+      impl<'a> ToV8<'a> for Le<$t> {
+        type Error = StdAnyError;
+        #[inline]
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          self.0.to_le_bytes().as_slice().to_v8(scope)
+        }
+      }
+
+      impl<'a> FromV8<'a> for Le<$t> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let name = concat!("a little-endian ", stringify!($t));
+          read_bytes(scope, value, name).map(|bytes| Le(<$t>::from_le_bytes(bytes)))
+        }
+      }
+    )*
+  };
+}
+
+impl_endian!(u16, u32, u64, u128, i16, i32, i64, i128);
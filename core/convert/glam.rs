@@ -0,0 +1,121 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for the `f32` vector, quaternion and matrix types of [`glam`], behind
+//! the `glam` feature.
+//!
+//! Each type converts to a `Float32Array` of its components, which JS graphics code
+//! can pass straight to WebGL or WebGPU, and converts back from one with a single bulk
+//! copy rather than a conversion per component. Vectors are ordered `x, y, z, w`, and
+//! quaternions too, with `w` last. Matrices are column-major, which is `glam`'s own
+//! layout and the one WebGL, WebGPU and gl-matrix expect, so the translation of a `Mat4`
+//! is at indices 12 to 14.
+
+use super::check_detached;
+use super::ConvertError;
+use super::FromV8;
+use super::Number;
+use super::ToV8;
+use crate::error::StdAnyError;
+use glam::Mat2;
+use glam::Mat3;
+use glam::Mat4;
+use glam::Quat;
+use glam::Vec2;
+use glam::Vec3;
+use glam::Vec4;
+
+/// Creates a `Float32Array` holding `values`.
+fn f32s_to_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  values: &[f32],
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let bytes = values
+    .iter()
+    .flat_map(|v| v.to_ne_bytes())
+    .collect::<Vec<u8>>();
+  let backing_store =
+    v8::ArrayBuffer::new_backing_store_from_bytes(bytes).make_shared();
+  let buffer = v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+  v8::Float32Array::new(scope, buffer, 0, values.len())
+    .map(Into::into)
+    .ok_or_else(|| {
+      crate::error::type_error("Failed to allocate Float32Array").into()
+    })
+}
+
+/// Reads exactly `N` components from a `Float32Array`, copying its bytes in bulk, or
+/// from a plain array of numbers, which are narrowed like [`Number<f32>`].
+fn f32s_from_v8<'a, const N: usize>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+  name: &str,
+) -> Result<[f32; N], StdAnyError> {
+  if value.is_array() {
+    return <[Number<f32>; N]>::from_v8(scope, value)
+      .map(|values| values.map(|Number(v)| v));
+  }
+  let Ok(array) = v8::Local::<v8::Float32Array>::try_from(value) else {
+    return Err(
+      ConvertError::wrong_type("Float32Array or array", &value).into(),
+    );
+  };
+  check_detached(scope, array.into())?;
+  if array.length() != N {
+    return Err(
+      ConvertError::invalid(format!(
+        "Expected Float32Array of length {N} for a {name}, got {}",
+        array.length()
+      ))
+      .into(),
+    );
+  }
+  let mut out = [0f32; N];
+  // SAFETY: Any bit pattern is a valid `f32`, and the slice covers exactly the bytes of
+  // `out`, so copying into it can't leave an invalid value or write out of bounds.
+  let bytes = unsafe {
+    std::slice::from_raw_parts_mut(
+      out.as_mut_ptr() as *mut u8,
+      std::mem::size_of_val(&out),
+    )
+  };
+  array.copy_contents(bytes);
+  Ok(out)
+}
+
+macro_rules! impl_glam {
+  ($($ty:ident: $len:literal, $to:expr, $from:expr;)*) => {
+    $(
+      impl<'a> ToV8<'a> for $ty {
+        type Error = StdAnyError;
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          let to: fn($ty) -> [f32; $len] = $to;
+          f32s_to_v8(scope, &to(self))
+        }
+      }
+
+      impl<'a> FromV8<'a> for $ty {
+        type Error = StdAnyError;
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let from: fn([f32; $len]) -> $ty = $from;
+          f32s_from_v8::<$len>(scope, value, stringify!($ty)).map(from)
+        }
+      }
+    )*
+  };
+}
+
+impl_glam! {
+  Vec2: 2, |v| v.to_array(), Vec2::from_array;
+  Vec3: 3, |v| v.to_array(), Vec3::from_array;
+  Vec4: 4, |v| v.to_array(), Vec4::from_array;
+  Quat: 4, |q| q.to_array(), Quat::from_array;
+  Mat2: 4, |m| m.to_cols_array(), |a| Mat2::from_cols_array(&a);
+  Mat3: 9, |m| m.to_cols_array(), |a| Mat3::from_cols_array(&a);
+  Mat4: 16, |m| m.to_cols_array(), |a| Mat4::from_cols_array(&a);
+}
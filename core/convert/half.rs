@@ -0,0 +1,90 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for [`f16`](half::f16), behind the `half` feature.
+
+use super::typed_vec_from_v8;
+use super::FromV8;
+use super::Number;
+use super::Numeric;
+use super::ToV8;
+use super::TypedArrayElement;
+use super::TypedArrayPrimitive;
+use crate::error::StdAnyError;
+use crate::runtime::ops;
+use half::f16;
+
+/// Every `f16` converts to a JS `number` exactly. Converting from JS rounds to the
+/// nearest `f16`, with ties to even, so magnitudes too small for the smallest subnormal
+/// become a signed zero and magnitudes of 65520 or more become a signed infinity. `NaN`
+/// stays `NaN`.
+impl Numeric for f16 {
+  const NAME: &'static str = "f16";
+  #[inline(always)]
+  fn from_value(value: &v8::Value) -> Option<Self> {
+    ops::to_f64_option(value).map(f16::from_f64)
+  }
+
+  #[inline(always)]
+  fn as_f64(self) -> f64 {
+    self.to_f64()
+  }
+}
+
+impl TypedArrayPrimitive for f16 {}
+
+impl TypedArrayElement for f16 {
+  const NAME: &'static str = "Uint16Array";
+  #[inline(always)]
+  fn is_typed_array(value: &v8::Value) -> bool {
+    value.is_uint16_array()
+  }
+  fn widen(_view: v8::Local<v8::ArrayBufferView>) -> Option<Vec<Self>> {
+    None
+  }
+  #[inline(always)]
+  fn from_element<'a>(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, StdAnyError> {
+    Number::<f16>::from_v8(scope, value).map(|Number(v)| v)
+  }
+}
+
+/// Converts to a `Uint16Array` holding the bits of each element, in native byte order,
+/// for bulk transfer of half-precision data such as tensors. JS can read the values
+/// through a `Float16Array` over the same buffer, where one is available.
+impl<'a> ToV8<'a> for Vec<f16> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let len = self.len();
+    let bytes = self
+      .into_iter()
+      .flat_map(|v| v.to_bits().to_ne_bytes())
+      .collect::<Vec<u8>>();
+    let backing_store =
+      v8::ArrayBuffer::new_backing_store_from_bytes(bytes).make_shared();
+    let buffer = v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+    v8::Uint16Array::new(scope, buffer, 0, len)
+      .map(Into::into)
+      .ok_or_else(|| {
+        crate::error::type_error("Failed to allocate Uint16Array").into()
+      })
+  }
+}
+
+/// Converts from a `Uint16Array` holding the bits of each element, in native byte order,
+/// which is copied out of its buffer in bulk, or from a plain array of numbers, which
+/// are narrowed like [`Number<f16>`]. Converting a view of a detached `ArrayBuffer`
+/// fails, and any other typed array is rejected.
+impl<'a> FromV8<'a> for Vec<f16> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    typed_vec_from_v8(scope, value)
+  }
+}
@@ -0,0 +1,219 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for [`serde_json::Value`], behind the `json` feature.
+
+use super::element_error;
+use super::get_element;
+use super::key_error;
+use super::trace;
+use super::ConvertError;
+use super::FromV8;
+use super::ToV8;
+use super::MAX_RESERVED_ELEMENTS;
+use super::MAX_SAFE_INTEGER;
+use crate::error::StdAnyError;
+use serde_json::Map;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Converts directly to the equivalent JS value, without going through a JSON string:
+/// objects become plain objects with their keys in order, arrays become arrays, and so
+/// on.
+///
+/// Numbers that fit in an `i32` become smis, and every other number becomes the
+/// nearest `f64`, so integers beyond `Number.MAX_SAFE_INTEGER` lose precision in the
+/// same way they do with `JSON.parse`.
+impl<'a> ToV8<'a> for Value {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(match self {
+      Value::Null => v8::null(scope).into(),
+      Value::Bool(b) => v8::Boolean::new(scope, b).into(),
+      Value::Number(n) => {
+        match n.as_i64().and_then(|n| i32::try_from(n).ok()) {
+          Some(n) => v8::Integer::new(scope, n).into(),
+          None => v8::Number::new(scope, n.as_f64().unwrap_or(f64::NAN)).into(),
+        }
+      }
+      Value::String(s) => Cow::<str>::Owned(s).to_v8(scope)?,
+      Value::Array(values) => {
+        let mut elements = Vec::with_capacity(values.len());
+        for (i, value) in values.into_iter().enumerate() {
+          elements
+            .push(trace::to_v8(scope, value).map_err(|e| element_error(i, e))?);
+        }
+        v8::Array::new_with_elements(scope, &elements).into()
+      }
+      Value::Object(map) => {
+        let object = v8::Object::new(scope);
+        for (key, value) in map {
+          let value =
+            trace::to_v8(scope, value).map_err(|e| key_error(&key, e))?;
+          let key = v8::String::new(scope, &key).ok_or_else(|| {
+            ConvertError::out_of_range("String is too long to convert")
+          })?;
+          // Define own properties, so that keys like `__proto__` don't invoke setters
+          object.create_data_property(scope, key.into(), value);
+        }
+        object.into()
+      }
+    })
+  }
+}
+
+/// Converts directly from a JS value, with the same results as `JSON.stringify` followed
+/// by parsing the string, for the values that it supports:
+///
+/// - `null` and `undefined` become `null`, except that object properties that are
+///   `undefined` are left out.
+/// - Integers within the safe integer range become integer numbers, and other numbers
+///   become floats. `NaN` and infinities become `null`.
+/// - Arrays become arrays, with holes as `null`.
+/// - Any other object becomes an object of its own enumerable string-keyed properties.
+///   Unlike `JSON.stringify`, `toJSON` methods are not called.
+///
+/// `BigInt`s, symbols and functions are rejected rather than dropped, and so is an
+/// object or array that contains itself, directly or not, where `JSON.stringify` would
+/// throw. An object that appears more than once without containing itself, as in
+/// `[x, x]`, is converted each time.
+impl<'a> FromV8<'a> for Value {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    value_from_v8(scope, value, &mut Vec::new())
+  }
+}
+
+/// Converts a value nested in the objects and arrays of `ancestors`, which are being
+/// converted around it.
+fn nested_from_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+  ancestors: &mut Vec<v8::Local<'a, v8::Object>>,
+) -> Result<Value, StdAnyError> {
+  trace::from_v8_with::<Value, _>(|| value_from_v8(scope, value, ancestors))
+}
+
+fn value_from_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+  ancestors: &mut Vec<v8::Local<'a, v8::Object>>,
+) -> Result<Value, StdAnyError> {
+  if value.is_null_or_undefined() {
+    return Ok(Value::Null);
+  }
+  if value.is_boolean() {
+    return Ok(Value::Bool(value.is_true()));
+  }
+  if let Ok(n) = v8::Local::<v8::Number>::try_from(value) {
+    let n = n.value();
+    if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER as f64 {
+      return Ok(Value::from(n as i64));
+    }
+    // `NaN` and infinities become `null`
+    return Ok(Value::from(n));
+  }
+  if value.is_string() {
+    return Cow::<str>::from_v8(scope, value)
+      .map(|s| Value::String(s.into_owned()));
+  }
+  let got = if value.is_big_int() {
+    "bigint"
+  } else if value.is_symbol() {
+    "symbol"
+  } else if value.is_function() {
+    "function"
+  } else if let Ok(array) = v8::Local::<v8::Array>::try_from(value) {
+    enter(ancestors, array.into())?;
+    let values = array_from_v8(scope, array, ancestors)?;
+    ancestors.pop();
+    return Ok(Value::Array(values));
+  } else if let Ok(object) = v8::Local::<v8::Object>::try_from(value) {
+    enter(ancestors, object)?;
+    let map = object_from_v8(scope, object, ancestors)?;
+    ancestors.pop();
+    return Ok(Value::Object(map));
+  } else {
+    "unknown"
+  };
+  Err(
+    ConvertError::ExpectedType {
+      expected: "JSON value".into(),
+      got: Some(got.into()),
+    }
+    .into(),
+  )
+}
+
+/// Adds `object` to `ancestors` before its contents are converted, failing if it is
+/// already one of them, since that would convert it forever.
+fn enter<'a>(
+  ancestors: &mut Vec<v8::Local<'a, v8::Object>>,
+  object: v8::Local<'a, v8::Object>,
+) -> Result<(), StdAnyError> {
+  if ancestors
+    .iter()
+    .any(|ancestor| ancestor.strict_equals(object.into()))
+  {
+    return Err(
+      ConvertError::invalid("Circular reference to an enclosing value").into(),
+    );
+  }
+  ancestors.push(object);
+  Ok(())
+}
+
+fn array_from_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  array: v8::Local<'a, v8::Array>,
+  ancestors: &mut Vec<v8::Local<'a, v8::Object>>,
+) -> Result<Vec<Value>, StdAnyError> {
+  let len = array.length() as usize;
+  let mut values = Vec::with_capacity(len.min(MAX_RESERVED_ELEMENTS));
+  for i in 0..len {
+    let value = get_element(scope, array, "Array", i)?;
+    values.push(
+      nested_from_v8(scope, value, ancestors)
+        .map_err(|e| element_error(i, e))?,
+    );
+  }
+  Ok(values)
+}
+
+fn object_from_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  object: v8::Local<'a, v8::Object>,
+  ancestors: &mut Vec<v8::Local<'a, v8::Object>>,
+) -> Result<Map<String, Value>, StdAnyError> {
+  let keys = object
+    .get_own_property_names(
+      scope,
+      v8::GetPropertyNamesArgsBuilder::new()
+        .key_conversion(v8::KeyConversionMode::ConvertToString)
+        .build(),
+    )
+    .ok_or_else(|| ConvertError::unreadable("Failed to read object keys"))?;
+  let len = keys.length();
+  let mut map = Map::with_capacity(len as _);
+  for i in 0..len {
+    let key = keys
+      .get_index(scope, i)
+      .ok_or_else(|| ConvertError::unreadable("Failed to read object keys"))?;
+    let key_str = key.to_rust_string_lossy(scope);
+    let value = object.get(scope, key).ok_or_else(|| {
+      ConvertError::unreadable(format!("Failed to read property '{key_str}'"))
+    })?;
+    if value.is_undefined() {
+      continue;
+    }
+    let value = nested_from_v8(scope, value, ancestors)
+      .map_err(|e| key_error(&key_str, e))?;
+    map.insert(key_str, value);
+  }
+  Ok(map)
+}
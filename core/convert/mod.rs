@@ -0,0 +1,4650 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+use crate::error::StdAnyError;
+use crate::runtime::ops;
+use crate::JsRuntime;
+use crate::Resource;
+use crate::ResourceId;
+use bytes::Bytes;
+use bytes::BytesMut;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::LinkedList;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::ffi::c_void;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::num::Saturating;
+use std::num::Wrapping;
+use std::ops::Range;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub use deno_ops::FromV8;
+pub use deno_ops::ToV8;
+
+#[cfg(feature = "bitflags")]
+mod bitflags;
+mod cached;
+mod channel;
+mod constants;
+mod endian;
+#[cfg(feature = "glam")]
+mod glam;
+#[cfg(feature = "half")]
+mod half;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+#[cfg(feature = "convert_net")]
+mod net;
+mod object;
+pub mod op_return;
+mod out_buffer;
+#[cfg(feature = "convert_path")]
+mod path;
+#[cfg(feature = "smallvec")]
+mod smallvec;
+mod throw;
+#[cfg(feature = "convert_time")]
+mod time;
+mod trace;
+pub mod util;
+#[cfg(feature = "uuid")]
+mod uuid;
+
+#[cfg(feature = "bitflags")]
+pub use bitflags::BitFlags;
+#[cfg(feature = "bitflags")]
+pub use bitflags::TruncatedBitFlags;
+pub use cached::Cached;
+pub(crate) use cached::CachedValues;
+pub use channel::Drained;
+pub use constants::EnumConstants;
+pub use endian::Be;
+pub use endian::Le;
+#[cfg(feature = "convert_net")]
+pub use net::NetAddr;
+#[doc(hidden)]
+pub use object::field_keys;
+#[doc(hidden)]
+pub use object::FieldNames;
+pub use object::ObjectBuilder;
+#[doc(hidden)]
+pub use object::ObjectFields;
+pub(crate) use object::ObjectKeyCache;
+pub use object::ObjectKeys;
+pub use object::WithPrototype;
+pub use out_buffer::OutBuffer;
+pub use throw::ErrorObject;
+pub use throw::Throw;
+pub(crate) use throw::Thrown;
+#[cfg(feature = "convert_time")]
+pub use time::Date;
+#[cfg(feature = "convert_time")]
+pub use time::HrTime;
+#[cfg(feature = "convert_time")]
+pub use time::Millis;
+#[cfg(feature = "convert_time")]
+pub use time::Nanos;
+#[cfg(feature = "uuid")]
+pub use uuid::UuidBytes;
+
+/// A conversion from a rust value to a v8 value.
+///
+/// When passing data from Rust into JS, either
+/// via an op or by calling a JS function directly,
+/// you need to serialize the data into a native
+/// V8 value. When using the [`op2`][deno_core::op2] macro, the return
+/// value is converted to a `v8::Local<Value>` automatically,
+/// and the strategy for conversion is controlled by attributes
+/// like `#[smi]`, `#[number]`, `#[string]`. For types with support
+/// built-in to the op2 macro, like primitives, strings, and buffers,
+/// these attributes are sufficient and you don't need to worry about this trait.
+///
+/// If, however, you want to return a custom type from an op, or
+/// simply want more control over the conversion process,
+/// you can implement the `ToV8` trait. This allows you the
+/// choose the best serialization strategy for your specific use case.
+/// You can then use the `#[to_v8]` attribute to indicate
+/// that the `#[op2]` macro should call your implementation for the conversion.
+///
+/// # Example
+///
+/// ```ignore
+/// use deno_core::ToV8;
+/// use deno_core::convert::Smi;
+/// use deno_core::op2;
+///
+/// struct Foo(i32);
+///
+/// impl<'a> ToV8<'a> for Foo {
+///   // This conversion can never fail, so we use `Infallible` as the error type.
+///   // Any error type that implements `std::error::Error` can be used here.
+///   type Error = std::convert::Infallible;
+///
+///   fn to_v8(self, scope: &mut v8::HandleScope<'a>) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+///     // For performance, pass this value as a `v8::Integer` (i.e. a `smi`).
+///     // The `Smi` wrapper type implements this conversion for you.
+///     Smi(self.0).to_v8(scope)
+///   }
+/// }
+///
+/// // using the `#[to_v8]` attribute tells the `op2` macro to call this implementation.
+/// #[op2]
+/// #[to_v8]
+/// fn op_foo() -> Foo {
+///   Foo(42)
+/// }
+/// ```
+///
+/// # Performance Notes
+/// ## Structs
+/// The natural representation of a struct in JS is an object with fields
+/// corresponding the struct. This, however, is a performance footgun and
+/// you should avoid creating and passing objects to V8 whenever possible.
+/// In general, if you need to pass a compound type to JS, it is more performant to serialize
+/// to a tuple (a `v8::Array`) rather than an object.
+/// Object keys are V8 strings, and strings are expensive to pass to V8
+/// and they have to be managed by the V8 garbage collector.
+/// Tuples, on the other hand, are keyed by `smi`s, which are immediates
+/// and don't require allocation or garbage collection. Rust tuples of up to
+/// eight elements implement `ToV8` and `FromV8` using this representation.
+///
+/// `#[derive(ToV8)]` generates this representation for a struct, converting its
+/// fields in declaration order. Add `#[v8(object)]` to the struct to convert it
+/// to an object instead, `#[v8(rename = "name")]` to change a field's key, and
+/// `#[v8(skip)]` to leave a field out. Enums can be derived too: unit variants become
+/// strings, and other variants become `[tag, payload]` arrays, or tagged objects with
+/// `#[v8(tag = "type")]`.
+///
+/// Objects built by the derive, or by hand with an [`ObjectBuilder`], create their key
+/// strings once per isolate and reuse them, which takes the key allocations out of the
+/// cost of each object, but not the cost of the object itself.
+pub trait ToV8<'a> {
+  type Error: std::error::Error + Send + Sync + 'static;
+
+  /// Converts the value to a V8 value.
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error>;
+}
+
+/// A conversion from a v8 value to a rust value.
+///
+/// When writing a op, or otherwise writing a function in Rust called
+/// from JS, arguments passed from JS are represented as [`v8::Local<v8::Value>>`][deno_core::v8::Value].
+/// To convert these values into custom Rust types, you can implement the [`FromV8`] trait.
+///
+/// Once you've implemented this trait, you can use the `#[from_v8]` attribute
+/// to tell the [`op2`][deno_core::op2] macro to use your implementation to convert the argument
+/// to the desired type.
+///
+/// # Example
+///
+/// ```ignore
+/// use deno_core::FromV8;
+/// use deno_core::convert::Smi;
+/// use deno_core::op2;
+///
+/// struct Foo(i32);
+///
+/// impl<'a> FromV8<'a> for Foo {
+///   // This conversion can fail, so we use `deno_core::error::StdAnyError` as the error type.
+///   // Any error type that implements `std::error::Error` can be used here.
+///   type Error = deno_core::error::StdAnyError;
+///
+///   fn from_v8(scope: &mut v8::HandleScope<'a>, value: v8::Local<'a, v8::Value>) -> Result<Self, Self::Error> {
+///     /// We expect this value to be a `v8::Integer`, so we use the [`Smi`][deno_core::convert::Smi] wrapper type to convert it.
+///     Smi::from_v8(scope, value).map(|Smi(v)| Foo(v))
+///   }
+/// }
+///
+/// // using the `#[from_v8]` attribute tells the `op2` macro to call this implementation.
+/// #[op2]
+/// fn op_foo(#[from_v8] foo: Foo) {
+///   let Foo(_) = foo;
+/// }
+/// ```
+///
+/// `#[derive(FromV8)]` reads a struct from the same array or object representation
+/// that `#[derive(ToV8)]` produces. In object mode, fields can be renamed with
+/// `#[v8(rename = "name")]`, and a missing property is only allowed for `Option` and
+/// [`Maybe`] fields. An `Option<Option<T>>` field reads a missing or `undefined` property
+/// as `None` and `null` as `Some(None)`.
+///
+/// Conversions only receive the scope, but can reach the op's `OpState` through
+/// [`JsRuntime::op_state_from`](crate::JsRuntime::op_state_from), as [`ResourceRef`]
+/// does to look up resources.
+pub trait FromV8<'a>: Sized {
+  type Error: std::error::Error + Send + Sync + 'static;
+
+  /// Converts a V8 value to a Rust value.
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error>;
+
+  /// Converts a V8 value to a Rust value, returning `None` rather than an error if it
+  /// can't be converted.
+  ///
+  /// Use this when a value that doesn't fit is expected, such as when probing a value
+  /// for one of several types. The default implementation discards the error from
+  /// [`from_v8`](Self::from_v8). Override it if building the error is expensive, so that
+  /// a miss doesn't pay for a message that is thrown away.
+  #[inline]
+  fn try_from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    Self::from_v8(scope, value).ok()
+  }
+}
+
+/// Extension methods for every [`ToV8`] type.
+pub trait ToV8Ext<'a>: ToV8<'a> + Sized {
+  /// Converts the value like [`ToV8::to_v8`], but with the error erased into an
+  /// [`AnyError`](crate::error::AnyError).
+  ///
+  /// This lets values with different `Error` types be converted with `?` in the same
+  /// function, such as when building an array from a mix of infallible and fallible
+  /// types. A `StdAnyError` is unwrapped rather than wrapped again, so the class of the
+  /// error is kept. Use `to_v8` directly where the error type matters, such as for
+  /// `Infallible` conversions that can't fail at all.
+  ///
+  /// ```ignore
+  /// let elements = [Smi(1).try_to_v8(scope)?, name.try_to_v8(scope)?];
+  /// let array = v8::Array::new_with_elements(scope, &elements);
+  /// ```
+  fn try_to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, crate::error::AnyError> {
+    self.to_v8(scope).map_err(into_anyhow)
+  }
+}
+
+impl<'a, T: ToV8<'a>> ToV8Ext<'a> for T {}
+
+// impls
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a numeric type as being serialized as a v8 `smi` in a `v8::Integer`.  
+///
+/// The conversion is a bitwise cast to and from `i32`, so values outside of the `i32`
+/// range are silently truncated (ie: `Smi(u64::MAX)` is converted to `-1`). Use
+/// [`CheckedSmi`] if out-of-range values should be rejected instead.
+#[repr(transparent)]
+pub struct Smi<T: SmallInt>(pub T);
+
+/// A trait for types that can represent a JS `smi`.
+pub trait SmallInt {
+  const NAME: &'static str;
+
+  #[allow(clippy::wrong_self_convention)]
+  fn as_i32(self) -> i32;
+  fn from_i32(value: i32) -> Self;
+}
+
+/// A [`SmallInt`] that can check whether a value fits, for [`CheckedSmi`].
+pub trait CheckedSmallInt: SmallInt + Sized {
+  /// Converts to an `i32`, returning `None` if the value is out of range.
+  #[allow(clippy::wrong_self_convention)]
+  fn try_as_i32(self) -> Option<i32>;
+  /// Converts from an `i32`, returning `None` if the value is out of range.
+  fn try_from_i32(value: i32) -> Option<Self>;
+}
+
+macro_rules! impl_smallint {
+  (for $($t:ty),*) => {
+    $(
+      impl SmallInt for $t {
+        const NAME: &'static str = stringify!($t);
+        #[allow(clippy::wrong_self_convention)]
+        #[inline(always)]
+        fn as_i32(self) -> i32 {
+          self as _
+        }
+
+        #[inline(always)]
+        fn from_i32(value: i32) -> Self {
+            value as _
+        }
+      }
+
+      impl CheckedSmallInt for $t {
+        #[allow(clippy::wrong_self_convention)]
+        #[inline(always)]
+        fn try_as_i32(self) -> Option<i32> {
+          self.try_into().ok()
+        }
+
+        #[inline(always)]
+        fn try_from_i32(value: i32) -> Option<Self> {
+          value.try_into().ok()
+        }
+      }
+    )*
+  };
+}
+
+impl_smallint!(for u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl<'a, T: SmallInt> ToV8<'a> for Smi<T> {
+  type Error = Infallible;
+
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(v8::Integer::new(scope, self.0.as_i32()).into())
+  }
+}
+
+impl<'a, T: SmallInt> FromV8<'a> for Smi<T> {
+  type Error = StdAnyError;
+
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let v = crate::runtime::ops::to_i32_option(&value)
+      .ok_or_else(|| ConvertError::wrong_type(T::NAME, &value))?;
+    Ok(Smi(T::from_i32(v)))
+  }
+
+  #[inline]
+  fn try_from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    crate::runtime::ops::to_i32_option(&value).map(|v| Smi(T::from_i32(v)))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Like [`Smi`], but errors rather than truncating when the value does not fit in the
+/// `i32` range, or in the range of `T`.
+///
+/// When converting from JS, the value must be an integral `number` (`1.5` and `2 ** 31`
+/// are both rejected), and negative values are rejected for unsigned types.
+#[repr(transparent)]
+pub struct CheckedSmi<T: CheckedSmallInt>(pub T);
+
+impl<'a, T: CheckedSmallInt> ToV8<'a> for CheckedSmi<T> {
+  type Error = StdAnyError;
+
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let v = self.0.try_as_i32().ok_or_else(|| {
+      ConvertError::out_of_range(format!(
+        "{} value does not fit in a smi",
+        T::NAME
+      ))
+    })?;
+    Ok(v8::Integer::new(scope, v).into())
+  }
+}
+
+impl<'a, T: CheckedSmallInt> FromV8<'a> for CheckedSmi<T> {
+  type Error = StdAnyError;
+
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(n) = v8::Local::<v8::Number>::try_from(value) else {
+      return Err(ConvertError::wrong_type(T::NAME, &value).into());
+    };
+    checked_smi(n.value()).ok_or_else(|| {
+      ConvertError::out_of_range(format!("Value out of range for {}", T::NAME))
+        .into()
+    })
+  }
+
+  #[inline]
+  fn try_from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    checked_smi(v8::Local::<v8::Number>::try_from(value).ok()?.value())
+  }
+}
+
+#[inline]
+fn checked_smi<T: CheckedSmallInt>(n: f64) -> Option<CheckedSmi<T>> {
+  // `as` saturates (and maps NaN to zero), so check that the value round-trips exactly
+  let v = (n as i32 as f64 == n).then_some(n as i32);
+  v.and_then(T::try_from_i32).map(CheckedSmi)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a numeric type as being serialized as a v8 `number` in a `v8::Number`.  
+///
+/// Converting from JS is lenient about numeric values, but never coerces other types:
+/// strings such as `"42"`, booleans, `null` and `undefined` are rejected rather than
+/// going through JS `ToNumber`. What it does accept is:
+///
+/// - Any JS `number`, converted with an `as` cast, so for integer types fractional
+///   values truncate toward zero and `NaN` becomes zero. Negative integers that fit in
+///   an `i32` wrap when cast to an unsigned type, while other out-of-range values
+///   saturate.
+/// - Any `BigInt`, converted through its low 64 bits and then cast, so large values
+///   wrap.
+///
+/// Use [`StrictNumber`] to reject `BigInt`s as well.
+#[repr(transparent)]
+pub struct Number<T: Numeric>(pub T);
+
+/// A trait for types that can represent a JS `number`.
+pub trait Numeric: Sized {
+  const NAME: &'static str;
+  #[allow(clippy::wrong_self_convention)]
+  fn as_f64(self) -> f64;
+  fn from_value(value: &v8::Value) -> Option<Self>;
+}
+
+macro_rules! impl_numeric {
+  ($($t:ty : $from: path ),*) => {
+    $(
+      impl Numeric for $t {
+        const NAME: &'static str = stringify!($t);
+        #[inline(always)]
+        fn from_value(value: &v8::Value) -> Option<Self> {
+          $from(value).map(|v| v as _)
+        }
+
+        #[allow(clippy::wrong_self_convention)]
+        #[inline(always)]
+        fn as_f64(self) -> f64 {
+            self as _
+        }
+      }
+    )*
+  };
+}
+
+impl_numeric!(
+  f32   : ops::to_f32_option,
+  f64   : ops::to_f64_option,
+  u32   : ops::to_u32_option,
+  u64   : ops::to_u64_option,
+  usize : ops::to_u64_option,
+  i32   : ops::to_i32_option,
+  i64   : ops::to_i64_option,
+  isize : ops::to_i64_option
+);
+
+impl<'a, T: Numeric> ToV8<'a> for Number<T> {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(v8::Number::new(scope, self.0.as_f64()).into())
+  }
+}
+
+impl<'a, T: Numeric> FromV8<'a> for Number<T> {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    T::from_value(&value)
+      .map(Number)
+      .ok_or_else(|| ConvertError::wrong_type(T::NAME, &value).into())
+  }
+
+  #[inline]
+  fn try_from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    T::from_value(&value).map(Number)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Like [`Number`], but only accepts an actual JS `number` when converting from JS,
+/// rejecting `BigInt`s along with every other type. Numbers are then cast to `T` like
+/// they are for `Number`.
+#[repr(transparent)]
+pub struct StrictNumber<T: Numeric>(pub T);
+
+impl<'a, T: Numeric> ToV8<'a> for StrictNumber<T> {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Number(self.0).to_v8(scope)
+  }
+}
+
+impl<'a, T: Numeric> FromV8<'a> for StrictNumber<T> {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Self::try_from_v8(scope, value)
+      .ok_or_else(|| ConvertError::wrong_type(T::NAME, &value).into())
+  }
+
+  #[inline]
+  fn try_from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    if !value.is_number() {
+      return None;
+    }
+    T::from_value(&value).map(StrictNumber)
+  }
+}
+
+/// The largest integer that can be represented exactly as a JS `number`
+/// (`Number.MAX_SAFE_INTEGER`).
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Like [`Number`], but for integer types, and errors rather than losing precision when
+/// the value is outside of the safe integer range (see `Number.isSafeInteger`).
+///
+/// When converting from JS, fractional values and values outside of the safe integer
+/// range are rejected, as are values that do not fit in `T`.
+#[repr(transparent)]
+pub struct SafeNumber<T: SafeInteger>(pub T);
+
+/// A trait for integer types that can be represented as a JS `number` without loss
+/// of precision when they are within the safe integer range.
+pub trait SafeInteger: Sized {
+  const NAME: &'static str;
+
+  /// Converts to an `f64`, returning `None` if the value is not a safe integer.
+  #[allow(clippy::wrong_self_convention)]
+  fn to_safe_f64(self) -> Option<f64>;
+  /// Converts from an `f64`, returning `None` if the value is not a safe integer or
+  /// does not fit in this type.
+  fn from_safe_f64(value: f64) -> Option<Self>;
+}
+
+macro_rules! impl_safe_integer {
+  (for $($t:ty),*) => {
+    $(
+      impl SafeInteger for $t {
+        const NAME: &'static str = stringify!($t);
+
+        #[allow(clippy::wrong_self_convention)]
+        #[inline(always)]
+        fn to_safe_f64(self) -> Option<f64> {
+          let value = i64::try_from(self).ok()?;
+          (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER)
+            .contains(&value)
+            .then_some(value as f64)
+        }
+
+        #[inline(always)]
+        fn from_safe_f64(value: f64) -> Option<Self> {
+          // `fract` is NaN for infinite and NaN values, so these are rejected too
+          if value.fract() != 0.0 || value.abs() > MAX_SAFE_INTEGER as f64 {
+            return None;
+          }
+          (value as i64).try_into().ok()
+        }
+      }
+    )*
+  };
+}
+
+impl_safe_integer!(for u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl<'a, T: SafeInteger> ToV8<'a> for SafeNumber<T> {
+  type Error = StdAnyError;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let v = self.0.to_safe_f64().ok_or_else(|| {
+      ConvertError::out_of_range(format!(
+        "{} value is not a safe integer",
+        T::NAME
+      ))
+    })?;
+    Ok(v8::Number::new(scope, v).into())
+  }
+}
+
+impl<'a, T: SafeInteger> FromV8<'a> for SafeNumber<T> {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(n) = v8::Local::<v8::Number>::try_from(value) else {
+      return Err(ConvertError::wrong_type(T::NAME, &value).into());
+    };
+    T::from_safe_f64(n.value()).map(SafeNumber).ok_or_else(|| {
+      ConvertError::out_of_range(format!(
+        "Expected {} that is a safe integer",
+        T::NAME
+      ))
+      .into()
+    })
+  }
+
+  #[inline]
+  fn try_from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    let n = v8::Local::<v8::Number>::try_from(value).ok()?;
+    T::from_safe_f64(n.value()).map(SafeNumber)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a numeric type as being serialized as a `v8::BigInt`.
+///
+/// Unlike [`Number`], this can represent the full range of 64- and 128-bit integers
+/// exactly. When converting from JS, the value must be a `bigint` that fits in `T`.
+#[repr(transparent)]
+pub struct BigInt<T: BigInteger>(pub T);
+
+/// A trait for types that can be represented as a JS `bigint`.
+pub trait BigInteger: Sized {
+  const NAME: &'static str;
+
+  #[allow(clippy::wrong_self_convention)]
+  fn to_big_int<'a>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> v8::Local<'a, v8::BigInt>;
+  /// Returns `None` if the value does not fit in this type.
+  fn from_big_int(value: &v8::BigInt) -> Option<Self>;
+}
+
+impl BigInteger for i64 {
+  const NAME: &'static str = "i64";
+
+  #[inline(always)]
+  fn to_big_int<'a>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> v8::Local<'a, v8::BigInt> {
+    v8::BigInt::new_from_i64(scope, self)
+  }
+
+  #[inline(always)]
+  fn from_big_int(value: &v8::BigInt) -> Option<Self> {
+    let (v, lossless) = value.i64_value();
+    lossless.then_some(v)
+  }
+}
+
+impl BigInteger for u64 {
+  const NAME: &'static str = "u64";
+
+  #[inline(always)]
+  fn to_big_int<'a>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> v8::Local<'a, v8::BigInt> {
+    v8::BigInt::new_from_u64(scope, self)
+  }
+
+  #[inline(always)]
+  fn from_big_int(value: &v8::BigInt) -> Option<Self> {
+    let (v, lossless) = value.u64_value();
+    lossless.then_some(v)
+  }
+}
+
+/// Creates a `v8::BigInt` from a sign and a 128-bit magnitude.
+fn big_int_from_u128<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  negative: bool,
+  magnitude: u128,
+) -> v8::Local<'a, v8::BigInt> {
+  let words = [magnitude as u64, (magnitude >> 64) as u64];
+  // Two words can never exceed the maximum BigInt size
+  v8::BigInt::new_from_words(scope, negative, &words).unwrap()
+}
+
+/// Reads the sign and 128-bit magnitude of a `v8::BigInt`, returning `None` if
+/// the magnitude is larger than 128 bits.
+fn big_int_to_u128(value: &v8::BigInt) -> Option<(bool, u128)> {
+  if value.word_count() > 2 {
+    return None;
+  }
+  let mut words = [0; 2];
+  let (negative, words) = value.to_words_array(&mut words);
+  let magnitude = words
+    .iter()
+    .rev()
+    .fold(0u128, |acc, word| (acc << 64) | *word as u128);
+  Some((negative, magnitude))
+}
+
+impl BigInteger for i128 {
+  const NAME: &'static str = "i128";
+
+  #[inline(always)]
+  fn to_big_int<'a>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> v8::Local<'a, v8::BigInt> {
+    big_int_from_u128(scope, self < 0, self.unsigned_abs())
+  }
+
+  #[inline(always)]
+  fn from_big_int(value: &v8::BigInt) -> Option<Self> {
+    match big_int_to_u128(value)? {
+      (false, magnitude) => magnitude.try_into().ok(),
+      // The magnitude of `i128::MIN` is one larger than `i128::MAX`
+      (true, magnitude) if magnitude <= i128::MIN.unsigned_abs() => {
+        Some((magnitude as i128).wrapping_neg())
+      }
+      (true, _) => None,
+    }
+  }
+}
+
+impl BigInteger for u128 {
+  const NAME: &'static str = "u128";
+
+  #[inline(always)]
+  fn to_big_int<'a>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> v8::Local<'a, v8::BigInt> {
+    big_int_from_u128(scope, false, self)
+  }
+
+  #[inline(always)]
+  fn from_big_int(value: &v8::BigInt) -> Option<Self> {
+    match big_int_to_u128(value)? {
+      (false, magnitude) => Some(magnitude),
+      (true, 0) => Some(0),
+      (true, _) => None,
+    }
+  }
+}
+
+impl<'a, T: BigInteger> ToV8<'a> for BigInt<T> {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(self.0.to_big_int(scope).into())
+  }
+}
+
+impl<'a, T: BigInteger> FromV8<'a> for BigInt<T> {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(value) = v8::Local::<v8::BigInt>::try_from(value) else {
+      return Err(
+        ConvertError::wrong_type(format!("{} BigInt", T::NAME), &value).into(),
+      );
+    };
+    T::from_big_int(&value).map(BigInt).ok_or_else(|| {
+      ConvertError::out_of_range(format!(
+        "BigInt value does not fit in {} without loss",
+        T::NAME
+      ))
+      .into()
+    })
+  }
+
+  #[inline]
+  fn try_from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    let value = v8::Local::<v8::BigInt>::try_from(value).ok()?;
+    T::from_big_int(&value).map(BigInt)
+  }
+}
+
+/// Implements `ToV8` and `FromV8` for `NonZero` integer types through a wrapper
+/// conversion for the underlying primitive, rejecting zero when converting from JS.
+macro_rules! impl_non_zero {
+  ($wrapper:ident for $($ty:ident : $prim:ident),+ $(,)?) => {
+    $(
+      impl<'a> ToV8<'a> for std::num::$ty {
+        type Error = <$wrapper<$prim> as ToV8<'a>>::Error;
+        #[inline]
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          $wrapper(self.get()).to_v8(scope)
+        }
+      }
+
+      impl<'a> FromV8<'a> for std::num::$ty {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let $wrapper(v) = $wrapper::<$prim>::from_v8(scope, value)?;
+          Self::new(v).ok_or_else(|| {
+            ConvertError::out_of_range(concat!(
+              "Expected a non-zero ",
+              stringify!($prim)
+            ))
+            .into()
+          })
+        }
+
+        #[inline]
+        fn try_from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Option<Self> {
+          let $wrapper(v) = $wrapper::<$prim>::try_from_v8(scope, value)?;
+          Self::new(v)
+        }
+      }
+    )+
+  };
+}
+
+// Pointer-sized types are converted to a `number`, like lengths and indices, and error
+// outside of the safe integer range. 64- and 128-bit types would lose precision in a
+// `number`, so they are converted to a `bigint` instead.
+impl_non_zero!(SafeNumber for
+  NonZeroU8: u8, NonZeroU16: u16, NonZeroU32: u32, NonZeroUsize: usize,
+  NonZeroI8: i8, NonZeroI16: i16, NonZeroI32: i32, NonZeroIsize: isize,
+);
+impl_non_zero!(BigInt for
+  NonZeroU64: u64, NonZeroU128: u128, NonZeroI64: i64, NonZeroI128: i128,
+);
+
+/// Implements `ToV8` and `FromV8` for atomic integer types through a wrapper conversion
+/// for the underlying primitive.
+///
+/// A shared reference to an atomic is converted by loading its current value with
+/// [`Relaxed`](std::sync::atomic::Ordering::Relaxed) ordering, which is enough for a
+/// counter or statistic that JS only reads. If the value has to be ordered with other
+/// memory accesses, load it with the ordering you need and convert the integer instead.
+/// An owned atomic is converted from its value with `into_inner`.
+///
+/// Converting from JS creates a new atomic holding the value. It can't update an
+/// existing atomic, so this is mostly useful for fields of a derived struct that is
+/// then shared, for example in an `Arc`.
+macro_rules! impl_atomic {
+  ($wrapper:ident for $($ty:ident : $prim:ident),+ $(,)?) => {
+    $(
+      impl<'a> ToV8<'a> for std::sync::atomic::$ty {
+        type Error = <$wrapper<$prim> as ToV8<'a>>::Error;
+        #[inline]
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          $wrapper(self.into_inner()).to_v8(scope)
+        }
+      }
+
+      impl<'a, 'r> ToV8<'a> for &'r std::sync::atomic::$ty {
+        type Error = <$wrapper<$prim> as ToV8<'a>>::Error;
+        #[inline]
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          $wrapper(self.load(std::sync::atomic::Ordering::Relaxed)).to_v8(scope)
+        }
+      }
+
+      impl<'a> FromV8<'a> for std::sync::atomic::$ty {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let $wrapper(v) = $wrapper::<$prim>::from_v8(scope, value)?;
+          Ok(Self::new(v))
+        }
+
+        #[inline]
+        fn try_from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Option<Self> {
+          let $wrapper(v) = $wrapper::<$prim>::try_from_v8(scope, value)?;
+          Some(Self::new(v))
+        }
+      }
+    )+
+  };
+}
+
+// The same representations as the `NonZero` types above.
+impl_atomic!(SafeNumber for
+  AtomicU8: u8, AtomicU16: u16, AtomicU32: u32, AtomicUsize: usize,
+  AtomicI8: i8, AtomicI16: i16, AtomicI32: i32, AtomicIsize: isize,
+);
+impl_atomic!(BigInt for AtomicU64: u64, AtomicI64: i64);
+
+/// Truncates a JS `number` towards zero and wraps it into the range of a 128-bit integer.
+/// Every value at least `2 ** 127` in magnitude is an exact multiple of `2 ** 64`, so
+/// mapping them to zero keeps the result correct modulo `2 ** 64` and below. `NaN` and
+/// the infinities map to zero.
+#[inline]
+fn wrap_f64(value: f64) -> i128 {
+  if !value.is_finite() || value.abs() >= 2f64.powi(127) {
+    return 0;
+  }
+  value.trunc() as i128
+}
+
+/// Implements `ToV8` and `FromV8` for `Wrapping` and `Saturating` integers. Converting to
+/// JS is the same as for the wrapper conversion of the underlying primitive. Converting
+/// from JS accepts any value of the right JS type and wraps or clamps it, where the
+/// wrapper conversion would error.
+macro_rules! impl_overflow {
+  (SafeNumber for $($prim:ident),+ $(,)?) => {
+    $(
+      impl_overflow!(@to_v8 SafeNumber $prim);
+
+      impl<'a> FromV8<'a> for Wrapping<$prim> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          _scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let Ok(n) = v8::Local::<v8::Number>::try_from(value) else {
+            return Err(
+              ConvertError::wrong_type(stringify!($prim), &value)
+                .into(),
+            );
+          };
+          Ok(Wrapping(wrap_f64(n.value()) as $prim))
+        }
+      }
+
+      impl<'a> FromV8<'a> for Saturating<$prim> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          _scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let Ok(n) = v8::Local::<v8::Number>::try_from(value) else {
+            return Err(
+              ConvertError::wrong_type(stringify!($prim), &value)
+                .into(),
+            );
+          };
+          // `as` truncates towards zero, saturates at the bounds, and maps NaN to zero
+          Ok(Saturating(n.value() as $prim))
+        }
+      }
+    )+
+  };
+  (BigInt for $($prim:ident : $value:ident),+ $(,)?) => {
+    $(
+      impl_overflow!(@to_v8 BigInt $prim);
+
+      impl<'a> FromV8<'a> for Wrapping<$prim> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          _scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let Ok(n) = v8::Local::<v8::BigInt>::try_from(value) else {
+            return Err(
+              ConvertError::wrong_type(concat!(stringify!($prim), " BigInt"), &value)
+              .into(),
+            );
+          };
+          // V8 keeps the low 64 bits of values that don't fit
+          Ok(Wrapping(n.$value().0))
+        }
+      }
+
+      impl<'a> FromV8<'a> for Saturating<$prim> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          _scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let Ok(n) = v8::Local::<v8::BigInt>::try_from(value) else {
+            return Err(
+              ConvertError::wrong_type(concat!(stringify!($prim), " BigInt"), &value)
+              .into(),
+            );
+          };
+          let (v, lossless) = n.$value();
+          if lossless {
+            return Ok(Saturating(v));
+          }
+          let (negative, _) = n.to_words_array(&mut []);
+          Ok(Saturating(if negative { $prim::MIN } else { $prim::MAX }))
+        }
+      }
+    )+
+  };
+  (@to_v8 $wrapper:ident $prim:ident) => {
+    impl<'a> ToV8<'a> for Wrapping<$prim> {
+      type Error = <$wrapper<$prim> as ToV8<'a>>::Error;
+      #[inline]
+      fn to_v8(
+        self,
+        scope: &mut v8::HandleScope<'a>,
+      ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+        $wrapper(self.0).to_v8(scope)
+      }
+    }
+
+    impl<'a> ToV8<'a> for Saturating<$prim> {
+      type Error = <$wrapper<$prim> as ToV8<'a>>::Error;
+      #[inline]
+      fn to_v8(
+        self,
+        scope: &mut v8::HandleScope<'a>,
+      ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+        $wrapper(self.0).to_v8(scope)
+      }
+    }
+  };
+}
+
+// Like the `NonZero` types, these use a `number` up to pointer size and a `bigint` for
+// 64-bit types. Unlike every other integer conversion, converting from JS never fails
+// for a value of the right JS type: `Wrapping` truncates fractions towards zero and
+// wraps modulo `2 ** bits` (`NaN` and the infinities become zero), and `Saturating`
+// truncates fractions and clamps to the bounds of the type (`NaN` becomes zero). Use
+// `CheckedSmi`, `SafeNumber` or `BigInt` to reject these values instead. Wrapped
+// pointer-sized values outside of the safe integer range fail to convert to JS, as
+// with `SafeNumber`.
+impl_overflow!(SafeNumber for u8, u16, u32, usize, i8, i16, i32, isize);
+impl_overflow!(BigInt for u64: u64_value, i64: i64_value);
+
+/// A number that is clamped into `MIN..=MAX` when converting from JS, rather than
+/// rejected when it is out of range. This is for lenient APIs, like an audio gain or
+/// an opacity, where an out-of-range value should saturate:
+///
+/// ```ignore
+/// #[op2]
+/// fn op_set_opacity(#[from_v8] Clamp(opacity): Clamp<f64, 0, 1>) {
+///   // ...
+/// }
+/// ```
+///
+/// Only a JS `number` is accepted, as with [`StrictNumber`]. Values below `MIN` become
+/// `MIN` and values above `MAX` become `MAX`, including the infinities, and then for
+/// integer types fractions are truncated toward zero. `NaN` has no place in the range
+/// and is an error. This is unlike the strict conversions, such as [`SafeNumber`] and
+/// [`CheckedSmi`], which reject any value that doesn't fit, and `Saturating`, which only
+/// clamps to the bounds of the type itself.
+///
+/// The bounds are integers, since const generics can't be floats. They must be in
+/// order and fit in `T`, which is checked at compile time when the conversion is
+/// instantiated (so by `cargo build`, but not `cargo check`). Converting to JS clamps
+/// the value in the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Clamp<T, const MIN: i64, const MAX: i64>(pub T);
+
+macro_rules! impl_clamp {
+  ($($prim:ident),+ $(,)?) => {
+    $(
+      impl<const MIN: i64, const MAX: i64> Clamp<$prim, MIN, MAX> {
+        const BOUNDS: () = assert!(
+          MIN <= MAX
+            && MIN as i128 >= $prim::MIN as i128
+            && MAX as i128 <= $prim::MAX as i128,
+          concat!("Clamp bounds must be in order and fit in ", stringify!($prim)),
+        );
+
+        #[inline]
+        fn clamp(value: f64) -> $prim {
+          value.clamp(MIN as f64, MAX as f64) as $prim
+        }
+      }
+
+      impl<'a, const MIN: i64, const MAX: i64> FromV8<'a> for Clamp<$prim, MIN, MAX> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          _scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let () = Self::BOUNDS;
+          let Ok(n) = v8::Local::<v8::Number>::try_from(value) else {
+            return Err(ConvertError::wrong_type(stringify!($prim), &value).into());
+          };
+          let n = n.value();
+          if n.is_nan() {
+            return Err(
+              ConvertError::out_of_range(format!(
+                "Expected a number between {MIN} and {MAX}, got NaN"
+              ))
+              .into(),
+            );
+          }
+          Ok(Clamp(Self::clamp(n)))
+        }
+      }
+
+      impl<'a, const MIN: i64, const MAX: i64> ToV8<'a> for Clamp<$prim, MIN, MAX> {
+        type Error = Infallible;
+        #[inline]
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          let () = Self::BOUNDS;
+          Ok(v8::Number::new(scope, Self::clamp(self.0 as f64) as f64).into())
+        }
+      }
+    )+
+  };
+}
+
+impl_clamp!(u8, u16, u32, i8, i16, i32, f32, f64);
+
+/// Passes a JS value through unchanged, so that generic code over `T: ToV8` can be
+/// given a value that is already in V8.
+impl<'a> ToV8<'a> for v8::Local<'a, v8::Value> {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    _scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(self)
+  }
+}
+
+/// Passes a JS value through unchanged, so that generic code over `T: FromV8` can
+/// accept any value.
+impl<'a> FromV8<'a> for v8::Local<'a, v8::Value> {
+  type Error = Infallible;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Ok(value)
+  }
+}
+
+macro_rules! impl_local {
+  ($($ty:ident: $expected:literal),* $(,)?) => {
+    $(
+      impl<'a> ToV8<'a> for v8::Local<'a, v8::$ty> {
+        type Error = Infallible;
+        #[inline]
+        fn to_v8(
+          self,
+          _scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          Ok(self.into())
+        }
+      }
+
+      #[doc = concat!("Checks that the value is a `v8::", stringify!($ty), "`, without copying it.")]
+      impl<'a> FromV8<'a> for v8::Local<'a, v8::$ty> {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          _scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          v8::Local::<v8::$ty>::try_from(value).map_err(|_| {
+            ConvertError::wrong_type($expected, &value).into()
+          })
+        }
+
+        #[inline]
+        fn try_from_v8(
+          _scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Option<Self> {
+          v8::Local::<v8::$ty>::try_from(value).ok()
+        }
+      }
+    )*
+  };
+}
+
+impl_local!(
+  Object: "object",
+  Array: "array",
+  Function: "function",
+  String: "string",
+  Symbol: "symbol",
+  Number: "number",
+  Boolean: "boolean",
+  BigInt: "bigint",
+  Promise: "promise",
+  Map: "Map",
+  Set: "Set",
+  ArrayBuffer: "ArrayBuffer",
+  ArrayBufferView: "ArrayBufferView",
+  Uint8Array: "Uint8Array",
+  External: "external",
+);
+
+/// Converts a persistent handle back into a local handle to the same JS value.
+///
+/// The `Global` must have been created in the isolate that `scope` belongs to.
+/// `v8::Local::new` panics otherwise.
+impl<'a> ToV8<'a> for v8::Global<v8::Value> {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(v8::Local::new(scope, self))
+  }
+}
+
+/// Keeps any JS value alive beyond the current scope, such as a callback that a later op
+/// will invoke.
+///
+/// Each `Global` is a GC root, so the value cannot be collected until the `Global` is
+/// dropped, and creating and dropping one costs more than a `v8::Local`. The handle is
+/// only valid in the isolate it was created in.
+impl<'a> FromV8<'a> for v8::Global<v8::Value> {
+  type Error = Infallible;
+  #[inline]
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Ok(v8::Global::new(scope, value))
+  }
+}
+
+/// Passes a raw pointer to JS as an opaque `v8::External`, and back again.
+///
+/// JS cannot read or modify the pointer, but V8 does not own or track the pointee
+/// either: nothing keeps it alive, nothing frees it, and JS may hold on to the external
+/// for as long as it likes. Converting from JS only checks that the value is a
+/// `v8::External`, not that it was created from an `External<T>` with the same `T`.
+///
+/// Creating and converting an `External` is safe, but dereferencing the pointer it
+/// returns is not. Before doing so, you must guarantee that the pointer came from an
+/// `External<T>` of the same type, and that the pointee is still alive and not
+/// otherwise borrowed. Prefer [`ExternalPointer`](crate::ExternalPointer), which checks
+/// the type, or keeping the object in the resource table and only using the pointer as
+/// an identity token.
+///
+/// # Example
+///
+/// ```ignore
+/// use deno_core::convert::External;
+/// use deno_core::op2;
+///
+/// struct Widget;
+/// impl Resource for Widget {}
+///
+/// /// Returns a handle to a new widget as a `[rid, external]` pair.
+/// #[op2]
+/// #[to_v8]
+/// fn op_widget_new(state: &mut OpState) -> (Smi<ResourceId>, External<Widget>) {
+///   let widget = Rc::new(Widget);
+///   let external = External(Rc::as_ptr(&widget) as *mut Widget);
+///   (Smi(state.resource_table.add_rc(widget)), external)
+/// }
+///
+/// #[op2]
+/// fn op_widget_use(
+///   state: &mut OpState,
+///   #[from_v8] handle: (Smi<ResourceId>, External<Widget>),
+/// ) -> Result<(), AnyError> {
+///   let (Smi(rid), external) = handle;
+///   // The resource table owns the widget, so we never dereference the pointer. A
+///   // closed or mismatched handle is an error rather than a use-after-free.
+///   let widget = state.resource_table.get::<Widget>(rid)?;
+///   if Rc::as_ptr(&widget) != external.0 {
+///     return Err(type_error("Invalid widget handle"));
+///   }
+///   Ok(())
+/// }
+/// ```
+pub struct External<T>(pub *mut T);
+
+impl<T> Clone for External<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<T> Copy for External<T> {}
+
+impl<'a, T> ToV8<'a> for External<T> {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(v8::External::new(scope, self.0 as _).into())
+  }
+}
+
+impl<'a, T> FromV8<'a> for External<T> {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(external) = v8::Local::<v8::External>::try_from(value) else {
+      return Err(ConvertError::wrong_type("external", &value).into());
+    };
+    Ok(External(external.value() as _))
+  }
+}
+
+/// Converts a resource id from JS into the resource it refers to in the op's
+/// [`ResourceTable`](crate::ResourceTable), so an op can take the resource directly.
+///
+/// Converting from JS looks the id up in the `OpState` of the isolate being converted
+/// in, and fails with a `BadResource` error if there is no such resource or it is of a
+/// different type. The `OpState` is only borrowed for the lookup, and op arguments are
+/// converted before the op's own `&OpState` or `&mut OpState` argument is borrowed, so
+/// the two can be combined. A `ResourceRef` converted by hand while the caller holds a
+/// mutable borrow of the state fails with an error rather than panicking. Converting to
+/// JS is not supported, since the resource id is not kept; return a [`NewResource`] to
+/// hand a new resource to JS.
+///
+/// Custom `FromV8` impls can reach the op's state the same way, through
+/// [`JsRuntime::op_state_from`](crate::JsRuntime::op_state_from) on the scope.
+///
+/// # Example
+///
+/// ```ignore
+/// #[op2]
+/// fn op_widget_use(#[from_v8] widget: ResourceRef<Widget>) {
+///   widget.0.poke();
+/// }
+/// ```
+pub struct ResourceRef<T: Resource>(pub Rc<T>);
+
+impl<T: Resource> Clone for ResourceRef<T> {
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+impl<T: Resource> std::fmt::Debug for ResourceRef<T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("ResourceRef").field(&self.0.name()).finish()
+  }
+}
+
+impl<'a, T: Resource> FromV8<'a> for ResourceRef<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let CheckedSmi(rid) = CheckedSmi::<ResourceId>::from_v8(scope, value)?;
+    let state = JsRuntime::op_state_from(scope);
+    let resource = state
+      .try_borrow()
+      .map_err(|_| {
+        crate::error::type_error(
+          "Failed to look up a resource: the OpState is already borrowed",
+        )
+      })?
+      .resource_table
+      .get::<T>(rid)?;
+    Ok(ResourceRef(resource))
+  }
+}
+
+/// Adds a resource to the op's [`ResourceTable`](crate::ResourceTable) when it is
+/// converted to JS, and converts to its new resource id as a smi.
+///
+/// This saves an op from borrowing the `OpState` just to add the resource it creates.
+/// Return values are converted after the op's own `&OpState` or `&mut OpState` argument
+/// has been released, so the two can be combined. JS can then pass the id back to an
+/// op that takes a [`ResourceRef`].
+///
+/// The `OpState` must not be borrowed while the resource is converted, so a
+/// `NewResource` converted by hand, for example inside another `ToV8` impl, fails with
+/// an error if the caller still holds a borrow of the state, rather than panicking.
+///
+/// # Example
+///
+/// ```ignore
+/// #[op2]
+/// #[to_v8]
+/// fn op_widget_new() -> NewResource<Widget> {
+///   NewResource(Widget::default())
+/// }
+/// ```
+pub struct NewResource<T: Resource>(pub T);
+
+impl<T: Resource> std::fmt::Debug for NewResource<T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("NewResource").field(&self.0.name()).finish()
+  }
+}
+
+impl<'a, T: Resource> ToV8<'a> for NewResource<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let state = JsRuntime::op_state_from(scope);
+    let rid = state
+      .try_borrow_mut()
+      .map_err(|_| {
+        crate::error::type_error(
+          "Failed to add a resource: the OpState is already borrowed",
+        )
+      })?
+      .resource_table
+      .add(self.0);
+    Ok(v8::Integer::new(scope, rid.as_i32()).into())
+  }
+}
+
+/// Builds a value with a closure when it is converted, for one-off conversions that
+/// don't warrant a named type and `ToV8` impl.
+///
+/// The closure runs in the scope the value is converted in, so it can create any JS
+/// value. Use [`TryLazy`] if building the value can fail. Construct it with
+/// [`Lazy::new`] rather than `Lazy(..)`, which lets Rust infer the closure's argument
+/// lifetimes.
+///
+/// # Example
+///
+/// ```ignore
+/// #[op2]
+/// #[to_v8]
+/// fn op_point(
+///   x: u32,
+///   y: u32,
+/// ) -> Lazy<impl for<'s> FnOnce(&mut v8::HandleScope<'s>) -> v8::Local<'s, v8::Value>>
+/// {
+///   Lazy::new(move |scope| {
+///     let object = v8::Object::new(scope);
+///     for (key, value) in [("x", x), ("y", y)] {
+///       let key = v8::String::new(scope, key).unwrap();
+///       let value = v8::Integer::new_from_unsigned(scope, value);
+///       object.set(scope, key.into(), value.into());
+///     }
+///     object.into()
+///   })
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Lazy<F>(pub F);
+
+impl<F> Lazy<F>
+where
+  F: for<'s> FnOnce(&mut v8::HandleScope<'s>) -> v8::Local<'s, v8::Value>,
+{
+  pub fn new(f: F) -> Self {
+    Self(f)
+  }
+}
+
+impl<F> std::fmt::Debug for Lazy<F> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Lazy").finish_non_exhaustive()
+  }
+}
+
+impl<'a, F> ToV8<'a> for Lazy<F>
+where
+  F: FnOnce(&mut v8::HandleScope<'a>) -> v8::Local<'a, v8::Value>,
+{
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok((self.0)(scope))
+  }
+}
+
+/// Like [`Lazy`], but the closure may fail. Its error is returned from the conversion,
+/// and is thrown as a JS exception when an op returns it.
+#[derive(Clone, Copy)]
+pub struct TryLazy<F>(pub F);
+
+impl<F, E> TryLazy<F>
+where
+  F: for<'s> FnOnce(
+    &mut v8::HandleScope<'s>,
+  ) -> Result<v8::Local<'s, v8::Value>, E>,
+{
+  pub fn new(f: F) -> Self {
+    Self(f)
+  }
+}
+
+impl<F> std::fmt::Debug for TryLazy<F> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("TryLazy").finish_non_exhaustive()
+  }
+}
+
+impl<'a, F, E> ToV8<'a> for TryLazy<F>
+where
+  F: FnOnce(&mut v8::HandleScope<'a>) -> Result<v8::Local<'a, v8::Value>, E>,
+  E: std::error::Error + Send + Sync + 'static,
+{
+  type Error = E;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    (self.0)(scope)
+  }
+}
+
+/// Converts any serde-compatible type using [`serde_v8`], the same conversion used by
+/// `#[serde]` arguments.
+///
+/// This lets a type that only implements `Serialize` and `Deserialize` be used with
+/// `#[to_v8]` and `#[from_v8]`, including inside other conversions such as `Vec<Serde<T>>`.
+/// The serde path is generic over every data model type, so it is usually slower than a
+/// hand-written or derived `ToV8`/`FromV8` impl, and it reports errors in serde's terms
+/// rather than naming the failing field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Serde<T>(pub T);
+
+impl<'a, T: serde::Serialize> ToV8<'a> for Serde<T> {
+  type Error = serde_v8::Error;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    serde_v8::to_v8(scope, self.0)
+  }
+}
+
+impl<'a, T: serde::de::DeserializeOwned> FromV8<'a> for Serde<T> {
+  type Error = serde_v8::Error;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    serde_v8::from_v8(scope, value).map(Serde)
+  }
+}
+
+/// Converts to `undefined`, so that generic code over `T: ToV8` also works with `()`.
+impl<'a> ToV8<'a> for () {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(v8::undefined(scope).into())
+  }
+}
+
+/// Converts from `undefined` or `null`. Any other value is an error, so that a `()` in
+/// a tuple or struct field still checks that nothing was passed there. Use
+/// [`v8::Local<v8::Value>`] or skip the field to ignore a value instead.
+impl<'a> FromV8<'a> for () {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    if value.is_null_or_undefined() {
+      Ok(())
+    } else {
+      Err(ConvertError::wrong_type("undefined or null", &value).into())
+    }
+  }
+
+  #[inline]
+  fn try_from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    value.is_null_or_undefined().then_some(())
+  }
+}
+
+impl<'a> ToV8<'a> for bool {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(v8::Boolean::new(scope, self).into())
+  }
+}
+
+impl<'a> FromV8<'a> for bool {
+  type Error = Infallible;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Ok(value.is_true())
+  }
+}
+
+impl<'a, 's> ToV8<'a> for Cow<'s, str> {
+  type Error = StdAnyError;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    v8::String::new(scope, &self)
+      .map(Into::into)
+      .ok_or_else(|| {
+        ConvertError::out_of_range("String is too long to convert").into()
+      })
+  }
+}
+
+/// Converts a JS string to a Rust string.
+///
+/// V8 does not allow borrowing the contents of a heap string, so the result is always
+/// [`Cow::Owned`], except for the empty string, and says nothing about how V8 stored
+/// the string. One-byte strings are copied out as latin1 first: an ASCII string is used
+/// as-is, and any other is transcoded in a single pass into a `String` of exactly the
+/// right size, rather than being collected one `char` at a time.
+impl<'a, 's> FromV8<'a> for Cow<'s, str> {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(string) = v8::Local::<v8::String>::try_from(value) else {
+      return Err(ConvertError::wrong_type("string", &value).into());
+    };
+    if string.length() == 0 {
+      return Ok(Cow::Borrowed(""));
+    }
+    if let Ok(bytes) = ops::to_cow_one_byte(scope, &value) {
+      return Ok(Cow::Owned(latin1_to_string(bytes.into_owned())));
+    }
+    Ok(Cow::Owned(string.to_rust_string_lossy(scope)))
+  }
+}
+
+/// Converts latin1 bytes to a `String`, reusing the buffer if they are all ASCII.
+fn latin1_to_string(bytes: Vec<u8>) -> String {
+  if bytes.is_ascii() {
+    // SAFETY: ASCII is valid UTF-8
+    return unsafe { String::from_utf8_unchecked(bytes) };
+  }
+  // Latin1 code points map directly onto the first 256 chars, and those above ASCII
+  // take two bytes in UTF-8
+  let non_ascii = bytes.iter().filter(|&&b| b >= 0x80).count();
+  let len = bytes.len() + non_ascii;
+  let mut out = Vec::<u8>::with_capacity(len);
+  let dst = out.as_mut_ptr();
+  let mut written = 0;
+  for &b in &bytes {
+    // SAFETY: Each byte is written as one byte if it is ASCII and two otherwise, which
+    // is exactly how `len` was counted, so every write is within the capacity.
+    unsafe {
+      if b < 0x80 {
+        dst.add(written).write(b);
+        written += 1;
+      } else {
+        dst.add(written).write(0xc0 | (b >> 6));
+        dst.add(written + 1).write(0x80 | (b & 0x3f));
+        written += 2;
+      }
+    }
+  }
+  debug_assert_eq!(written, len);
+  // SAFETY: The first `len` bytes were written above, as the UTF-8 encoding of each
+  // latin1 code point.
+  unsafe {
+    out.set_len(len);
+    String::from_utf8_unchecked(out)
+  }
+}
+
+/// Marks bytes as being serialized as a JS string with one character per byte, where
+/// each byte is a latin1 (ISO-8859-1) code point, such as for HTTP header values.
+///
+/// Converting to JS copies the bytes into a one-byte string directly, skipping the UTF-8
+/// decoding that converting a `str` needs. Converting from JS copies a string's
+/// characters back into a `Vec<u8>` without any UTF-8 validation or encoding, so
+/// `"\u00e9"` becomes the single byte `0xe9`.
+///
+/// A string with any character above `\u00ff` can't be represented and is rejected
+/// rather than narrowed lossily. A string that V8 happens to store as two-byte is fine
+/// as long as every character is in the latin1 range.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Latin1String<B = Vec<u8>>(pub B);
+
+impl<'a, B: AsRef<[u8]>> ToV8<'a> for Latin1String<B> {
+  type Error = StdAnyError;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    v8::String::new_from_one_byte(
+      scope,
+      self.0.as_ref(),
+      v8::NewStringType::Normal,
+    )
+    .map(Into::into)
+    .ok_or_else(|| {
+      ConvertError::out_of_range("String is too long to convert").into()
+    })
+  }
+}
+
+impl<'a> FromV8<'a> for Latin1String {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    if !value.is_string() {
+      return Err(ConvertError::wrong_type("string", &value).into());
+    }
+    ops::to_cow_one_byte(scope, &value)
+      .map(|bytes| Latin1String(bytes.into_owned()))
+      .map_err(|_| {
+        ConvertError::invalid(
+          "Expected a string containing only latin1 characters",
+        )
+        .into()
+      })
+  }
+}
+
+/// Marks a value as being converted to a frozen JS object (see `Object.freeze`), so that
+/// JS can't add, remove or change any of its properties. This suits read-only snapshots,
+/// such as configuration, that are handed to JS.
+///
+/// In sloppy mode code, writes to a frozen object are silently ignored, while in strict
+/// mode code they throw a `TypeError`.
+///
+/// Freezing is shallow: objects nested in the value stay mutable unless they are frozen
+/// too, such as by converting a `Frozen` field. Values that don't convert to objects,
+/// like numbers and strings, are already immutable and are left as they are.
+///
+/// Freezing an object moves it to a new hidden class, which is an extra cost for each
+/// object converted, though objects of the same shape share it. Property reads on frozen
+/// objects are as fast as on any other object, and they are garbage collected the same
+/// way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Frozen<T>(pub T);
+
+/// Like [`Frozen`], but converts to a sealed JS object (see `Object.seal`): JS can't add
+/// or remove properties, but it can still change the values of existing ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct Sealed<T>(pub T);
+
+fn with_integrity_level<'a, T: ToV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+  level: v8::IntegrityLevel,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let value =
+    trace::to_v8(scope, value).map_err(|e| StdAnyError(into_anyhow(e)))?;
+  if let Ok(object) = v8::Local::<v8::Object>::try_from(value) {
+    if object.set_integrity_level(scope, level) != Some(true) {
+      return Err(
+        crate::error::type_error("Failed to set the object's integrity level")
+          .into(),
+      );
+    }
+  }
+  Ok(value)
+}
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for Frozen<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    with_integrity_level(scope, self.0, v8::IntegrityLevel::Frozen)
+  }
+}
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for Sealed<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    with_integrity_level(scope, self.0, v8::IntegrityLevel::Sealed)
+  }
+}
+
+/// Converts to a JS string, in the same way as `Cow<str>`. Fails with a `RangeError` if
+/// the string is longer than V8's maximum string length.
+impl<'a> ToV8<'a> for String {
+  type Error = StdAnyError;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Cow::<str>::Owned(self).to_v8(scope)
+  }
+}
+
+/// Converts to a JS string, in the same way as `Cow<str>`.
+impl<'a, 's> ToV8<'a> for &'s str {
+  type Error = StdAnyError;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Cow::Borrowed(self).to_v8(scope)
+  }
+}
+
+/// Formats a value with its `Display` impl, only when it is converted to a JS string.
+///
+/// This lets an op return something formattable, such as a log message, without
+/// formatting it into a `String` of its own first:
+///
+/// ```ignore
+/// #[op2]
+/// #[to_v8]
+/// fn op_describe(#[state] conn: &Connection) -> DisplayString<ConnectionSummary> {
+///   DisplayString(conn.summary())
+/// }
+/// ```
+///
+/// The value is formatted into a buffer that is reused by every conversion on the
+/// thread, and the JS string is created from that, so after warming up converting
+/// doesn't allocate on the Rust side at all. A buffer that grew past 64KiB for a large
+/// value is freed afterwards rather than kept. Fails with a `RangeError` if the
+/// formatted string is longer than V8's maximum string length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct DisplayString<T: std::fmt::Display>(pub T);
+
+thread_local! {
+  static DISPLAY_BUFFER: std::cell::RefCell<String> =
+    const { std::cell::RefCell::new(String::new()) };
+}
+
+/// The largest formatting buffer kept for reuse.
+const DISPLAY_BUFFER_RETAINED: usize = 64 * 1024;
+
+impl<'a, T: std::fmt::Display> ToV8<'a> for DisplayString<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    use std::fmt::Write;
+    DISPLAY_BUFFER.with(|buffer| {
+      // A `Display` impl that converts another `DisplayString` gets its own buffer
+      let mut owned = String::new();
+      let mut borrowed = buffer.try_borrow_mut().ok();
+      let buffer = borrowed.as_deref_mut().unwrap_or(&mut owned);
+      buffer.clear();
+      let res = write!(buffer, "{}", self.0)
+        .map_err(|_| {
+          crate::error::type_error("Failed to format the value for conversion")
+            .into()
+        })
+        .and_then(|()| buffer.as_str().to_v8(scope));
+      if buffer.capacity() > DISPLAY_BUFFER_RETAINED {
+        *buffer = String::new();
+      }
+      res
+    })
+  }
+}
+
+/// Converts formatting arguments, as created by `format_args!`, to a JS string in the
+/// same way as [`DisplayString`]. Arguments that are only a string literal are
+/// converted without formatting.
+impl<'a, 'b> ToV8<'a> for std::fmt::Arguments<'b> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    match self.as_str() {
+      Some(s) => s.to_v8(scope),
+      None => DisplayString(self).to_v8(scope),
+    }
+  }
+}
+
+/// Converts a JS string to a Rust string, in the same way as `Cow<str>`.
+///
+/// The value must already be a string. Other values are rejected rather than coerced
+/// with `String(value)`, which could run arbitrary JS through `toString`.
+impl<'a> FromV8<'a> for String {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Cow::<str>::from_v8(scope, value).map(Cow::into_owned)
+  }
+}
+
+/// Converts a `char` to a JS string containing that single character.
+impl<'a> ToV8<'a> for char {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let mut buffer = [0; 2];
+    let units = self.encode_utf16(&mut buffer);
+    // A one- or two-unit string can never exceed the maximum string length
+    Ok(
+      v8::String::new_from_two_byte(scope, units, v8::NewStringType::Normal)
+        .unwrap()
+        .into(),
+    )
+  }
+}
+
+/// Reads a JS string containing exactly one Unicode scalar value.
+///
+/// Characters outside the Basic Multilingual Plane (such as most emoji) are accepted,
+/// even though they have a `length` of 2 in JS, as long as they are a valid surrogate
+/// pair. Lone surrogates are not scalar values and are rejected.
+impl<'a> FromV8<'a> for char {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(string) = v8::Local::<v8::String>::try_from(value) else {
+      return Err(ConvertError::wrong_type("string", &value).into());
+    };
+    let err = || ConvertError::expected_type("a single character");
+    let len = string.length();
+    if len == 0 || len > 2 {
+      return Err(err().into());
+    }
+    let mut buffer = [0; 2];
+    string.write(
+      scope,
+      &mut buffer[..len],
+      0,
+      v8::WriteOptions::NO_NULL_TERMINATION,
+    );
+    let mut chars = char::decode_utf16(buffer[..len].iter().copied());
+    match (chars.next(), chars.next()) {
+      (Some(Ok(c)), None) => Ok(c),
+      _ => Err(err().into()),
+    }
+  }
+}
+
+/// Converts an [`Ordering`] to `-1`, `0` or `1`, as returned by a JS comparator such as
+/// the callback for `Array.prototype.sort`.
+impl<'a> ToV8<'a> for Ordering {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(v8::Integer::new(scope, self as i32).into())
+  }
+}
+
+/// Reads the result of a JS comparator by its sign, so any negative number is `Less` and
+/// any positive number is `Greater`. Zero, negative zero and `NaN` are `Equal`, which is
+/// how `Array.prototype.sort` treats them.
+impl<'a> FromV8<'a> for Ordering {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(n) = v8::Local::<v8::Number>::try_from(value) else {
+      return Err(ConvertError::wrong_type("number", &value).into());
+    };
+    Ok(n.value().partial_cmp(&0.0).unwrap_or(Ordering::Equal))
+  }
+}
+
+/// `None` is converted to `null`.
+///
+/// When converting from JS, both `null` and `undefined` are treated as `None`. This means
+/// that an `undefined` value will round-trip as `null`.
+impl<'a, T: ToV8<'a>> ToV8<'a> for Option<T> {
+  type Error = T::Error;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    match self {
+      Some(value) => trace::to_v8(scope, value),
+      None => Ok(v8::null(scope).into()),
+    }
+  }
+}
+
+impl<'a, T: FromV8<'a>> FromV8<'a> for Option<T> {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    if value.is_null_or_undefined() {
+      return Ok(None);
+    }
+    trace::from_v8::<T>(scope, value)
+      .map(Some)
+      .map_err(|e| StdAnyError(into_anyhow(e)))
+  }
+
+  #[inline]
+  fn try_from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    if value.is_null_or_undefined() {
+      return Some(None);
+    }
+    T::try_from_v8(scope, value).map(Some)
+  }
+}
+
+/// A JS `undefined | null | T`, for APIs where `undefined` and `null` mean different
+/// things, such as a JSON merge patch, where a missing or `undefined` property leaves a
+/// value as it is and `null` removes it.
+///
+/// Unlike `Option<T>`, which reads both `undefined` and `null` as `None` and converts
+/// `None` to `null`, each state converts to and from its own JS value, so all three
+/// round-trip unchanged. In a `#[derive(FromV8)]` object, a missing property is read as
+/// [`Undefined`](Self::Undefined), like a missing `Option` field is read as `None`.
+///
+/// ```ignore
+/// #[derive(FromV8)]
+/// #[v8(object)]
+/// struct SettingsPatch {
+///   proxy: Maybe<String>,
+/// }
+///
+/// match patch.proxy {
+///   Maybe::Undefined => {}
+///   Maybe::Null => settings.proxy = None,
+///   Maybe::Present(proxy) => settings.proxy = Some(proxy),
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Maybe<T> {
+  Undefined,
+  Null,
+  Present(T),
+}
+
+impl<T> Maybe<T> {
+  pub fn is_undefined(&self) -> bool {
+    matches!(self, Self::Undefined)
+  }
+
+  pub fn is_null(&self) -> bool {
+    matches!(self, Self::Null)
+  }
+
+  pub fn is_present(&self) -> bool {
+    matches!(self, Self::Present(_))
+  }
+
+  pub fn as_ref(&self) -> Maybe<&T> {
+    match self {
+      Self::Undefined => Maybe::Undefined,
+      Self::Null => Maybe::Null,
+      Self::Present(value) => Maybe::Present(value),
+    }
+  }
+
+  /// The value if it is present, treating `undefined` and `null` alike.
+  pub fn into_option(self) -> Option<T> {
+    match self {
+      Self::Present(value) => Some(value),
+      Self::Undefined | Self::Null => None,
+    }
+  }
+}
+
+impl<T> Default for Maybe<T> {
+  fn default() -> Self {
+    Self::Undefined
+  }
+}
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for Maybe<T> {
+  type Error = T::Error;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    match self {
+      Self::Undefined => Ok(v8::undefined(scope).into()),
+      Self::Null => Ok(v8::null(scope).into()),
+      Self::Present(value) => trace::to_v8(scope, value),
+    }
+  }
+}
+
+impl<'a, T: FromV8<'a>> FromV8<'a> for Maybe<T> {
+  type Error = T::Error;
+  #[inline]
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    if value.is_undefined() {
+      Ok(Self::Undefined)
+    } else if value.is_null() {
+      Ok(Self::Null)
+    } else {
+      trace::from_v8::<T>(scope, value).map(Self::Present)
+    }
+  }
+
+  #[inline]
+  fn try_from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    if value.is_undefined() {
+      Some(Self::Undefined)
+    } else if value.is_null() {
+      Some(Self::Null)
+    } else {
+      T::try_from_v8(scope, value).map(Self::Present)
+    }
+  }
+}
+
+/// `Ok(value)` is converted to `{ ok: true, value }` and `Err(error)` to `{ ok: false, error }`.
+///
+/// This is distinct from an op returning `Result`, where `Err` is thrown as an exception:
+/// `#[op2]` always treats a top-level `Result` return type that way. To hand a `Result` to JS
+/// as a value, for example one outcome per item in a batch op, return it inside another type
+/// such as `Vec<Result<T, E>>`.
+impl<'a, T: ToV8<'a>, E: ToV8<'a>> ToV8<'a> for Result<T, E> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let (ok, name, value) = match self {
+      Ok(value) => (
+        true,
+        "value",
+        trace::to_v8(scope, value).map_err(|e| field_error("value", e))?,
+      ),
+      Err(error) => (
+        false,
+        "error",
+        trace::to_v8(scope, error).map_err(|e| field_error("error", e))?,
+      ),
+    };
+    let object = v8::Object::new(scope);
+    let key = field_key(scope, "ok")?;
+    let ok = v8::Boolean::new(scope, ok);
+    object.create_data_property(scope, key.into(), ok.into());
+    let key = field_key(scope, name)?;
+    object.create_data_property(scope, key.into(), value);
+    Ok(object.into())
+  }
+}
+
+/// Reads an object of the form `{ ok: true, value }` or `{ ok: false, error }`.
+impl<'a, T: FromV8<'a>, E: FromV8<'a>> FromV8<'a> for Result<T, E> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let object = field_object(value)?;
+    let ok = field_property(scope, object, "ok", false)?;
+    if !ok.is_boolean() {
+      return Err(ConvertError::wrong_type("boolean 'ok'", &ok).into());
+    }
+    if ok.is_true() {
+      let value = field_property(scope, object, "value", true)?;
+      trace::from_v8::<T>(scope, value)
+        .map(Ok)
+        .map_err(|e| field_error("value", e))
+    } else {
+      let error = field_property(scope, object, "error", true)?;
+      trace::from_v8::<E>(scope, error)
+        .map(Err)
+        .map_err(|e| field_error("error", e))
+    }
+  }
+}
+
+/// One of two types, for a JS union type such as `string | number`.
+///
+/// Converting from JS tries `L` first and falls back to `R`, so when a value would
+/// convert to both, it becomes `Left`: put the stricter type on the left, such as a
+/// `Smi` before a [`Number`]. If neither converts, the error reports both attempts.
+/// When both failed on the type of the value itself, they are merged into one, as in
+/// `Expected string or f64, got boolean`, and nested `Either`s merge in the same way.
+/// Both conversions run in full, so getters on an object may run twice.
+///
+/// ```ignore
+/// #[op2]
+/// fn op_set_timeout(#[from_v8] delay: Either<Number<f64>, String>) {
+///   // ...
+/// }
+/// ```
+///
+/// Converting to JS converts whichever value is present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Either<L, R> {
+  Left(L),
+  Right(R),
+}
+
+impl<'a, L: ToV8<'a>, R: ToV8<'a>> ToV8<'a> for Either<L, R> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    match self {
+      Either::Left(value) => {
+        trace::to_v8(scope, value).map_err(|e| StdAnyError(into_anyhow(e)))
+      }
+      Either::Right(value) => {
+        trace::to_v8(scope, value).map_err(|e| StdAnyError(into_anyhow(e)))
+      }
+    }
+  }
+}
+
+impl<'a, L: FromV8<'a>, R: FromV8<'a>> FromV8<'a> for Either<L, R> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let left = match trace::from_v8::<L>(scope, value) {
+      Ok(value) => return Ok(Either::Left(value)),
+      Err(err) => into_anyhow(err),
+    };
+    let right = match trace::from_v8::<R>(scope, value) {
+      Ok(value) => return Ok(Either::Right(value)),
+      Err(err) => into_anyhow(err),
+    };
+    Err(either_error(left, right))
+  }
+
+  #[inline]
+  fn try_from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Option<Self> {
+    if let Some(value) = L::try_from_v8(scope, value) {
+      return Some(Either::Left(value));
+    }
+    R::try_from_v8(scope, value).map(Either::Right)
+  }
+}
+
+/// Combines the errors from both sides of an [`Either`] that failed to convert.
+fn either_error(left: anyhow::Error, right: anyhow::Error) -> StdAnyError {
+  // Only merge errors about the value itself, not about a nested element or field
+  if let (
+    Some(ConvertError::ExpectedType {
+      expected: left_expected,
+      got: left_got,
+    }),
+    Some(ConvertError::ExpectedType {
+      expected: right_expected,
+      got: right_got,
+    }),
+  ) = (
+    left.downcast_ref::<ConvertError>(),
+    right.downcast_ref::<ConvertError>(),
+  ) {
+    return ConvertError::ExpectedType {
+      expected: format!("{left_expected} or {right_expected}").into(),
+      got: left_got.clone().or_else(|| right_got.clone()),
+    }
+    .into();
+  }
+  ConvertError::invalid(format!(
+    "Failed to convert as either type: {left}; {right}"
+  ))
+  .into()
+}
+
+/// Converts a `HashMap` to a plain JS object, with each key converted to a string property name.
+///
+/// Note that objects are a performance footgun (see [`ToV8`]): every key is allocated as a
+/// V8 string. If the keys aren't naturally strings, or the JS side expects `Map` semantics,
+/// consider [`V8Map`] instead.
+impl<'a, K, V, H> ToV8<'a> for HashMap<K, V, H>
+where
+  K: ToString,
+  V: ToV8<'a>,
+{
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let obj = v8::Object::new(scope);
+    for (key, value) in self {
+      let key_string = key.to_string();
+      let key = v8::String::new(scope, &key_string).ok_or_else(|| {
+        ConvertError::out_of_range("String is too long to convert")
+      })?;
+      let value =
+        trace::to_v8(scope, value).map_err(|e| key_error(&key_string, e))?;
+      // Use `create_data_property` rather than `set` so that keys like `__proto__`
+      // are defined as own properties instead of invoking setters.
+      obj.create_data_property(scope, key.into(), value);
+    }
+    Ok(obj.into())
+  }
+}
+
+/// Converts a JS object to a `HashMap`, parsing each own enumerable string-keyed property
+/// name with [`FromStr`].
+///
+/// Symbol-keyed properties are skipped. Integer-like property names are passed to
+/// [`FromStr`] as strings, and a key that fails to parse is an error. So is a key that
+/// parses to the same value as an earlier one, such as `"01"` after `"1"` for a
+/// `HashMap<u32, _>`, rather than one of the values silently replacing the other.
+impl<'a, K, V, H> FromV8<'a> for HashMap<K, V, H>
+where
+  K: FromStr + Eq + Hash,
+  V: FromV8<'a>,
+  H: BuildHasher + Default,
+{
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let obj = v8::Local::<v8::Object>::try_from(value)
+      .map_err(|_| ConvertError::wrong_type("object", &value))?;
+    let keys = obj
+      .get_own_property_names(
+        scope,
+        v8::GetPropertyNamesArgsBuilder::new()
+          .key_conversion(v8::KeyConversionMode::ConvertToString)
+          .build(),
+      )
+      .ok_or_else(|| ConvertError::unreadable("Failed to read object keys"))?;
+    let len = keys.length();
+    let mut out = HashMap::with_capacity_and_hasher(len as _, H::default());
+    for i in 0..len {
+      let key = keys.get_index(scope, i).ok_or_else(|| {
+        ConvertError::unreadable("Failed to read object keys")
+      })?;
+      let key_str = key.to_rust_string_lossy(scope);
+      let Ok(parsed_key) = key_str.parse::<K>() else {
+        return Err(
+          ConvertError::invalid(format!("Invalid key '{key_str}'")).into(),
+        );
+      };
+      if out.contains_key(&parsed_key) {
+        return Err(
+          ConvertError::invalid(format!(
+            "Duplicate key '{key_str}', which parses to the same key as an earlier property"
+          ))
+          .into(),
+        );
+      }
+      let value = obj.get(scope, key).ok_or_else(|| {
+        ConvertError::unreadable(format!("Failed to read property '{key_str}'"))
+      })?;
+      let value = trace::from_v8::<V>(scope, value)
+        .map_err(|e| key_error(&key_str, e))?;
+      out.insert(parsed_key, value);
+    }
+    Ok(out)
+  }
+}
+
+/// A symbol used as an object property key.
+///
+/// String-keyed conversions, such as the `HashMap` impls and `#[derive]`d objects, never
+/// see symbol-keyed properties. Use a `SymbolKey` to read or write one directly, or mark
+/// a `#[derive]`d object field with `#[v8(symbol = "key")]` to store it under
+/// `Symbol.for("key")` instead of its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKey {
+  /// The symbol for this key in the global symbol registry, as returned by `Symbol.for`.
+  Registered(&'static str),
+  /// `Symbol.asyncIterator`
+  AsyncIterator,
+  /// `Symbol.hasInstance`
+  HasInstance,
+  /// `Symbol.isConcatSpreadable`
+  IsConcatSpreadable,
+  /// `Symbol.iterator`
+  Iterator,
+  /// `Symbol.match`
+  Match,
+  /// `Symbol.replace`
+  Replace,
+  /// `Symbol.search`
+  Search,
+  /// `Symbol.split`
+  Split,
+  /// `Symbol.toPrimitive`
+  ToPrimitive,
+  /// `Symbol.toStringTag`
+  ToStringTag,
+  /// `Symbol.unscopables`
+  Unscopables,
+}
+
+impl SymbolKey {
+  /// Returns the symbol.
+  pub fn symbol<'a>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Symbol>, StdAnyError> {
+    Ok(match self {
+      SymbolKey::Registered(key) => {
+        let key = v8::String::new(scope, key).ok_or_else(|| {
+          crate::error::type_error(format!("Failed to create symbol '{key}'"))
+        })?;
+        v8::Symbol::for_key(scope, key)
+      }
+      SymbolKey::AsyncIterator => v8::Symbol::get_async_iterator(scope),
+      SymbolKey::HasInstance => v8::Symbol::get_has_instance(scope),
+      SymbolKey::IsConcatSpreadable => {
+        v8::Symbol::get_is_concat_spreadable(scope)
+      }
+      SymbolKey::Iterator => v8::Symbol::get_iterator(scope),
+      SymbolKey::Match => v8::Symbol::get_match(scope),
+      SymbolKey::Replace => v8::Symbol::get_replace(scope),
+      SymbolKey::Search => v8::Symbol::get_search(scope),
+      SymbolKey::Split => v8::Symbol::get_split(scope),
+      SymbolKey::ToPrimitive => v8::Symbol::get_to_primitive(scope),
+      SymbolKey::ToStringTag => v8::Symbol::get_to_string_tag(scope),
+      SymbolKey::Unscopables => v8::Symbol::get_unscopables(scope),
+    })
+  }
+
+  /// Reads `object[symbol]` and converts it, which is `undefined` if the property is
+  /// missing. This may run a getter, and follows the prototype chain.
+  pub fn get<'a, T: FromV8<'a>>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+    object: v8::Local<'a, v8::Object>,
+  ) -> Result<T, StdAnyError> {
+    let key = self.symbol(scope)?;
+    let value = object.get(scope, key.into()).ok_or_else(|| {
+      ConvertError::unreadable(format!("{self} could not be read"))
+    })?;
+    trace::from_v8::<T>(scope, value)
+      .map_err(|e| path_error(format_args!("[{self}]"), e))
+  }
+
+  /// Converts `value` and defines it as an own data property of `object` under the
+  /// symbol, without invoking any setter.
+  pub fn set<'a, T: ToV8<'a>>(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+    object: v8::Local<'a, v8::Object>,
+    value: T,
+  ) -> Result<(), StdAnyError> {
+    let key = self.symbol(scope)?;
+    let value = trace::to_v8(scope, value)
+      .map_err(|e| path_error(format_args!("[{self}]"), e))?;
+    object
+      .create_data_property(scope, key.into(), value)
+      .filter(|defined| *defined)
+      .ok_or_else(|| {
+        crate::error::type_error(format!("{self} could not be set")).into()
+      })
+      .map(|_| ())
+  }
+}
+
+/// Formats the symbol as it would be written in JS, such as `Symbol.iterator` or
+/// `Symbol.for("key")`.
+impl std::fmt::Display for SymbolKey {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      SymbolKey::Registered(key) => return write!(f, "Symbol.for({key:?})"),
+      SymbolKey::AsyncIterator => "asyncIterator",
+      SymbolKey::HasInstance => "hasInstance",
+      SymbolKey::IsConcatSpreadable => "isConcatSpreadable",
+      SymbolKey::Iterator => "iterator",
+      SymbolKey::Match => "match",
+      SymbolKey::Replace => "replace",
+      SymbolKey::Search => "search",
+      SymbolKey::Split => "split",
+      SymbolKey::ToPrimitive => "toPrimitive",
+      SymbolKey::ToStringTag => "toStringTag",
+      SymbolKey::Unscopables => "unscopables",
+    };
+    write!(f, "Symbol.{name}")
+  }
+}
+
+/// Converts to the symbol itself.
+impl<'a> ToV8<'a> for SymbolKey {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(self.symbol(scope)?.into())
+  }
+}
+
+/// Converts a `HashMap` to and from a JS `Map`, rather than the plain object used by the
+/// `HashMap` impls.
+///
+/// Keys are converted with [`ToV8`]/[`FromV8`] like values, so numeric, boolean and
+/// object keys are kept as they are instead of being turned into property name strings.
+/// Prefer this when the keys aren't strings or the JS side wants `Map` methods and
+/// semantics. For string keys that JS treats as a record, a plain object is usually the
+/// better fit: it is what JS APIs expect, and V8 is heavily optimized for property access
+/// on objects. The `map_*` benches in `core/benches/ops/sync.rs` compare the two for
+/// 10,000 integer keys in each direction.
+///
+/// The JS `Map` is filled in the iteration order of the `HashMap`, which is unspecified.
+/// Distinct Rust keys that convert to the same JS primitive are merged by the `Map`, with
+/// the last one winning. When converting from JS, an error in an entry is reported with
+/// the position of that entry in the `Map`'s insertion order.
+#[derive(Debug, Clone, Default)]
+pub struct V8Map<K, V, H = std::collections::hash_map::RandomState>(
+  pub HashMap<K, V, H>,
+);
+
+impl<K, V, H> From<HashMap<K, V, H>> for V8Map<K, V, H> {
+  fn from(map: HashMap<K, V, H>) -> Self {
+    V8Map(map)
+  }
+}
+
+impl<'a, K, V, H> ToV8<'a> for V8Map<K, V, H>
+where
+  K: ToV8<'a>,
+  V: ToV8<'a>,
+{
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let map = v8::Map::new(scope);
+    for (i, (key, value)) in self.0.into_iter().enumerate() {
+      let key = trace::to_v8(scope, key).map_err(|e| element_error(i, e))?;
+      let value =
+        trace::to_v8(scope, value).map_err(|e| element_error(i, e))?;
+      map.set(scope, key, value).ok_or_else(|| {
+        crate::error::type_error(format!("Map entry {i} could not be set"))
+      })?;
+    }
+    Ok(map.into())
+  }
+}
+
+impl<'a, K, V, H> FromV8<'a> for V8Map<K, V, H>
+where
+  K: FromV8<'a> + Eq + Hash,
+  V: FromV8<'a>,
+  H: BuildHasher + Default,
+{
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let map = v8::Local::<v8::Map>::try_from(value)
+      .map_err(|_| ConvertError::wrong_type("Map", &value))?;
+    // `as_array` returns a snapshot of the entries as `[key0, value0, key1, value1, ...]`.
+    let entries = map.as_array(scope);
+    let len = entries.length() as usize / 2;
+    let mut out = HashMap::with_capacity_and_hasher(len, H::default());
+    for i in 0..len {
+      let key = get_element(scope, entries, "Map", i * 2)?;
+      let key =
+        trace::from_v8::<K>(scope, key).map_err(|e| element_error(i, e))?;
+      let value = get_element(scope, entries, "Map", i * 2 + 1)?;
+      let value =
+        trace::from_v8::<V>(scope, value).map_err(|e| element_error(i, e))?;
+      out.insert(key, value);
+    }
+    Ok(V8Map(out))
+  }
+}
+
+/// An error from converting a value nested inside another, along with the path to that
+/// value from the outermost one. It is displayed as, for example, `at [2].foo: Expected u32`.
+///
+/// Collection impls, tuples and derived types wrap element and field errors in this as
+/// they unwind, so only the innermost error message is kept, with the path in front.
+/// The JS error class is that of the innermost error.
+#[derive(Debug)]
+pub struct ConversionError {
+  path: String,
+  source: anyhow::Error,
+}
+
+impl ConversionError {
+  /// The path to the value that failed to convert, such as `[2].foo`.
+  pub fn path(&self) -> &str {
+    self.path.strip_prefix('.').unwrap_or(&self.path)
+  }
+
+  /// The error from converting the value at [`path`](Self::path).
+  pub fn error(&self) -> &anyhow::Error {
+    &self.source
+  }
+}
+
+impl std::fmt::Display for ConversionError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "at {}: {}", self.path(), self.source)
+  }
+}
+
+impl std::error::Error for ConversionError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    Some(self.source.as_ref())
+  }
+}
+
+/// The reason a value could not be converted, for callers that want to match on the kind
+/// of failure rather than its message. The built-in impls return these, wrapped in a
+/// [`StdAnyError`] and, for nested values, a [`ConversionError`]; use
+/// [`ConvertError::find`] to get it back out.
+///
+/// An op that returns one as its error throws an `OutOfRange` error as a `RangeError` and
+/// any other kind as a `TypeError`, as given by
+/// [`get_custom_error_class`](crate::error::get_custom_error_class). A failure to
+/// convert an op's arguments or return value is always thrown as a `TypeError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConvertError {
+  /// The value is not of the expected type, such as a number where a string was
+  /// expected. Displayed as `Expected {expected}`, followed by `, got {got}` when the
+  /// actual type is known.
+  ExpectedType {
+    expected: Cow<'static, str>,
+    got: Option<Cow<'static, str>>,
+  },
+  /// The value has the right type but can't be represented by the target type, such as
+  /// `256` for a `u8` or a fractional number for an integer.
+  OutOfRange { message: Cow<'static, str> },
+  /// A required object property is missing. Displayed as `Missing field '{name}'`.
+  MissingField { name: Cow<'static, str> },
+  /// A required tuple element is missing. Displayed as `Missing tuple element {index}`.
+  MissingElement { index: usize },
+  /// An array has the wrong length. Displayed as `Expected array of length {expected}`.
+  LengthMismatch { expected: usize, actual: usize },
+  /// The value has the right type but its contents are not valid, such as a malformed
+  /// IP address string or an unknown enum tag.
+  Invalid { message: Cow<'static, str> },
+  /// The `ArrayBuffer` backing the value has been detached.
+  Detached,
+  /// A property or element of the value could not be read, such as when its getter
+  /// throws.
+  Unreadable { message: Cow<'static, str> },
+}
+
+impl ConvertError {
+  /// Creates an [`ExpectedType`](Self::ExpectedType) error without the actual type.
+  /// Prefer [`wrong_type`](Self::wrong_type) when the value is at hand.
+  pub fn expected_type(expected: impl Into<Cow<'static, str>>) -> Self {
+    Self::ExpectedType {
+      expected: expected.into(),
+      got: None,
+    }
+  }
+
+  /// Creates an [`ExpectedType`](Self::ExpectedType) error naming the actual type of
+  /// `value`, as given by [`js_type_name`].
+  pub fn wrong_type(
+    expected: impl Into<Cow<'static, str>>,
+    value: &v8::Value,
+  ) -> Self {
+    Self::ExpectedType {
+      expected: expected.into(),
+      got: Some(js_type_name(value).into()),
+    }
+  }
+
+  /// Creates an [`OutOfRange`](Self::OutOfRange) error.
+  pub fn out_of_range(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::OutOfRange {
+      message: message.into(),
+    }
+  }
+
+  /// Creates an [`Invalid`](Self::Invalid) error.
+  pub fn invalid(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::Invalid {
+      message: message.into(),
+    }
+  }
+
+  /// Creates an [`Unreadable`](Self::Unreadable) error.
+  pub fn unreadable(message: impl Into<Cow<'static, str>>) -> Self {
+    Self::Unreadable {
+      message: message.into(),
+    }
+  }
+
+  /// Finds the `ConvertError` in an error returned from a conversion, looking through
+  /// any [`ConversionError`] that adds the path to it.
+  pub fn find(error: &anyhow::Error) -> Option<&Self> {
+    error.chain().find_map(|e| e.downcast_ref::<Self>())
+  }
+}
+
+impl std::fmt::Display for ConvertError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::ExpectedType {
+        expected,
+        got: None,
+      } => write!(f, "Expected {expected}"),
+      Self::ExpectedType {
+        expected,
+        got: Some(got),
+      } => write!(f, "Expected {expected}, got {got}"),
+      Self::OutOfRange { message }
+      | Self::Invalid { message }
+      | Self::Unreadable { message } => f.write_str(message),
+      Self::MissingField { name } => write!(f, "Missing field '{name}'"),
+      Self::MissingElement { index } => {
+        write!(f, "Missing tuple element {index}")
+      }
+      Self::LengthMismatch { expected, .. } => {
+        write!(f, "Expected array of length {expected}")
+      }
+      Self::Detached => f.write_str("ArrayBuffer is detached"),
+    }
+  }
+}
+
+impl std::error::Error for ConvertError {}
+
+impl From<ConvertError> for StdAnyError {
+  fn from(err: ConvertError) -> Self {
+    StdAnyError(err.into())
+  }
+}
+
+/// Converts an error to an `anyhow::Error`, unwrapping rather than re-wrapping a
+/// [`StdAnyError`] so that a [`ConversionError`] inside it can be found again.
+fn into_anyhow<E: std::error::Error + Send + Sync + 'static>(
+  err: E,
+) -> anyhow::Error {
+  let mut err = Some(err);
+  if let Some(err) =
+    (&mut err as &mut dyn std::any::Any).downcast_mut::<Option<StdAnyError>>()
+  {
+    return err.take().unwrap().0;
+  }
+  anyhow::Error::new(err.unwrap())
+}
+
+/// Prepends `segment` to the path of the error from converting a nested value.
+fn path_error<E: std::error::Error + Send + Sync + 'static>(
+  segment: std::fmt::Arguments,
+  err: E,
+) -> StdAnyError {
+  let err = match into_anyhow(err).downcast::<ConversionError>() {
+    Ok(mut err) => {
+      err.path.insert_str(0, &segment.to_string());
+      err
+    }
+    Err(source) => ConversionError {
+      path: segment.to_string(),
+      source,
+    },
+  };
+  StdAnyError(err.into())
+}
+
+/// Wraps an error from converting an array, tuple or set element.
+fn element_error<E: std::error::Error + Send + Sync + 'static>(
+  index: usize,
+  err: E,
+) -> StdAnyError {
+  path_error(format_args!("[{index}]"), err)
+}
+
+/// Wraps an error from converting an object property or map entry. Keys that are valid
+/// identifiers are written as `.key`, and any others as `[0]` or `["some key"]`.
+fn key_error<E: std::error::Error + Send + Sync + 'static>(
+  key: &str,
+  err: E,
+) -> StdAnyError {
+  let mut chars = key.chars();
+  let identifier = chars
+    .next()
+    .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+    && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+  if identifier {
+    path_error(format_args!(".{key}"), err)
+  } else if !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()) {
+    path_error(format_args!("[{key}]"), err)
+  } else {
+    path_error(format_args!("[{key:?}]"), err)
+  }
+}
+
+/// The most elements that are reserved up front when converting an array of a length
+/// reported by JS. A sparse array such as `new Array(2 ** 32 - 1)` is cheap to create,
+/// so reserving its whole length could abort the process before the first element is
+/// even read. Longer arrays grow as their elements are converted.
+const MAX_RESERVED_ELEMENTS: usize = 1024;
+
+/// Reads `arr[index]`. This may fail if the array has an accessor at that index whose
+/// getter throws, in which case the exception is replaced by the returned error.
+fn get_element<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  arr: v8::Local<'a, v8::Array>,
+  kind: &str,
+  index: usize,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  arr.get_index(scope, index as _).ok_or_else(|| {
+    ConvertError::unreadable(format!(
+      "{kind} element {index} could not be read"
+    ))
+    .into()
+  })
+}
+
+/// A tuple that converts from a JS array of any length, for payloads whose shape grows
+/// over time.
+///
+/// Elements past the end of the tuple are ignored. Elements missing from the end of the
+/// array are converted from `undefined`, so a trailing element may only be missing if
+/// its type accepts `undefined`: `Option<T>` becomes `None`, `()` becomes `()`, `bool`
+/// becomes `false` and `v8::Global<v8::Value>` holds `undefined`. For any other type,
+/// such as `Smi<u32>` or `Number<f64>`, a missing element is an error even if the type
+/// implements `Default`. Holes inside the array also convert from `undefined`.
+/// Converting to JS is the same as for the tuple itself.
+///
+/// ```ignore
+/// #[op2]
+/// fn op_connect(
+///   #[from_v8] PartialTuple((port, timeout)): PartialTuple<(Smi<u16>, Option<Smi<u32>>)>,
+/// ) {
+///   // `[8080]` and `[8080, 1000, "added in a later version"]` are both accepted
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct PartialTuple<T>(pub T);
+
+macro_rules! impl_tuple {
+  ($len:literal; $($name:ident : $idx:tt),+) => {
+    impl<'a, $($name: ToV8<'a>),+> ToV8<'a> for ($($name,)+) {
+      type Error = StdAnyError;
+      fn to_v8(
+        self,
+        scope: &mut v8::HandleScope<'a>,
+      ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+        let elements = [
+          $(trace::to_v8(scope, self.$idx).map_err(|e| element_error($idx, e))?),+
+        ];
+        Ok(v8::Array::new_with_elements(scope, &elements).into())
+      }
+    }
+
+    impl<'a, $($name: FromV8<'a>),+> FromV8<'a> for ($($name,)+) {
+      type Error = StdAnyError;
+      fn from_v8(
+        scope: &mut v8::HandleScope<'a>,
+        value: v8::Local<'a, v8::Value>,
+      ) -> Result<Self, Self::Error> {
+        let arr = v8::Local::<v8::Array>::try_from(value)
+          .map_err(|_| ConvertError::wrong_type("array", &value))?;
+        if arr.length() != $len {
+          return Err(ConvertError::LengthMismatch {
+            expected: $len,
+            actual: arr.length() as usize,
+          }.into());
+        }
+        Ok(($(
+          {
+            let value = get_element(scope, arr, "Tuple", $idx)?;
+            trace::from_v8::<$name>(scope, value)
+              .map_err(|e| element_error($idx, e))?
+          },
+        )+))
+      }
+    }
+
+    impl<'a, $($name: ToV8<'a>),+> ToV8<'a> for PartialTuple<($($name,)+)> {
+      type Error = StdAnyError;
+      fn to_v8(
+        self,
+        scope: &mut v8::HandleScope<'a>,
+      ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+        self.0.to_v8(scope)
+      }
+    }
+
+    impl<'a, $($name: FromV8<'a>),+> FromV8<'a> for PartialTuple<($($name,)+)> {
+      type Error = StdAnyError;
+      fn from_v8(
+        scope: &mut v8::HandleScope<'a>,
+        value: v8::Local<'a, v8::Value>,
+      ) -> Result<Self, Self::Error> {
+        let arr = v8::Local::<v8::Array>::try_from(value)
+          .map_err(|_| ConvertError::wrong_type("array", &value))?;
+        let len = arr.length() as usize;
+        Ok(PartialTuple(($(
+          if $idx < len {
+            let value = get_element(scope, arr, "Tuple", $idx)?;
+            trace::from_v8::<$name>(scope, value)
+              .map_err(|e| element_error($idx, e))?
+          } else {
+            let undefined = v8::undefined(scope).into();
+            $name::try_from_v8(scope, undefined).ok_or_else(|| {
+              ConvertError::MissingElement { index: $idx }
+            })?
+          },
+        )+)))
+      }
+    }
+  };
+}
+
+impl_tuple!(1; A: 0);
+impl_tuple!(2; A: 0, B: 1);
+impl_tuple!(3; A: 0, B: 1, C: 2);
+impl_tuple!(4; A: 0, B: 1, C: 2, D: 3);
+impl_tuple!(5; A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_tuple!(6; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_tuple!(7; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_tuple!(8; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+/// Converts to and from a `[start, end]` array. Use [`RangeObject`] for a
+/// `{ start, end }` object instead.
+///
+/// Any `start` and `end` are accepted from JS, since a `Range` whose start is past its
+/// end is valid and empty.
+impl<'a, T: ToV8<'a>> ToV8<'a> for Range<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    (self.start, self.end).to_v8(scope)
+  }
+}
+
+impl<'a, T: FromV8<'a>> FromV8<'a> for Range<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let (start, end) = <(T, T)>::from_v8(scope, value)?;
+    Ok(start..end)
+  }
+}
+
+/// Converts to and from a `[start, end]` array, where `end` is included in the range.
+/// Use [`RangeObject`] for a `{ start, end }` object instead.
+///
+/// Converting from JS fails unless `start <= end`, so bounds that can't be compared,
+/// such as a NaN, are rejected too.
+impl<'a, T: ToV8<'a>> ToV8<'a> for RangeInclusive<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    self.into_inner().to_v8(scope)
+  }
+}
+
+impl<'a, T: FromV8<'a> + PartialOrd> FromV8<'a> for RangeInclusive<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let (start, end) = <(T, T)>::from_v8(scope, value)?;
+    inclusive_range(start, end)
+  }
+}
+
+fn inclusive_range<T: PartialOrd>(
+  start: T,
+  end: T,
+) -> Result<RangeInclusive<T>, StdAnyError> {
+  // Incomparable bounds, such as a NaN, are rejected too
+  if !matches!(
+    start.partial_cmp(&end),
+    Some(Ordering::Less | Ordering::Equal)
+  ) {
+    return Err(
+      ConvertError::out_of_range("Expected range start to be at most its end")
+        .into(),
+    );
+  }
+  Ok(start..=end)
+}
+
+/// Converts a [`Range`] or [`RangeInclusive`] to and from a `{ start, end }` object,
+/// rather than a `[start, end]` array. The same validation applies as for the range
+/// itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct RangeObject<R>(pub R);
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for RangeObject<Range<T>> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    range_object_to_v8(scope, self.0.start, self.0.end)
+  }
+}
+
+impl<'a, T: FromV8<'a>> FromV8<'a> for RangeObject<Range<T>> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let (start, end) = range_object_from_v8(scope, value)?;
+    Ok(RangeObject(start..end))
+  }
+}
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for RangeObject<RangeInclusive<T>> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let (start, end) = self.0.into_inner();
+    range_object_to_v8(scope, start, end)
+  }
+}
+
+impl<'a, T: FromV8<'a> + PartialOrd> FromV8<'a>
+  for RangeObject<RangeInclusive<T>>
+{
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let (start, end) = range_object_from_v8(scope, value)?;
+    inclusive_range(start, end).map(RangeObject)
+  }
+}
+
+fn range_object_to_v8<'a, T: ToV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  start: T,
+  end: T,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let object = v8::Object::new(scope);
+  let key = field_key(scope, "start")?;
+  let start =
+    trace::to_v8(scope, start).map_err(|e| field_error("start", e))?;
+  object.create_data_property(scope, key.into(), start);
+  let key = field_key(scope, "end")?;
+  let end = trace::to_v8(scope, end).map_err(|e| field_error("end", e))?;
+  object.create_data_property(scope, key.into(), end);
+  Ok(object.into())
+}
+
+fn range_object_from_v8<'a, T: FromV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) -> Result<(T, T), StdAnyError> {
+  let object = field_object(value)?;
+  let start = field_property(scope, object, "start", false)?;
+  let start =
+    trace::from_v8::<T>(scope, start).map_err(|e| field_error("start", e))?;
+  let end = field_property(scope, object, "end", false)?;
+  let end =
+    trace::from_v8::<T>(scope, end).map_err(|e| field_error("end", e))?;
+  Ok((start, end))
+}
+
+/// A span of text, such as a selection or the location of a diagnostic, converted to
+/// and from a `{ start, end }` object of offsets.
+///
+/// Spans are half-open: `start` is the offset of the first unit in the span and `end` is
+/// the offset just past the last one, so `end - start` is the length and an empty span
+/// has `start == end`, like a [`Range`] and like `String.prototype.slice` in JS. The
+/// unit is up to the op, but note that offsets from JS strings count UTF-16 code units
+/// rather than bytes or chars.
+///
+/// Unlike a [`RangeObject`], a span is always checked to have `start <= end`, in both
+/// directions, so an inverted span is an error rather than an empty range. Offsets must
+/// be integers in the safe integer range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TextSpan {
+  pub start: usize,
+  pub end: usize,
+}
+
+impl TextSpan {
+  /// The length of the span, or zero if it is inverted.
+  pub fn len(&self) -> usize {
+    self.end.saturating_sub(self.start)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Errors if the span is inverted.
+  fn check(self) -> Result<Self, StdAnyError> {
+    if self.start > self.end {
+      return Err(
+        ConvertError::invalid(format!(
+          "Expected span start to be at most its end, got {} > {}",
+          self.start, self.end
+        ))
+        .into(),
+      );
+    }
+    Ok(self)
+  }
+}
+
+impl From<TextSpan> for Range<usize> {
+  fn from(span: TextSpan) -> Self {
+    span.start..span.end
+  }
+}
+
+impl<'a> ToV8<'a> for TextSpan {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let TextSpan { start, end } = self.check()?;
+    range_object_to_v8(scope, SafeNumber(start), SafeNumber(end))
+  }
+}
+
+impl<'a> FromV8<'a> for TextSpan {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let (SafeNumber(start), SafeNumber(end)) =
+      range_object_from_v8(scope, value)?;
+    TextSpan { start, end }.check()
+  }
+}
+
+/// The arguments to a [`JsCallback`], as a tuple with one element per argument.
+///
+/// This is implemented for `()` and for tuples of up to eight [`ToV8`] elements. Each
+/// element becomes a separate argument rather than the tuple being passed as an array.
+pub trait CallbackArgs<'a> {
+  /// Converts each element to a V8 value, in order.
+  fn to_v8_args(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<Vec<v8::Local<'a, v8::Value>>, StdAnyError>;
+}
+
+impl<'a> CallbackArgs<'a> for () {
+  fn to_v8_args(
+    self,
+    _scope: &mut v8::HandleScope<'a>,
+  ) -> Result<Vec<v8::Local<'a, v8::Value>>, StdAnyError> {
+    Ok(vec![])
+  }
+}
+
+macro_rules! impl_callback_args {
+  ($($name:ident : $idx:tt),+) => {
+    impl<'a, $($name: ToV8<'a>),+> CallbackArgs<'a> for ($($name,)+) {
+      fn to_v8_args(
+        self,
+        scope: &mut v8::HandleScope<'a>,
+      ) -> Result<Vec<v8::Local<'a, v8::Value>>, StdAnyError> {
+        Ok(vec![
+          $(trace::to_v8(scope, self.$idx).map_err(|e| element_error($idx, e))?),+
+        ])
+      }
+    }
+  };
+}
+
+impl_callback_args!(A: 0);
+impl_callback_args!(A: 0, B: 1);
+impl_callback_args!(A: 0, B: 1, C: 2);
+impl_callback_args!(A: 0, B: 1, C: 2, D: 3);
+impl_callback_args!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_callback_args!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_callback_args!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_callback_args!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+/// A JS function that can be called from Rust with typed arguments and return value.
+///
+/// `Args` is a tuple of the argument types (see [`CallbackArgs`]), and `Ret` is the type
+/// the return value is converted to. Converting from JS only checks that the value is
+/// callable, so argument and return value mismatches are reported by [`call`](Self::call).
+///
+/// The function is held as a [`v8::Global`], so a `JsCallback` can be stored and called
+/// after the op that received it returns, as long as it is called on the same isolate.
+///
+/// ```ignore
+/// #[op2]
+/// fn op_map_value(
+///   scope: &mut v8::HandleScope,
+///   #[from_v8] f: JsCallback<(Smi<u32>,), Smi<u32>>,
+///   value: u32,
+/// ) -> Result<u32, AnyError> {
+///   Ok(f.call(scope, (Smi(value),))?.0)
+/// }
+/// ```
+pub struct JsCallback<Args, Ret> {
+  function: v8::Global<v8::Function>,
+  _marker: PhantomData<fn(Args) -> Ret>,
+}
+
+impl<Args, Ret> Clone for JsCallback<Args, Ret> {
+  fn clone(&self) -> Self {
+    JsCallback {
+      function: self.function.clone(),
+      _marker: PhantomData,
+    }
+  }
+}
+
+impl<Args, Ret> std::fmt::Debug for JsCallback<Args, Ret> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("JsCallback").finish_non_exhaustive()
+  }
+}
+
+impl<Args, Ret> JsCallback<Args, Ret> {
+  /// Wraps a function, to be called with `Args` and return `Ret`.
+  pub fn new(function: v8::Global<v8::Function>) -> Self {
+    JsCallback {
+      function,
+      _marker: PhantomData,
+    }
+  }
+
+  /// The underlying function.
+  pub fn function(&self) -> &v8::Global<v8::Function> {
+    &self.function
+  }
+
+  /// Calls the function with `args` and `undefined` as `this`, and converts its return
+  /// value.
+  ///
+  /// An exception thrown by the function is returned as a [`JsError`](crate::error::JsError).
+  /// A promise is converted like any other return value, and is not awaited.
+  pub fn call<'a>(
+    &self,
+    scope: &mut v8::HandleScope<'a>,
+    args: Args,
+  ) -> Result<Ret, anyhow::Error>
+  where
+    Args: CallbackArgs<'a>,
+    Ret: FromV8<'a>,
+  {
+    let args = args.to_v8_args(scope)?;
+    let scope = &mut v8::TryCatch::new(scope);
+    let function = v8::Local::new(scope, &self.function);
+    let this = v8::undefined(scope).into();
+    let Some(value) = function.call(scope, this, &args) else {
+      let exception = match scope.exception() {
+        Some(exception) => exception,
+        None => v8::undefined(scope).into(),
+      };
+      return crate::error::exception_to_err_result(
+        scope, exception, false, true,
+      );
+    };
+    Ret::from_v8(scope, value).map_err(into_anyhow)
+  }
+}
+
+impl<'a, Args, Ret> FromV8<'a> for JsCallback<Args, Ret> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(function) = v8::Local::<v8::Function>::try_from(value) else {
+      return Err(ConvertError::wrong_type("function", &value).into());
+    };
+    Ok(JsCallback::new(v8::Global::new(scope, function)))
+  }
+}
+
+impl<'a, T: ToV8<'a>, const N: usize> ToV8<'a> for [T; N] {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let mut elements = Vec::with_capacity(N);
+    for (i, value) in self.into_iter().enumerate() {
+      elements
+        .push(trace::to_v8(scope, value).map_err(|e| element_error(i, e))?);
+    }
+    Ok(v8::Array::new_with_elements(scope, &elements).into())
+  }
+}
+
+/// Converts the elements of `arr` in order, writing them into `out`, which must have the
+/// same length as `arr`.
+fn array_from_v8<'a, T: FromV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  arr: v8::Local<'a, v8::Array>,
+  kind: &str,
+  out: &mut [MaybeUninit<T>],
+) -> Result<(), StdAnyError> {
+  let mut guard = util::InitGuard::new(out);
+  for i in 0..guard.len() {
+    let value = get_element(scope, arr, kind, i)?;
+    guard.push(
+      trace::from_v8::<T>(scope, value).map_err(|e| element_error(i, e))?,
+    );
+  }
+  guard.finish();
+  Ok(())
+}
+
+/// Converts a JS array of exactly `N` elements, without a heap allocation.
+impl<'a, T: FromV8<'a>, const N: usize> FromV8<'a> for [T; N] {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let arr = v8::Local::<v8::Array>::try_from(value)
+      .map_err(|_| ConvertError::wrong_type("array", &value))?;
+    if arr.length() as usize != N {
+      return Err(
+        ConvertError::LengthMismatch {
+          expected: N,
+          actual: arr.length() as usize,
+        }
+        .into(),
+      );
+    }
+    // SAFETY: An array of `MaybeUninit` does not require initialization.
+    let mut out: [MaybeUninit<T>; N] =
+      unsafe { MaybeUninit::uninit().assume_init() };
+    array_from_v8(scope, arr, "Array", &mut out)?;
+    // SAFETY: All N elements have been initialized, and `[MaybeUninit<T>; N]` has the
+    // same layout as `[T; N]`.
+    Ok(unsafe { std::ptr::read(&out as *const _ as *const [T; N]) })
+  }
+}
+
+/// Converts each item into an element of a new `v8::Array`, in iteration order.
+///
+/// This collects the handles first and creates the array with one bulk call, at every
+/// length. V8 allocates the backing store of a bulk-created array at its final length,
+/// so the only intermediate allocation is the buffer of handles. Pre-sizing an array
+/// with `v8::Array::new` and storing elements one by one, like [`to_v8_array`] does,
+/// would avoid that buffer, but it also changes the result:
+///
+/// - `v8::Array::new` creates a holey array, and V8 never turns it back into a packed
+///   one once it is filled, so JS code that reads it later may take slower paths.
+/// - Each element has to be defined through a string key, which bulk creation does
+///   not need.
+///
+/// Conversions that want the pre-sized path anyway can call [`to_v8_array`] directly.
+fn iter_to_v8<'a, T: ToV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  iter: impl ExactSizeIterator<Item = T>,
+) -> Result<v8::Local<'a, v8::Array>, StdAnyError> {
+  let mut elements = Vec::with_capacity(iter.len());
+  for (i, value) in iter.enumerate() {
+    elements.push(trace::to_v8(scope, value).map_err(|e| element_error(i, e))?);
+  }
+  Ok(v8::Array::new_with_elements(scope, &elements))
+}
+
+/// Converts each item of `iter` straight into an element of a new `v8::Array`, in
+/// iteration order, without collecting them into an intermediate buffer first.
+///
+/// The `ToV8` impls for `Vec` and other collections build a temporary `Vec` of handles
+/// and create the array in a single bulk call. This builder stores one element at a time
+/// instead, so it avoids that allocation, which helps for iterators that would otherwise
+/// have to be collected just to be converted. The array is pre-sized from the
+/// iterator's lower size bound.
+///
+/// V8 treats a pre-sized array as holey even once every element is set, which can make
+/// later reads from JS slower. Each element is also defined through a string key, which
+/// the bulk path does not need. Prefer returning a `Vec` when the items are already
+/// collected or the array is read heavily from JS, and reach for this builder when
+/// collecting first would only add a copy; the `op_array_collect` and
+/// `op_array_builder` benches compare the two paths from 100 to 1,000,000 elements.
+///
+/// Elements are defined as own data properties, so an indexed setter on
+/// `Array.prototype` never observes them. If converting an element fails, the error
+/// names its index and the partially filled array is left for the garbage collector.
+pub fn to_v8_array<'a, I>(
+  scope: &mut v8::HandleScope<'a>,
+  iter: I,
+) -> Result<v8::Local<'a, v8::Array>, StdAnyError>
+where
+  I: IntoIterator,
+  I::Item: ToV8<'a>,
+{
+  let iter = iter.into_iter();
+  let len = iter.size_hint().0.min(i32::MAX as usize);
+  let array = v8::Array::new(scope, len as _);
+  for (i, value) in iter.enumerate() {
+    let value = trace::to_v8(scope, value).map_err(|e| element_error(i, e))?;
+    // Use `create_data_property` rather than `set_index` so that setters on
+    // `Array.prototype` cannot intercept the element
+    let defined = v8::Integer::new_from_unsigned(scope, i as _)
+      .to_string(scope)
+      .and_then(|key| array.create_data_property(scope, key.into(), value));
+    if defined != Some(true) {
+      return Err(
+        crate::error::type_error(format!("Array element {i} could not be set"))
+          .into(),
+      );
+    }
+  }
+  Ok(array)
+}
+
+/// Converts an iterator to a JS array with [`to_v8_array`], so that an op can return a
+/// computed sequence without collecting it into a `Vec` first:
+///
+/// ```ignore
+/// #[op2]
+/// #[to_v8]
+/// fn op_squares(#[smi] len: u32) -> Collect<impl Iterator<Item = Smi<u32>>> {
+///   Collect((0..len).map(|i| Smi(i * i)))
+/// }
+/// ```
+///
+/// The array is pre-sized from the iterator's lower size bound and filled one element at
+/// a time as the iterator is consumed, so the caveats of [`to_v8_array`] apply: the
+/// array is holey, and each element is defined through a string key.
+pub struct Collect<I>(pub I);
+
+impl<'a, I> ToV8<'a> for Collect<I>
+where
+  I: Iterator,
+  I::Item: ToV8<'a>,
+{
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(to_v8_array(scope, self.0)?.into())
+  }
+}
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for Vec<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8(scope, self.into_iter())?.into())
+  }
+}
+
+/// Converts from a JS array, converting each element in order.
+///
+/// Nested vectors compose, so for a `Vec<Vec<u32>>` each row goes through the impl for
+/// `Vec<u32>` and may be a typed array that is copied in bulk. Errors name the full
+/// index path to the element that failed, such as `at [1][2]: Expected u32`.
+impl<'a, T: FromV8<'a>> FromV8<'a> for Vec<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let arr = expect_array(value)?;
+    util::collect_exact(arr.length() as _, |i| {
+      let value = get_element(scope, arr, "Array", i)?;
+      trace::from_v8::<T>(scope, value).map_err(|e| element_error(i, e))
+    })
+  }
+}
+
+/// Casts `value` to an array, naming the type of the value in the error if it isn't one,
+/// including the kind of typed array, since those look like arrays from JS.
+fn expect_array(
+  value: v8::Local<v8::Value>,
+) -> Result<v8::Local<v8::Array>, StdAnyError> {
+  v8::Local::<v8::Array>::try_from(value)
+    .map_err(|_| ConvertError::wrong_type("array", &value).into())
+}
+
+/// A `Vec<T>` that converts from any array-like object: one with a `length` and
+/// indexed properties, like `arguments`, a `NodeList` or `{ 0: 'a', 1: 'b', length: 2 }`,
+/// rather than only from a `v8::Array`.
+///
+/// Real arrays convert like `Vec<T>`. For other objects, `length` is read and coerced in
+/// the same way as `Array.from` does: it is converted to a number (calling `valueOf` if
+/// it is an object) and truncated toward zero, and a missing `length`, `NaN` or a
+/// negative length means the object is empty. A length above `u32::MAX` is an error.
+/// Each index below the length is then read with an ordinary property get, so getters
+/// run and missing indices convert from `undefined`.
+///
+/// Primitives such as strings are not array-like objects and are rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ArrayLike<T>(pub Vec<T>);
+
+impl<'a, T: FromV8<'a>> FromV8<'a> for ArrayLike<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    if value.is_array() {
+      return Vec::<T>::from_v8(scope, value).map(ArrayLike);
+    }
+    let object = v8::Local::<v8::Object>::try_from(value)
+      .map_err(|_| ConvertError::wrong_type("array-like object", &value))?;
+    let key = field_key(scope, "length")?;
+    let length = object
+      .get(scope, key.into())
+      .and_then(|length| length.number_value(scope))
+      .ok_or_else(|| {
+        ConvertError::unreadable("Array-like length could not be read")
+      })?;
+    // `NaN` and negative lengths are 0, like `ToLength`
+    let length = if length > 0.0 { length.trunc() } else { 0.0 };
+    if length > u32::MAX as f64 {
+      return Err(
+        ConvertError::out_of_range(format!(
+          "Array-like length {length} is too large"
+        ))
+        .into(),
+      );
+    }
+    let length = length as usize;
+    // Unlike an array's, this length isn't backed by elements, so it only bounds the
+    // initial allocation
+    let mut out = Vec::with_capacity(length.min(MAX_RESERVED_ELEMENTS));
+    for i in 0..length {
+      let value = object.get_index(scope, i as _).ok_or_else(|| {
+        ConvertError::unreadable(format!(
+          "Array-like element {i} could not be read"
+        ))
+      })?;
+      out.push(
+        trace::from_v8::<T>(scope, value).map_err(|e| element_error(i, e))?,
+      );
+    }
+    Ok(ArrayLike(out))
+  }
+}
+
+/// A `Vec<T>` that converts from a JS array of at most `MAX` elements, for ops that
+/// take untrusted input.
+///
+/// An array like `new Array(2 ** 32 - 1)` is cheap to create in JS because it is
+/// sparse. Converting it to a `Vec<T>` only reserves room for a bounded number of
+/// elements, but if `T` accepts `undefined`, as `Option<String>` does, every hole is
+/// still converted and the `Vec` grows to tens of gigabytes. This checks the length
+/// before converting anything and errors with `Array too large` instead:
+///
+/// ```ignore
+/// #[op2]
+/// fn op_write_lines(#[from_v8] lines: BoundedVec<String, 4096>) {
+///   // ...
+/// }
+/// ```
+///
+/// Otherwise this converts exactly like `Vec<T>`. Only the outer array is bounded, so
+/// nest `BoundedVec`s to bound the elements as well.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BoundedVec<T, const MAX: usize>(pub Vec<T>);
+
+impl<'a, T: FromV8<'a>, const MAX: usize> FromV8<'a> for BoundedVec<T, MAX> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let arr = expect_array(value)?;
+    let len = arr.length() as usize;
+    if len > MAX {
+      return Err(
+        ConvertError::out_of_range(format!(
+          "Array too large: expected at most {MAX} elements, got {len}"
+        ))
+        .into(),
+      );
+    }
+    Vec::<T>::from_v8(scope, value).map(BoundedVec)
+  }
+}
+
+/// Converts a JS array like the `FromV8` impl for `Vec<T>`, but into `out` rather than
+/// a new `Vec`, so that an op called in a loop can keep reusing the same allocation.
+///
+/// `out` is cleared first and keeps its capacity, growing only if the array has more
+/// elements than it can already hold. If any element fails to convert, `out` is left
+/// empty, still with its capacity, and the error names the index of that element.
+///
+/// ```ignore
+/// thread_local! {
+///   static POINTS: RefCell<Vec<Point>> = const { RefCell::new(Vec::new()) };
+/// }
+///
+/// #[op2]
+/// fn op_draw<'s>(
+///   scope: &mut v8::HandleScope<'s>,
+///   value: v8::Local<'s, v8::Value>,
+/// ) -> Result<(), AnyError> {
+///   POINTS.with_borrow_mut(|points| {
+///     from_v8_into(scope, value, points)?;
+///     draw(points);
+///     Ok(())
+///   })
+/// }
+/// ```
+pub fn from_v8_into<'a, T: FromV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+  out: &mut Vec<T>,
+) -> Result<(), StdAnyError> {
+  out.clear();
+  let arr = expect_array(value)?;
+  let len = arr.length() as usize;
+  out.reserve(len.min(MAX_RESERVED_ELEMENTS));
+  for i in 0..len {
+    let converted = get_element(scope, arr, "Array", i).and_then(|value| {
+      trace::from_v8::<T>(scope, value).map_err(|e| element_error(i, e))
+    });
+    match converted {
+      Ok(value) => out.push(value),
+      Err(err) => {
+        out.clear();
+        return Err(err);
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Converts the elements of a JS array one at a time, in order, passing each one to `f`
+/// along with its index instead of collecting them into a `Vec`.
+///
+/// This lets an op fold over a very large array without holding every converted element
+/// at once. Each chunk of elements is read in its own `v8::HandleScope`, so the handles
+/// for elements that have already been passed to `f` are released as well, which is why
+/// `T` can't borrow from the scope.
+///
+/// Stops at the first element that fails to convert, reporting its index, or at the
+/// first error returned by `f`, which is returned unchanged.
+///
+/// ```ignore
+/// #[op2]
+/// fn op_sum<'s>(
+///   scope: &mut v8::HandleScope<'s>,
+///   value: v8::Local<'s, v8::Value>,
+/// ) -> Result<f64, AnyError> {
+///   let mut sum = 0.0;
+///   for_each_from_v8(scope, value, |_, Number(n): Number<f64>| {
+///     sum += n;
+///     Ok(())
+///   })?;
+///   Ok(sum)
+/// }
+/// ```
+pub fn for_each_from_v8<'a, T>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+  mut f: impl FnMut(usize, T) -> Result<(), anyhow::Error>,
+) -> Result<(), StdAnyError>
+where
+  T: for<'s> FromV8<'s>,
+{
+  const CHUNK_SIZE: usize = 1024;
+  let arr = v8::Local::<v8::Array>::try_from(value)
+    .map_err(|_| ConvertError::wrong_type("array", &value))?;
+  let len = arr.length() as usize;
+  for start in (0..len).step_by(CHUNK_SIZE) {
+    let scope = &mut v8::HandleScope::new(scope);
+    let arr = v8::Local::new(scope, arr);
+    for i in start..len.min(start + CHUNK_SIZE) {
+      let value = get_element(scope, arr, "Array", i)?;
+      let value =
+        trace::from_v8::<T>(scope, value).map_err(|e| element_error(i, e))?;
+      f(i, value)?;
+    }
+  }
+  Ok(())
+}
+
+/// A primitive numeric type that can be copied out of a typed array's buffer as bytes.
+trait TypedArrayPrimitive: Copy + Default {}
+
+impl TypedArrayPrimitive for u8 {}
+impl TypedArrayPrimitive for i8 {}
+impl TypedArrayPrimitive for u16 {}
+impl TypedArrayPrimitive for i16 {}
+impl TypedArrayPrimitive for u32 {}
+impl TypedArrayPrimitive for i32 {}
+impl TypedArrayPrimitive for f32 {}
+impl TypedArrayPrimitive for f64 {}
+
+/// An element type of a JS typed array that can be copied out of its buffer in bulk.
+trait TypedArrayElement: TypedArrayPrimitive {
+  const NAME: &'static str;
+  fn is_typed_array(value: &v8::Value) -> bool;
+  /// Converts the elements of a typed array with a narrower element type that this type
+  /// represents exactly, or returns `None` for any other view.
+  fn widen(view: v8::Local<v8::ArrayBufferView>) -> Option<Vec<Self>>;
+  /// Converts a single element of a plain JS array.
+  fn from_element<'a>(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, StdAnyError>;
+}
+
+macro_rules! impl_typed_array_element {
+  ($($t:ty : $name:literal $is:ident $wrapper:ident [$($src:ty : $is_src:ident),*]),* $(,)?) => {
+    $(
+      impl TypedArrayElement for $t {
+        const NAME: &'static str = $name;
+        #[inline(always)]
+        fn is_typed_array(value: &v8::Value) -> bool {
+          value.$is()
+        }
+        #[allow(unused_variables)]
+        fn widen(view: v8::Local<v8::ArrayBufferView>) -> Option<Vec<Self>> {
+          $(
+            if view.$is_src() {
+              let elements = copy_to_vec::<$src>(view.byte_length(), |bytes| {
+                view.copy_contents(bytes);
+              });
+              return Some(elements.into_iter().map(|v| v as $t).collect());
+            }
+          )*
+          None
+        }
+        #[inline(always)]
+        fn from_element<'a>(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, StdAnyError> {
+          $wrapper::<$t>::from_v8(scope, value).map(|$wrapper(v)| v)
+        }
+      }
+
+      #[doc = concat!("Converts from a JS `", $name, "`, or from a plain array of numbers.")]
+      ///
+      /// Typed arrays are copied out of their backing buffer in a single bulk copy, in
+      /// native byte order, rather than element by element. Converting a view of a
+      /// detached `ArrayBuffer` fails rather than producing an empty `Vec`.
+      ///
+      /// Typed arrays with a narrower element type that converts exactly, such as a
+      /// `Uint16Array` for a `Vec<u32>`, are accepted and widened. Any other typed array is
+      /// rejected up front with an error naming both array types.
+      impl<'a> FromV8<'a> for Vec<$t> {
+        type Error = StdAnyError;
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          typed_vec_from_v8(scope, value)
+        }
+      }
+    )*
+  };
+}
+
+impl_typed_array_element!(
+  u8: "Uint8Array" is_uint8_array SafeNumber [],
+  u32: "Uint32Array" is_uint32_array SafeNumber [
+    u8: is_uint8_array, u8: is_uint8_clamped_array, u16: is_uint16_array
+  ],
+  f32: "Float32Array" is_float32_array Number [
+    u8: is_uint8_array, u8: is_uint8_clamped_array, i8: is_int8_array,
+    u16: is_uint16_array, i16: is_int16_array
+  ],
+  f64: "Float64Array" is_float64_array Number [
+    u8: is_uint8_array, u8: is_uint8_clamped_array, i8: is_int8_array,
+    u16: is_uint16_array, i16: is_int16_array, u32: is_uint32_array,
+    i32: is_int32_array, f32: is_float32_array
+  ],
+);
+
+/// The name of the JS type of `value`, for error messages such as `Expected u32, got
+/// string`.
+///
+/// Primitives are named like `typeof` names them, except that `null` is `"null"`
+/// rather than `"object"`. Arrays are `"array"`, functions are `"function"`, typed
+/// arrays and `DataView`s are named by their constructor, such as `"Uint8Array"`, and
+/// any other object is `"object"`.
+pub fn js_type_name(value: &v8::Value) -> &'static str {
+  if value.is_undefined() {
+    "undefined"
+  } else if value.is_null() {
+    "null"
+  } else if value.is_boolean() {
+    "boolean"
+  } else if value.is_number() {
+    "number"
+  } else if value.is_big_int() {
+    "bigint"
+  } else if value.is_string() {
+    "string"
+  } else if value.is_symbol() {
+    "symbol"
+  } else if value.is_function() {
+    "function"
+  } else if value.is_array() {
+    "array"
+  } else {
+    view_name(value).unwrap_or("object")
+  }
+}
+
+/// The constructor name of a typed array or `DataView`.
+fn view_name(value: &v8::Value) -> Option<&'static str> {
+  Some(if value.is_uint8_array() {
+    "Uint8Array"
+  } else if value.is_uint8_clamped_array() {
+    "Uint8ClampedArray"
+  } else if value.is_int8_array() {
+    "Int8Array"
+  } else if value.is_uint16_array() {
+    "Uint16Array"
+  } else if value.is_int16_array() {
+    "Int16Array"
+  } else if value.is_uint32_array() {
+    "Uint32Array"
+  } else if value.is_int32_array() {
+    "Int32Array"
+  } else if value.is_float32_array() {
+    "Float32Array"
+  } else if value.is_float64_array() {
+    "Float64Array"
+  } else if value.is_big_int64_array() {
+    "BigInt64Array"
+  } else if value.is_big_uint64_array() {
+    "BigUint64Array"
+  } else if value.is_data_view() {
+    "DataView"
+  } else {
+    return None;
+  })
+}
+
+fn detached_error() -> StdAnyError {
+  ConvertError::Detached.into()
+}
+
+/// Fails if the buffer behind `view` was detached, such as by transferring it to a
+/// worker. A detached view reports a length of zero, so without this check it would
+/// silently convert to an empty `Vec`.
+fn check_detached(
+  scope: &mut v8::HandleScope,
+  view: v8::Local<v8::ArrayBufferView>,
+) -> Result<(), StdAnyError> {
+  match view.buffer(scope) {
+    Some(buffer) if buffer.was_detached() => Err(detached_error()),
+    _ => Ok(()),
+  }
+}
+
+/// Fills a new `Vec<T>` from `byte_length` bytes using `copy`. Any trailing bytes that do not
+/// make up a whole `T` are ignored.
+fn copy_to_vec<T: TypedArrayPrimitive>(
+  byte_length: usize,
+  copy: impl FnOnce(&mut [u8]),
+) -> Vec<T> {
+  let len = byte_length / std::mem::size_of::<T>();
+  let mut out = vec![T::default(); len];
+  // SAFETY: `out` holds `len` initialized elements, and every bit pattern is a valid
+  // value for the primitive numeric types that implement `TypedArrayPrimitive`.
+  let bytes = unsafe {
+    std::slice::from_raw_parts_mut(
+      out.as_mut_ptr() as *mut u8,
+      len * std::mem::size_of::<T>(),
+    )
+  };
+  copy(bytes);
+  out
+}
+
+fn typed_vec_from_v8<'a, T: TypedArrayElement>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) -> Result<Vec<T>, StdAnyError> {
+  if let Ok(arr) = v8::Local::<v8::Array>::try_from(value) {
+    let len = arr.length() as usize;
+    let mut out = Vec::with_capacity(len.min(MAX_RESERVED_ELEMENTS));
+    for i in 0..len {
+      let value = get_element(scope, arr, "Array", i)?;
+      out.push(T::from_element(scope, value).map_err(|e| element_error(i, e))?);
+    }
+    return Ok(out);
+  }
+  // A `Vec<u8>` can be read from the bytes of any view, or of a bare `ArrayBuffer`
+  let is_bytes = std::mem::size_of::<T>() == 1;
+  if T::is_typed_array(&value) || (is_bytes && value.is_array_buffer_view()) {
+    let view = v8::Local::<v8::ArrayBufferView>::try_from(value)
+      .map_err(|_| ConvertError::wrong_type(T::NAME, &value))?;
+    check_detached(scope, view)?;
+    return Ok(copy_to_vec(view.byte_length(), |bytes| {
+      view.copy_contents(bytes);
+    }));
+  }
+  if is_bytes {
+    if let Ok(buffer) = v8::Local::<v8::ArrayBuffer>::try_from(value) {
+      if buffer.was_detached() {
+        return Err(detached_error());
+      }
+      let Some(data) = buffer.data() else {
+        return Ok(vec![]);
+      };
+      return Ok(copy_to_vec(buffer.byte_length(), |bytes| {
+        // SAFETY: `data` points to `byte_length` bytes owned by the buffer, which
+        // cannot be freed or detached while we hold a handle to it.
+        bytes.copy_from_slice(unsafe {
+          std::slice::from_raw_parts(data.as_ptr() as *const u8, bytes.len())
+        });
+      }));
+    }
+    return Err(
+      ConvertError::wrong_type(
+        "ArrayBuffer, ArrayBufferView, or array",
+        &value,
+      )
+      .into(),
+    );
+  }
+  // Check the element type once, rather than failing on the first element
+  if let Ok(view) = v8::Local::<v8::ArrayBufferView>::try_from(value) {
+    check_detached(scope, view)?;
+    if let Some(out) = T::widen(view) {
+      return Ok(out);
+    }
+    return Err(ConvertError::wrong_type(T::NAME, &value).into());
+  }
+  Err(ConvertError::wrong_type(format!("{} or array", T::NAME), &value).into())
+}
+
+/// Wraps a backing store in a new `Uint8Array` covering all of it.
+fn backing_store_to_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  backing_store: v8::UniqueRef<v8::BackingStore>,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let len = backing_store.byte_length();
+  let buffer =
+    v8::ArrayBuffer::with_backing_store(scope, &backing_store.make_shared());
+  v8::Uint8Array::new(scope, buffer, 0, len)
+    .map(Into::into)
+    .ok_or_else(|| {
+      crate::error::type_error("Failed to allocate Uint8Array").into()
+    })
+}
+
+/// Converts to a `Uint8Array` that takes ownership of the `Vec`'s allocation rather
+/// than copying it. The allocation is freed when V8 garbage collects the buffer.
+///
+/// Unlike `Vec::into_boxed_slice`, this never reallocates to drop spare capacity.
+impl<'a> ToV8<'a> for Vec<u8> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    // Boxing the `Vec` itself keeps its capacity around for the deleter
+    let backing_store =
+      v8::ArrayBuffer::new_backing_store_from_bytes(Box::new(self));
+    backing_store_to_v8(scope, backing_store)
+  }
+}
+
+/// Converts to a `Uint8Array` that takes ownership of the allocation rather than
+/// copying it. The allocation is freed when V8 garbage collects the buffer.
+impl<'a> ToV8<'a> for Box<[u8]> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let backing_store =
+      v8::ArrayBuffer::new_backing_store_from_boxed_slice(self);
+    backing_store_to_v8(scope, backing_store)
+  }
+}
+
+/// Converts to a `Uint8Array`. A uniquely owned `Bytes` is not copied: the backing store
+/// holds on to it, and it is dropped when V8 garbage collects the buffer, even if the
+/// buffer was detached first. A `Bytes` that shares its memory with other handles, or
+/// that is backed by a static slice, is copied so that JS can't write through to them.
+impl<'a> ToV8<'a> for Bytes {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    // Nothing else can clone this handle while we own it, so it stays unique. Empty
+    // buffers go through `Vec` too, which gives them V8's empty backing store.
+    if self.is_empty() || !self.is_unique() {
+      return self.to_vec().to_v8(scope);
+    }
+
+    extern "C" fn drop_bytes(
+      _ptr: *mut c_void,
+      _len: usize,
+      data: *mut c_void,
+    ) {
+      // SAFETY: `data` is the boxed `Bytes` passed to the backing store below
+      drop(unsafe { Box::from_raw(data as *mut Bytes) });
+    }
+
+    let ptr = self.as_ptr() as *mut c_void;
+    let len = self.len();
+    let data = Box::into_raw(Box::new(self)) as *mut c_void;
+    // SAFETY: The memory is kept alive by the uniquely owned `Bytes` until the deleter
+    // runs, and no other handle can observe writes from JS
+    let backing_store = unsafe {
+      v8::ArrayBuffer::new_backing_store_from_ptr(ptr, len, drop_bytes, data)
+    };
+    backing_store_to_v8(scope, backing_store)
+  }
+}
+
+/// Converts from a `Uint8Array` or an array of bytes by copying it, with the same rules
+/// as `Vec<u8>`.
+impl<'a> FromV8<'a> for Bytes {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Vec::<u8>::from_v8(scope, value).map(Bytes::from)
+  }
+}
+
+/// Converts to a `Uint8Array` that takes ownership of the buffer rather than copying it.
+/// The buffer is dropped when V8 garbage collects the `ArrayBuffer`.
+impl<'a> ToV8<'a> for BytesMut {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let backing_store =
+      v8::ArrayBuffer::new_backing_store_from_bytes(Box::new(self));
+    backing_store_to_v8(scope, backing_store)
+  }
+}
+
+/// Converts from a `Uint8Array` or an array of bytes by copying it, with the same rules
+/// as `Vec<u8>`.
+impl<'a> FromV8<'a> for BytesMut {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Vec::<u8>::from_v8(scope, value).map(|vec| BytesMut::from(vec.as_slice()))
+  }
+}
+
+/// Converts to a JS array in front-to-back order, the same order as [`VecDeque::iter`].
+impl<'a, T: ToV8<'a>> ToV8<'a> for VecDeque<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8(scope, self.into_iter())?.into())
+  }
+}
+
+/// Converts from a JS array, with the first element at the front of the deque.
+impl<'a, T: FromV8<'a>> FromV8<'a> for VecDeque<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    // Converting from a `Vec` reuses its allocation
+    Vec::<T>::from_v8(scope, value).map(VecDeque::from)
+  }
+}
+
+/// Converts to a JS array in front-to-back order.
+impl<'a, T: ToV8<'a>> ToV8<'a> for LinkedList<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8(scope, self.into_iter())?.into())
+  }
+}
+
+/// Converts from a JS array, with the first element at the front of the list.
+impl<'a, T: FromV8<'a>> FromV8<'a> for LinkedList<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Vec::<T>::from_v8(scope, value).map(|vec| vec.into_iter().collect())
+  }
+}
+
+/// Converts to a JS array sorted in descending order, so the first element is the one
+/// [`BinaryHeap::pop`] would have returned first. Elements that compare equal may be in
+/// any order relative to each other.
+///
+/// The heap is sorted on the Rust side, in `O(n log n)`, rather than exposing its
+/// internal layout, which is unspecified.
+impl<'a, T: ToV8<'a> + Ord> ToV8<'a> for BinaryHeap<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8(scope, self.into_sorted_vec().into_iter().rev())?.into())
+  }
+}
+
+/// Converts from a JS array in any order, building the heap in place in `O(n)`.
+impl<'a, T: FromV8<'a> + Ord> FromV8<'a> for BinaryHeap<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    // Converting from a `Vec` reuses its allocation
+    Vec::<T>::from_v8(scope, value).map(BinaryHeap::from)
+  }
+}
+
+impl<'a, T: ToV8<'a>> ToV8<'a> for Box<[T]> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8(scope, self.into_vec().into_iter())?.into())
+  }
+}
+
+/// Converts the same way as the equivalent `Vec`, including the bulk copy from typed arrays.
+///
+/// The `Vec` is allocated with exactly the length of the array, so turning it into a
+/// boxed slice never reallocates. Prefer this over `Vec<T>` for data that won't grow: it
+/// is a word smaller, and the fixed length is part of the type. If an element fails to
+/// convert, the elements converted before it are dropped and the allocation is freed,
+/// the same as for `Vec<T>`.
+impl<'a, T> FromV8<'a> for Box<[T]>
+where
+  Vec<T>: FromV8<'a>,
+{
+  type Error = <Vec<T> as FromV8<'a>>::Error;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    Vec::<T>::from_v8(scope, value).map(Vec::into_boxed_slice)
+  }
+}
+
+/// Converts a borrowed slice to a JS array, cloning each element into the conversion.
+///
+/// The elements are converted by value, so this requires `T: Clone`. For `Copy` element
+/// types the clone is just a copy, and this avoids building a temporary `Vec` with
+/// `.to_vec()` first. For element types with expensive clones, such as `String`, the cost
+/// is the same as `.to_vec()`.
+impl<'a, 's, T: ToV8<'a> + Clone> ToV8<'a> for &'s [T] {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8(scope, self.iter().cloned())?.into())
+  }
+}
+
+/// Converts to a `Uint8Array` over a copy of the bytes, in the same way as `Vec<u8>`.
+impl<'a, 's> ToV8<'a> for &'s [u8] {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    self.to_vec().to_v8(scope)
+  }
+}
+
+/// Converts to a `Uint8Array`. An owned `Vec` is handed to V8 without copying, like
+/// `Vec<u8>`, but borrowed bytes are always copied.
+///
+/// A borrow can't be shared with V8 safely, even for a `&'static [u8]`: the buffer lives
+/// until it is garbage collected, which can be long after the borrow ends, and JS can
+/// write to it through the `Uint8Array`. Use [`Bytes`] to share immutable data with V8
+/// by reference counting instead.
+impl<'a, 's> ToV8<'a> for Cow<'s, [u8]> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    match self {
+      Cow::Borrowed(bytes) => bytes.to_v8(scope),
+      Cow::Owned(bytes) => bytes.to_v8(scope),
+    }
+  }
+}
+
+/// Converts from the same values as `Vec<u8>`.
+///
+/// Like `Cow<str>`, only empty input is returned as [`Cow::Borrowed`]. Borrowing the
+/// contents of a JS buffer isn't sound here, since the buffer can be detached, resized
+/// or written to by JS while the borrow is alive, and nothing ties the borrow to the
+/// buffer's lifetime. Use a `#[buffer]` op argument to borrow a buffer for the duration
+/// of an op call instead.
+impl<'a, 's> FromV8<'a> for Cow<'s, [u8]> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let bytes = Vec::<u8>::from_v8(scope, value)?;
+    if bytes.is_empty() {
+      return Ok(Cow::Borrowed(&[]));
+    }
+    Ok(Cow::Owned(bytes))
+  }
+}
+
+macro_rules! impl_shared_slice {
+  ($($ty:ident),*) => {
+    $(
+      /// Converts to a JS array by cloning each element, since the slice may be shared.
+      impl<'a, T: ToV8<'a> + Clone> ToV8<'a> for $ty<[T]> {
+        type Error = StdAnyError;
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          Ok(iter_to_v8(scope, self.iter().cloned())?.into())
+        }
+      }
+
+      /// Converts the same way as the equivalent `Vec`, including the bulk copy from typed
+      /// arrays, then moves the elements into a new allocation. The result never shares
+      /// memory with the JS value, so later changes on either side are not visible to the
+      /// other.
+      impl<'a, T> FromV8<'a> for $ty<[T]>
+      where
+        Vec<T>: FromV8<'a>,
+      {
+        type Error = <Vec<T> as FromV8<'a>>::Error;
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          Vec::<T>::from_v8(scope, value).map(Into::into)
+        }
+      }
+    )*
+  };
+}
+
+impl_shared_slice!(Rc, Arc);
+
+/// Converts each item into an element of a new `v8::Set`, in iteration order.
+fn iter_to_v8_set<'a, T: ToV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  iter: impl Iterator<Item = T>,
+) -> Result<v8::Local<'a, v8::Set>, StdAnyError> {
+  let set = v8::Set::new(scope);
+  for (i, value) in iter.enumerate() {
+    let value = trace::to_v8(scope, value).map_err(|e| element_error(i, e))?;
+    set.add(scope, value).ok_or_else(|| {
+      crate::error::type_error(format!("Set element {i} could not be added"))
+    })?;
+  }
+  Ok(set)
+}
+
+/// Converts the elements of a JS `Set` or array into a collection, in iteration order.
+fn set_from_v8<'a, T: FromV8<'a>, C: Default + Extend<T>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) -> Result<C, StdAnyError> {
+  let arr = if let Ok(set) = v8::Local::<v8::Set>::try_from(value) {
+    set.as_array(scope)
+  } else if let Ok(arr) = v8::Local::<v8::Array>::try_from(value) {
+    arr
+  } else {
+    return Err(ConvertError::wrong_type("Set or array", &value).into());
+  };
+  let mut out = C::default();
+  for i in 0..arr.length() as usize {
+    let value = get_element(scope, arr, "Set", i)?;
+    let value =
+      trace::from_v8::<T>(scope, value).map_err(|e| element_error(i, e))?;
+    out.extend(std::iter::once(value));
+  }
+  Ok(out)
+}
+
+/// Converts to a JS `Set`. The iteration order of the JS `Set` follows the iteration
+/// order of the `HashSet`, which is unspecified.
+///
+/// Distinct Rust values that convert to the same JS primitive are merged by the JS `Set`.
+impl<'a, T: ToV8<'a>, H> ToV8<'a> for HashSet<T, H> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8_set(scope, self.into_iter())?.into())
+  }
+}
+
+/// Converts from a JS `Set`, or from an array where any duplicate elements are merged.
+impl<'a, T, H> FromV8<'a> for HashSet<T, H>
+where
+  T: FromV8<'a> + Eq + Hash,
+  H: BuildHasher + Default,
+{
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    set_from_v8(scope, value)
+  }
+}
+
+/// Converts to a JS `Set`, with elements inserted in ascending order.
+///
+/// Distinct Rust values that convert to the same JS primitive are merged by the JS `Set`.
+impl<'a, T: ToV8<'a>> ToV8<'a> for BTreeSet<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8_set(scope, self.into_iter())?.into())
+  }
+}
+
+/// Converts from a JS `Set`, or from an array where any duplicate elements are merged.
+impl<'a, T: FromV8<'a> + Ord> FromV8<'a> for BTreeSet<T> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    set_from_v8(scope, value)
+  }
+}
+
+/// Wraps an error from converting a struct field, for use by `#[derive(ToV8)]`
+/// and `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn field_error<E: std::error::Error + Send + Sync + 'static>(
+  name: &str,
+  err: E,
+) -> StdAnyError {
+  key_error(name, err)
+}
+
+/// Passes on an error from converting a `#[v8(flatten)]` field, for use by
+/// `#[derive(FromV8)]`. The field is read from the same object as its parent, so its
+/// errors already name the path from there.
+#[doc(hidden)]
+pub fn flatten_error<E: std::error::Error + Send + Sync + 'static>(
+  err: E,
+) -> StdAnyError {
+  StdAnyError(into_anyhow(err))
+}
+
+/// Creates an internalized object key for a struct field, for use by `#[derive(ToV8)]`
+/// and `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn field_key<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  name: &'static str,
+) -> Result<v8::Local<'a, v8::String>, StdAnyError> {
+  v8::String::new_from_utf8(
+    scope,
+    name.as_bytes(),
+    v8::NewStringType::Internalized,
+  )
+  .ok_or_else(|| {
+    crate::error::type_error(format!("Failed to create key '{name}'")).into()
+  })
+}
+
+/// Checks that a value is an array with one element per field, for use by
+/// `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn field_array(
+  value: v8::Local<v8::Value>,
+  len: usize,
+) -> Result<v8::Local<v8::Array>, StdAnyError> {
+  let Ok(array) = v8::Local::<v8::Array>::try_from(value) else {
+    return Err(ConvertError::wrong_type("array", &value).into());
+  };
+  if array.length() as usize != len {
+    return Err(
+      ConvertError::LengthMismatch {
+        expected: len,
+        actual: array.length() as usize,
+      }
+      .into(),
+    );
+  }
+  Ok(array)
+}
+
+/// Reads the element for a struct field, for use by `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn field_element<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  array: v8::Local<'a, v8::Array>,
+  index: u32,
+  name: &str,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  array.get_index(scope, index).ok_or_else(|| {
+    ConvertError::unreadable(format!("Field '{name}' could not be read")).into()
+  })
+}
+
+/// Checks that a value is an object, for use by `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn field_object(
+  value: v8::Local<v8::Value>,
+) -> Result<v8::Local<v8::Object>, StdAnyError> {
+  v8::Local::<v8::Object>::try_from(value)
+    .map_err(|_| ConvertError::wrong_type("object", &value).into())
+}
+
+/// Reads the property for a struct field, for use by `#[derive(FromV8)]`. A
+/// missing property is an error unless the field is optional.
+#[doc(hidden)]
+pub fn field_property<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  object: v8::Local<'a, v8::Object>,
+  name: &'static str,
+  optional: bool,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let key = field_key(scope, name)?;
+  read_field(scope, object, key.into(), name, optional)
+}
+
+/// Reads the property for a struct field under the key at `index` of `keys`, which
+/// came from [`field_keys`], for use by `#[derive(FromV8)]`. A missing property is an
+/// error unless the field is optional.
+#[doc(hidden)]
+pub fn field_property_at<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  object: v8::Local<'a, v8::Object>,
+  keys: &[v8::Local<'a, v8::String>],
+  index: usize,
+  name: &'static str,
+  optional: bool,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  read_field(scope, object, keys[index].into(), name, optional)
+}
+
+/// Reads the symbol-keyed property for a struct field marked `#[v8(symbol)]`, for use
+/// by `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn field_symbol_property<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  object: v8::Local<'a, v8::Object>,
+  key: SymbolKey,
+  name: &'static str,
+  optional: bool,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let key = key.symbol(scope)?;
+  read_field(scope, object, key.into(), name, optional)
+}
+
+fn read_field<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  object: v8::Local<'a, v8::Object>,
+  key: v8::Local<'a, v8::Value>,
+  name: &'static str,
+  optional: bool,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let value = object.get(scope, key).ok_or_else(|| {
+    ConvertError::unreadable(format!("Field '{name}' could not be read"))
+  })?;
+  if value.is_undefined() && !optional {
+    return Err(ConvertError::MissingField { name: name.into() }.into());
+  }
+  Ok(value)
+}
+
+/// Reads the tag identifying an enum variant, for use by `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn variant_tag(
+  scope: &mut v8::HandleScope,
+  value: v8::Local<v8::Value>,
+) -> Result<String, StdAnyError> {
+  let Ok(tag) = v8::Local::<v8::String>::try_from(value) else {
+    return Err(ConvertError::wrong_type("string tag", &value).into());
+  };
+  Ok(tag.to_rust_string_lossy(scope))
+}
+
+/// Reads the discriminant of a `#[v8(discriminant)]` enum, for use by
+/// `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn discriminant_from_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) -> Result<i32, StdAnyError> {
+  CheckedSmi::<i32>::from_v8(scope, value).map(|CheckedSmi(value)| value)
+}
+
+/// Creates the error for a discriminant that doesn't match any variant, for use by
+/// `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn unknown_discriminant(
+  discriminant: i32,
+  variants: &[(&str, i32)],
+) -> StdAnyError {
+  let valid = variants
+    .iter()
+    .map(|(name, value)| format!("{name} = {value}"))
+    .collect::<Vec<_>>()
+    .join(", ");
+  ConvertError::invalid(format!(
+    "Unknown discriminant {discriminant}, expected one of: {valid}"
+  ))
+  .into()
+}
+
+/// Creates the error for an unrecognized enum variant tag, for use by
+/// `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn unknown_variant(tag: &str) -> StdAnyError {
+  ConvertError::invalid(format!("Unknown variant '{tag}'")).into()
+}
@@ -0,0 +1,195 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for one- and two-dimensional [`ndarray`] arrays of `u8`, `u32`, `f32`
+//! and `f64`, behind the `ndarray` feature.
+//!
+//! An `Array1` converts to a typed array of its element type, such as a `Float64Array`
+//! for an `Array1<f64>`. An `Array2` converts to a `{ data, shape }` object, where
+//! `shape` is `[rows, columns]` and `data` is a typed array holding the elements in
+//! row-major order, one row after another, which is the standard layout of `ndarray`.
+//! The elements are copied in bulk, rather than one by one, in both directions.
+//!
+//! Only arrays in standard layout, with their elements contiguous in row-major order,
+//! can be converted to JS. A strided view, such as one sliced with `s![..;2]`, or a
+//! transposed array is an error rather than being gathered element by element: call
+//! `as_standard_layout()` on it first.
+//!
+//! Converting from JS accepts the same values for the elements as a `Vec` of the
+//! element type, including plain arrays of numbers and typed arrays with a narrower
+//! element type, and the length of `data` must match the `shape`.
+
+use super::field_error;
+use super::field_object;
+use super::field_property;
+use super::trace;
+use super::typed_vec_from_v8;
+use super::ConvertError;
+use super::FromV8;
+use super::ObjectBuilder;
+use super::ObjectKeys;
+use super::SafeNumber;
+use super::ToV8;
+use super::TypedArrayElement;
+use crate::error::StdAnyError;
+use ndarray::Array1;
+use ndarray::Array2;
+use ndarray::ArrayView1;
+use ndarray::ArrayView2;
+
+static MATRIX_KEYS: ObjectKeys = ObjectKeys::new(&["data", "shape"]);
+
+/// Creates a typed array of `len` elements over the whole of `buffer`.
+type NewTypedArray<'a> = fn(
+  &mut v8::HandleScope<'a>,
+  v8::Local<'a, v8::ArrayBuffer>,
+  usize,
+) -> Option<v8::Local<'a, v8::Value>>;
+
+/// Copies `elements` into a new typed array, or fails if the array they came from isn't
+/// contiguous, in which case `elements` is `None`.
+fn elements_to_v8<'a, T: TypedArrayElement>(
+  scope: &mut v8::HandleScope<'a>,
+  elements: Option<&[T]>,
+  new: NewTypedArray<'a>,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let Some(elements) = elements else {
+    return Err(
+      ConvertError::invalid(
+        "Expected an array in standard layout, with its elements contiguous in row-major order",
+      )
+      .into(),
+    );
+  };
+  let mut bytes = vec![0u8; std::mem::size_of_val(elements)];
+  // SAFETY: `bytes` is exactly as long as `elements`, and the primitive numeric types
+  // that implement `TypedArrayElement` have no padding, so every byte is initialized.
+  unsafe {
+    std::ptr::copy_nonoverlapping(
+      elements.as_ptr() as *const u8,
+      bytes.as_mut_ptr(),
+      bytes.len(),
+    );
+  }
+  let backing_store =
+    v8::ArrayBuffer::new_backing_store_from_bytes(bytes).make_shared();
+  let buffer = v8::ArrayBuffer::with_backing_store(scope, &backing_store);
+  new(scope, buffer, elements.len()).ok_or_else(|| {
+    crate::error::type_error(format!("Failed to allocate {}", T::NAME)).into()
+  })
+}
+
+fn matrix_to_v8<'a, T: TypedArrayElement>(
+  scope: &mut v8::HandleScope<'a>,
+  (rows, cols): (usize, usize),
+  elements: Option<&[T]>,
+  new: NewTypedArray<'a>,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  let data =
+    elements_to_v8(scope, elements, new).map_err(|e| field_error("data", e))?;
+  let mut object = ObjectBuilder::new(scope, &MATRIX_KEYS)?;
+  object.property(scope, "data", data)?;
+  object.field(scope, "shape", [SafeNumber(rows), SafeNumber(cols)])?;
+  Ok(object.build().into())
+}
+
+fn matrix_from_v8<'a, T: TypedArrayElement>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) -> Result<Array2<T>, StdAnyError> {
+  let object = field_object(value)?;
+  let shape = field_property(scope, object, "shape", false)?;
+  let [SafeNumber(rows), SafeNumber(cols)] =
+    trace::from_v8::<[SafeNumber<usize>; 2]>(scope, shape)
+      .map_err(|e| field_error("shape", e))?;
+  let data = field_property(scope, object, "data", false)?;
+  let data =
+    typed_vec_from_v8::<T>(scope, data).map_err(|e| field_error("data", e))?;
+  if rows.checked_mul(cols) != Some(data.len()) {
+    return Err(
+      ConvertError::invalid(format!(
+        "Expected {rows} * {cols} elements for shape [{rows}, {cols}], got {}",
+        data.len()
+      ))
+      .into(),
+    );
+  }
+  Array2::from_shape_vec((rows, cols), data)
+    .map_err(|e| ConvertError::invalid(e.to_string()).into())
+}
+
+macro_rules! impl_ndarray {
+  ($($t:ty: $array:ident),* $(,)?) => {
+    $(
+      impl<'a> ToV8<'a> for Array1<$t> {
+        type Error = StdAnyError;
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          self.view().to_v8(scope)
+        }
+      }
+
+      impl<'a, 'b> ToV8<'a> for ArrayView1<'b, $t> {
+        type Error = StdAnyError;
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          elements_to_v8(scope, self.as_slice(), |scope, buffer, len| {
+            v8::$array::new(scope, buffer, 0, len).map(Into::into)
+          })
+        }
+      }
+
+      impl<'a> FromV8<'a> for Array1<$t> {
+        type Error = StdAnyError;
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          typed_vec_from_v8(scope, value).map(Array1::from_vec)
+        }
+      }
+
+      impl<'a> ToV8<'a> for Array2<$t> {
+        type Error = StdAnyError;
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          self.view().to_v8(scope)
+        }
+      }
+
+      impl<'a, 'b> ToV8<'a> for ArrayView2<'b, $t> {
+        type Error = StdAnyError;
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          matrix_to_v8(scope, self.dim(), self.as_slice(), |scope, buffer, len| {
+            v8::$array::new(scope, buffer, 0, len).map(Into::into)
+          })
+        }
+      }
+
+      impl<'a> FromV8<'a> for Array2<$t> {
+        type Error = StdAnyError;
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          matrix_from_v8(scope, value)
+        }
+      }
+    )*
+  };
+}
+
+impl_ndarray!(
+  u8: Uint8Array,
+  u32: Uint32Array,
+  f32: Float32Array,
+  f64: Float64Array,
+);
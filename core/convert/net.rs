@@ -0,0 +1,107 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for IP and socket addresses, behind the `convert_net` feature.
+
+use super::field_error;
+use super::field_key;
+use super::field_object;
+use super::field_property;
+use super::trace;
+use super::CheckedSmi;
+use super::ConvertError;
+use super::FromV8;
+use super::ToV8;
+use crate::error::StdAnyError;
+use std::borrow::Cow;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
+
+/// Implements `ToV8` and `FromV8` for a type using its canonical string form.
+macro_rules! impl_string_form {
+  ($($t:ty: $what:literal),* $(,)?) => {
+    $(
+      impl<'a> ToV8<'a> for $t {
+        type Error = StdAnyError;
+        #[inline]
+        fn to_v8(
+          self,
+          scope: &mut v8::HandleScope<'a>,
+        ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+          Cow::<str>::Owned(self.to_string()).to_v8(scope)
+        }
+      }
+
+      impl<'a> FromV8<'a> for $t {
+        type Error = StdAnyError;
+        #[inline]
+        fn from_v8(
+          scope: &mut v8::HandleScope<'a>,
+          value: v8::Local<'a, v8::Value>,
+        ) -> Result<Self, Self::Error> {
+          let string = Cow::<str>::from_v8(scope, value)?;
+          string.parse().map_err(|_| {
+            ConvertError::invalid(format!(
+              concat!("Invalid ", $what, " '{}'"),
+              string
+            ))
+            .into()
+          })
+        }
+      }
+    )*
+  };
+}
+
+// IPv6 zone ids are only supported in socket addresses, and only in their
+// numeric form, ie: `[fe80::1%2]:80`.
+impl_string_form!(
+  IpAddr: "IP address",
+  Ipv4Addr: "IPv4 address",
+  Ipv6Addr: "IPv6 address",
+  SocketAddr: "socket address",
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a [`SocketAddr`] as being serialized as a `{ hostname, port }` object, rather
+/// than its string form.
+///
+/// The hostname must be an IP address. IPv6 scope ids and flow info are not represented,
+/// and are zero when converting from JS.
+#[repr(transparent)]
+pub struct NetAddr(pub SocketAddr);
+
+impl<'a> ToV8<'a> for NetAddr {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let object = v8::Object::new(scope);
+    let key = field_key(scope, "hostname")?;
+    let hostname = self.0.ip().to_v8(scope)?;
+    object.create_data_property(scope, key.into(), hostname);
+    let key = field_key(scope, "port")?;
+    let port = v8::Integer::new_from_unsigned(scope, self.0.port() as u32);
+    object.create_data_property(scope, key.into(), port.into());
+    Ok(object.into())
+  }
+}
+
+impl<'a> FromV8<'a> for NetAddr {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let object = field_object(value)?;
+    let hostname = field_property(scope, object, "hostname", false)?;
+    let hostname = trace::from_v8::<IpAddr>(scope, hostname)
+      .map_err(|e| field_error("hostname", e))?;
+    let port = field_property(scope, object, "port", false)?;
+    let CheckedSmi(port) = trace::from_v8::<CheckedSmi<u16>>(scope, port)
+      .map_err(|e| field_error("port", e))?;
+    Ok(NetAddr(SocketAddr::new(hostname, port)))
+  }
+}
@@ -0,0 +1,342 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Building objects with keys that are cached per isolate.
+
+use super::field_key;
+use super::key_error;
+use super::trace;
+use super::ToV8;
+use crate::error::StdAnyError;
+use smallvec::SmallVec;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The string keys of the objects built by an [`ObjectBuilder`], and of those read by
+/// `#[derive(FromV8)]`.
+///
+/// The keys are created as internalized V8 strings the first time they are used in an
+/// isolate, and the same strings are reused by every later object, rather than looking
+/// each key up again. Declare them as a `static`, since the cache is keyed by address:
+///
+/// ```ignore
+/// static POINT_KEYS: ObjectKeys = ObjectKeys::new(&["x", "y"]);
+/// ```
+#[derive(Debug)]
+pub struct ObjectKeys {
+  names: &'static [&'static str],
+}
+
+impl ObjectKeys {
+  pub const fn new(names: &'static [&'static str]) -> Self {
+    Self { names }
+  }
+
+  pub fn names(&self) -> &'static [&'static str] {
+    self.names
+  }
+
+  /// Returns the keys as strings of this isolate, creating them on first use.
+  fn get<'a>(
+    &'static self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<SmallVec<[v8::Local<'a, v8::String>; 8]>, StdAnyError> {
+    let id = self as *const Self as usize;
+    let cached = scope
+      .get_slot::<ObjectKeyCache>()
+      .and_then(|cache| cache.0.get(&id).cloned());
+    let keys = match cached {
+      Some(keys) => keys,
+      None => {
+        let mut keys = Vec::with_capacity(self.names.len());
+        for name in self.names {
+          let key = field_key(scope, name)?;
+          keys.push(v8::Global::new(scope, key));
+        }
+        let keys: Rc<[_]> = keys.into();
+        if scope.get_slot::<ObjectKeyCache>().is_none() {
+          scope.set_slot(ObjectKeyCache::default());
+        }
+        let cache = scope.get_slot_mut::<ObjectKeyCache>().unwrap();
+        cache.0.insert(id, keys.clone());
+        keys
+      }
+    };
+    Ok(keys.iter().map(|key| v8::Local::new(scope, key)).collect())
+  }
+}
+
+/// Returns the keys as strings of this isolate, creating them on first use, for use by
+/// `#[derive(FromV8)]`.
+#[doc(hidden)]
+pub fn field_keys<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  keys: &'static ObjectKeys,
+) -> Result<SmallVec<[v8::Local<'a, v8::String>; 8]>, StdAnyError> {
+  keys.get(scope)
+}
+
+/// The keys created for each [`ObjectKeys`] in an isolate, stored in an isolate slot.
+///
+/// These are global handles, so the runtime removes the slot before it is torn down or
+/// snapshotted.
+#[derive(Default)]
+pub(crate) struct ObjectKeyCache(HashMap<usize, Rc<[v8::Global<v8::String>]>>);
+
+/// Builds a `v8::Object` from fields, using keys from an [`ObjectKeys`] so that the
+/// key strings aren't created again for every object.
+///
+/// This is what `#[derive(ToV8)]` generates for `#[v8(object)]` structs.
+///
+/// ```ignore
+/// static POINT_KEYS: ObjectKeys = ObjectKeys::new(&["x", "y"]);
+///
+/// impl<'a> ToV8<'a> for Point {
+///   type Error = StdAnyError;
+///   fn to_v8(
+///     self,
+///     scope: &mut v8::HandleScope<'a>,
+///   ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+///     let mut object = ObjectBuilder::new(scope, &POINT_KEYS)?;
+///     object.field(scope, "x", Number(self.x))?;
+///     object.field(scope, "y", Number(self.y))?;
+///     Ok(object.build().into())
+///   }
+/// }
+/// ```
+pub struct ObjectBuilder<'a> {
+  object: v8::Local<'a, v8::Object>,
+  names: &'static [&'static str],
+  keys: SmallVec<[v8::Local<'a, v8::String>; 8]>,
+}
+
+impl<'a> ObjectBuilder<'a> {
+  /// Creates an empty object that will be built with the given keys.
+  pub fn new(
+    scope: &mut v8::HandleScope<'a>,
+    keys: &'static ObjectKeys,
+  ) -> Result<Self, StdAnyError> {
+    let object = v8::Object::new(scope);
+    Self::from_object(scope, object, keys)
+  }
+
+  /// Adds properties to an existing object, with the given keys.
+  pub fn from_object(
+    scope: &mut v8::HandleScope<'a>,
+    object: v8::Local<'a, v8::Object>,
+    keys: &'static ObjectKeys,
+  ) -> Result<Self, StdAnyError> {
+    let names = keys.names;
+    let keys = keys.get(scope)?;
+    Ok(Self {
+      object,
+      names,
+      keys,
+    })
+  }
+
+  /// The object being built.
+  pub fn object(&self) -> v8::Local<'a, v8::Object> {
+    self.object
+  }
+
+  /// Converts `value` and defines it as the property `name`. Errors name the field.
+  ///
+  /// A `name` that isn't one of the builder's keys still works, but its key is created
+  /// for this call rather than cached.
+  pub fn field<T: ToV8<'a>>(
+    &mut self,
+    scope: &mut v8::HandleScope<'a>,
+    name: &'static str,
+    value: T,
+  ) -> Result<&mut Self, StdAnyError> {
+    let value = trace::to_v8(scope, value).map_err(|e| key_error(name, e))?;
+    self.property(scope, name, value)
+  }
+
+  /// Defines an already converted value as the property `name`, looking it up among
+  /// the builder's keys. Use [`property_at`](Self::property_at) when its index is known.
+  pub fn property(
+    &mut self,
+    scope: &mut v8::HandleScope<'a>,
+    name: &'static str,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<&mut Self, StdAnyError> {
+    let key = match self.names.iter().position(|n| *n == name) {
+      Some(i) => self.keys[i],
+      None => field_key(scope, name)?,
+    };
+    Ok(self.set(scope, key.into(), value))
+  }
+
+  /// Defines an already converted value as the property named by the builder's key at
+  /// `index`, which is what `#[derive(ToV8)]` generates. Panics if there is no such key.
+  pub fn property_at(
+    &mut self,
+    scope: &mut v8::HandleScope<'a>,
+    index: usize,
+    value: v8::Local<'a, v8::Value>,
+  ) -> &mut Self {
+    let key = self.keys[index];
+    self.set(scope, key.into(), value)
+  }
+
+  /// Defines a property with any key, such as a symbol.
+  pub fn set(
+    &mut self,
+    scope: &mut v8::HandleScope<'a>,
+    key: v8::Local<'a, v8::Name>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> &mut Self {
+    // Define own properties, so that keys like `__proto__` don't invoke setters
+    self.object.create_data_property(scope, key, value);
+    self
+  }
+
+  pub fn build(self) -> v8::Local<'a, v8::Object> {
+    self.object
+  }
+}
+
+/// Writes the fields of a `#[v8(object)]` struct into an object, which is how
+/// `#[derive(ToV8)]` converts them and how a `#[v8(flatten)]` field of another struct
+/// merges them into its parent.
+#[doc(hidden)]
+pub trait ObjectFields<'a> {
+  /// The keys that [`write_fields`](Self::write_fields) defines.
+  const NAMES: FieldNames;
+
+  fn write_fields(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+    object: v8::Local<'a, v8::Object>,
+  ) -> Result<(), StdAnyError>;
+}
+
+/// Converts a `#[v8(object)]` struct to an object created with the given prototype, so
+/// that it is an instance of a JS class, and passes `instanceof` checks, rather than a
+/// plain object.
+///
+/// The prototype is usually the `prototype` of a class that JS hands to an op once,
+/// which stores it to use for every object it returns later:
+///
+/// ```ignore
+/// struct MyErrorPrototype(v8::Global<v8::Object>);
+///
+/// #[op2]
+/// fn op_register_my_error(
+///   state: &mut OpState,
+///   #[global] prototype: v8::Global<v8::Object>,
+/// ) {
+///   // Called from JS as `op_register_my_error(MyError.prototype)`
+///   state.put(MyErrorPrototype(prototype));
+/// }
+///
+/// #[op2]
+/// #[to_v8]
+/// fn op_my_error(state: &OpState) -> WithPrototype<MyErrorDetails> {
+///   let prototype = state.borrow::<MyErrorPrototype>().0.clone();
+///   WithPrototype::new(prototype, MyErrorDetails { code: 1 })
+/// }
+/// ```
+///
+/// The object is created with the prototype and then has the struct's fields defined on
+/// it, so the class's constructor isn't run. A `v8::Global` keeps the prototype, and so
+/// the class, alive until it is dropped, and belongs to the isolate it was created in.
+/// Store it somewhere that is dropped with the runtime, such as the `OpState`, and
+/// never use it with another isolate.
+pub struct WithPrototype<T> {
+  pub prototype: v8::Global<v8::Object>,
+  pub value: T,
+}
+
+impl<T> WithPrototype<T> {
+  pub fn new(prototype: v8::Global<v8::Object>, value: T) -> Self {
+    Self { prototype, value }
+  }
+}
+
+impl<'a, T: ObjectFields<'a>> ToV8<'a> for WithPrototype<T> {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let prototype = v8::Local::new(scope, self.prototype);
+    let object = v8::Object::with_prototype_and_properties(
+      scope,
+      prototype.into(),
+      &[],
+      &[],
+    );
+    self.value.write_fields(scope, object)?;
+    Ok(object.into())
+  }
+}
+
+/// The string keys of an object written by `#[derive(ToV8)]`, including the keys of its
+/// `#[v8(flatten)]` fields, so that collisions can be found at compile time.
+#[doc(hidden)]
+pub struct FieldNames {
+  pub names: &'static [&'static str],
+  pub flattened: &'static [&'static FieldNames],
+}
+
+impl FieldNames {
+  /// Returns true if any key appears more than once.
+  pub const fn has_duplicates(&self) -> bool {
+    let len = self.len();
+    let mut i = 0;
+    while i < len {
+      let mut j = i + 1;
+      while j < len {
+        if str_eq(self.get(i), self.get(j)) {
+          return true;
+        }
+        j += 1;
+      }
+      i += 1;
+    }
+    false
+  }
+
+  const fn len(&self) -> usize {
+    let mut len = self.names.len();
+    let mut i = 0;
+    while i < self.flattened.len() {
+      len += self.flattened[i].len();
+      i += 1;
+    }
+    len
+  }
+
+  const fn get(&self, mut index: usize) -> &'static str {
+    if index < self.names.len() {
+      return self.names[index];
+    }
+    index -= self.names.len();
+    let mut i = 0;
+    loop {
+      let len = self.flattened[i].len();
+      if index < len {
+        return self.flattened[i].get(index);
+      }
+      index -= len;
+      i += 1;
+    }
+  }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+  let (a, b) = (a.as_bytes(), b.as_bytes());
+  if a.len() != b.len() {
+    return false;
+  }
+  let mut i = 0;
+  while i < a.len() {
+    if a[i] != b[i] {
+      return false;
+    }
+    i += 1;
+  }
+  true
+}
@@ -0,0 +1,156 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Converting Rust values to V8 values the way `#[op2]` converts the return values of
+//! ops, for code that hands values to JS without going through an op, such as the
+//! arguments of a JS function called from Rust.
+//!
+//! Each function is named after the return value attribute it mirrors, and uses the
+//! same conversion that the generated op code does, so a value converts to the same JS
+//! value here as when an op returns it:
+//!
+//! ```ignore
+//! // The same as returning `len` from an op marked `#[number]`
+//! let len = op_return::number(scope, file_len as u64);
+//! // The same as returning `name` from an op marked `#[string]`
+//! let name = op_return::string(scope, name.as_str())?;
+//! callback.call(scope, recv, &[len, name]);
+//! ```
+//!
+//! Conversions that can fail return a `TypeError` with the message that an op would
+//! throw.
+
+use crate::_ops::ArrayBufferMarker;
+use crate::_ops::NumberMarker;
+use crate::_ops::RustToV8;
+use crate::_ops::RustToV8Fallible;
+use crate::_ops::RustToV8Marker;
+use crate::_ops::SerdeMarker;
+use crate::_ops::SmiMarker;
+use crate::_ops::ToV8Marker;
+use crate::error::StdAnyError;
+use bytes::BytesMut;
+use std::borrow::Cow;
+
+/// Converts a value the way an op without a return value attribute does, such as a
+/// `bool`, `u32`, `f64`, `()` or `v8::Global`, or an `Option` of one, where `None` is
+/// `null`.
+pub fn value<'a, T: RustToV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> v8::Local<'a, v8::Value> {
+  value.to_v8(scope)
+}
+
+/// Converts an integer the way a `#[smi]` op return does. The value is cast to an
+/// `i32`, so JS sees unsigned values above `i32::MAX` as negative.
+pub fn smi<'a, T>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> v8::Local<'a, v8::Value>
+where
+  RustToV8Marker<SmiMarker, T>: RustToV8<'a>,
+{
+  RustToV8Marker::<SmiMarker, _>::from(value).to_v8(scope)
+}
+
+/// Converts a 64-bit integer the way a `#[number]` op return does. The value is cast to
+/// an `f64`, so values outside the safe integer range lose precision.
+pub fn number<'a, T>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> v8::Local<'a, v8::Value>
+where
+  RustToV8Marker<NumberMarker, T>: RustToV8<'a>,
+{
+  RustToV8Marker::<NumberMarker, _>::from(value).to_v8(scope)
+}
+
+/// Converts a `String`, `&str` or `Cow<str>` the way a `#[string]` op return does.
+pub fn string<'a, T>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError>
+where
+  T: RustToV8Fallible<'a> + AsRef<str>,
+{
+  value.to_v8_fallible(scope).map_err(type_error)
+}
+
+/// Converts latin1 bytes the way a `#[string(onebyte)]` op return does.
+pub fn string_onebyte<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  value: Cow<'a, [u8]>,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  value.to_v8_fallible(scope).map_err(type_error)
+}
+
+/// A buffer that can be returned from an op marked `#[buffer]`.
+pub trait BufferReturn<'a>: RustToV8Fallible<'a> + sealed::Sealed {}
+
+mod sealed {
+  pub trait Sealed {}
+}
+
+macro_rules! buffer_return {
+  ($($ty:ty),*) => {
+    $(
+      impl sealed::Sealed for $ty {}
+      impl<'a> BufferReturn<'a> for $ty {}
+    )*
+  };
+}
+
+buffer_return!(
+  serde_v8::V8Slice<u8>,
+  serde_v8::V8Slice<u32>,
+  serde_v8::JsBuffer,
+  Vec<u8>,
+  Box<[u8]>,
+  BytesMut
+);
+
+/// Converts bytes the way a `#[buffer]` op return does, to a `Uint8Array`, or a
+/// `Uint32Array` for a `V8Slice<u32>`.
+pub fn buffer<'a, T: BufferReturn<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  value.to_v8_fallible(scope).map_err(type_error)
+}
+
+/// Converts bytes the way an `#[arraybuffer]` op return does, to an `ArrayBuffer`.
+pub fn arraybuffer<'a, T>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> v8::Local<'a, v8::Value>
+where
+  RustToV8Marker<ArrayBufferMarker, T>: RustToV8<'a>,
+{
+  RustToV8Marker::<ArrayBufferMarker, _>::from(value).to_v8(scope)
+}
+
+/// Converts a value the way a `#[serde]` op return does, with `serde_v8`.
+pub fn serde<'a, T: serde::Serialize>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  RustToV8Marker::<SerdeMarker, _>::from(value)
+    .to_v8_fallible(scope)
+    .map_err(type_error)
+}
+
+/// Converts a value the way a `#[to_v8]` op return does, with its
+/// [`ToV8`](super::ToV8) impl.
+pub fn to_v8<'a, T: super::ToV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  RustToV8Marker::<ToV8Marker, _>::from(value)
+    .to_v8_fallible(scope)
+    .map_err(type_error)
+}
+
+/// The `TypeError` that an op throws when converting its return value fails.
+fn type_error(err: serde_v8::Error) -> StdAnyError {
+  crate::error::type_error(anyhow::Error::from(err).to_string()).into()
+}
@@ -0,0 +1,105 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Writing into a caller-provided `Uint8Array`.
+
+use super::check_detached;
+use super::ConvertError;
+use super::FromV8;
+use crate::error::StdAnyError;
+
+/// A `Uint8Array` that an op writes its output into, in place, rather than returning a
+/// new buffer. This lets JS reuse one buffer across calls, as compression and crypto
+/// ops commonly do:
+///
+/// ```ignore
+/// #[op2]
+/// fn op_fill_random(
+///   scope: &mut v8::HandleScope,
+///   #[from_v8] mut out: OutBuffer,
+/// ) {
+///   rand::thread_rng().fill_bytes(out.bytes_mut(scope));
+/// }
+/// ```
+///
+/// Only views of a non-shared `ArrayBuffer` are accepted. A `SharedArrayBuffer` may be
+/// written by other threads at the same time, which would be a data race on the slice,
+/// and a view of a detached buffer is rejected like it is for `Vec<u8>`.
+///
+/// # Safety of the slice
+///
+/// [`bytes_mut`](Self::bytes_mut) hands out a `&mut [u8]` that points straight into the
+/// buffer's backing store, which JS could otherwise detach, resize or read while the
+/// slice is alive. The slice mutably borrows the scope for as long as it lives, so no JS
+/// can run through that scope until the op is done writing, and its length is read from
+/// the view each time it is borrowed, so a buffer detached or shrunk between two calls
+/// gives a shorter slice, or an empty one, rather than a dangling one. For the same
+/// reason, two `OutBuffer`s can't be borrowed at the same time, even if JS passed the
+/// same buffer for both.
+///
+/// Rust can't see that other arguments of the op share the same memory. JS may pass the
+/// output buffer, or another view of its `ArrayBuffer`, as an input as well, so an op
+/// that takes its input as a `#[buffer] &[u8]` or `JsBuffer` must not assume that the
+/// input stays unchanged while it writes the output. Copy the input first, or read it
+/// through the same `OutBuffer`, when the two may overlap.
+pub struct OutBuffer<'a> {
+  view: v8::Local<'a, v8::Uint8Array>,
+}
+
+impl<'a> OutBuffer<'a> {
+  /// The current length of the buffer in bytes, which is zero once it is detached.
+  pub fn len(&self) -> usize {
+    self.view.byte_length()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// The `Uint8Array` being written into.
+  pub fn view(&self) -> v8::Local<'a, v8::Uint8Array> {
+    self.view
+  }
+
+  /// Borrows the bytes of the buffer to write into them, for as long as `scope` is
+  /// borrowed.
+  pub fn bytes_mut<'s>(
+    &'s mut self,
+    _scope: &'s mut v8::HandleScope,
+  ) -> &'s mut [u8] {
+    let len = self.view.byte_length();
+    let data = self.view.data() as *mut u8;
+    if len == 0 || data.is_null() {
+      return &mut [];
+    }
+    // SAFETY: `data` points to the `len` bytes of the view in a backing store that
+    // isn't shared, and that our handle keeps alive. Borrowing the scope for the
+    // lifetime of the slice keeps JS from running and detaching or resizing the buffer
+    // until the slice is dropped.
+    unsafe { std::slice::from_raw_parts_mut(data, len) }
+  }
+}
+
+impl<'a> FromV8<'a> for OutBuffer<'a> {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(view) = v8::Local::<v8::Uint8Array>::try_from(value) else {
+      return Err(ConvertError::wrong_type("Uint8Array", &value).into());
+    };
+    check_detached(scope, view.into())?;
+    if view
+      .get_backing_store()
+      .is_some_and(|store| store.is_shared())
+    {
+      return Err(
+        ConvertError::invalid(
+          "Expected Uint8Array backed by an ArrayBuffer, got one backed by a SharedArrayBuffer",
+        )
+        .into(),
+      );
+    }
+    Ok(Self { view })
+  }
+}
@@ -0,0 +1,143 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for [`Path`], [`PathBuf`], [`OsStr`] and [`OsString`], behind the
+//! `convert_path` feature.
+
+use super::ConvertError;
+use super::FromV8;
+use super::ToV8;
+use crate::error::StdAnyError;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Converts an OS string to a JS string, where `kind` names it in the error for a
+/// string that isn't valid UTF-8.
+fn os_str_to_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  os_str: &OsStr,
+  kind: &str,
+) -> Result<v8::Local<'a, v8::Value>, StdAnyError> {
+  #[cfg(windows)]
+  let string = {
+    use std::os::windows::ffi::OsStrExt;
+    let _ = kind;
+    let units = os_str.encode_wide().collect::<Vec<_>>();
+    v8::String::new_from_two_byte(scope, &units, v8::NewStringType::Normal)
+  };
+  #[cfg(not(windows))]
+  let string = {
+    let os_str = os_str.to_str().ok_or_else(|| {
+      ConvertError::invalid(format!(
+        "{kind} is not valid UTF-8: '{}'",
+        os_str.to_string_lossy()
+      ))
+    })?;
+    v8::String::new(scope, os_str)
+  };
+  string.map(Into::into).ok_or_else(|| {
+    ConvertError::out_of_range("String is too long to convert").into()
+  })
+}
+
+fn os_string_from_v8<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) -> Result<OsString, StdAnyError> {
+  #[cfg(windows)]
+  {
+    use std::os::windows::ffi::OsStringExt;
+    let Ok(string) = v8::Local::<v8::String>::try_from(value) else {
+      return Err(ConvertError::wrong_type("string", &value).into());
+    };
+    let mut units = vec![0; string.length()];
+    string.write(scope, &mut units, 0, v8::WriteOptions::NO_NULL_TERMINATION);
+    Ok(OsString::from_wide(&units))
+  }
+  #[cfg(not(windows))]
+  {
+    Cow::<str>::from_v8(scope, value).map(|string| string.into_owned().into())
+  }
+}
+
+/// Converts a path to a JS string.
+///
+/// On Windows, paths are UTF-16 and convert losslessly, including unpaired surrogates.
+/// Elsewhere, paths are arbitrary bytes and a path that is not valid UTF-8 is an error
+/// rather than being converted lossily, since the lossy string would name a different
+/// file.
+impl<'a> ToV8<'a> for PathBuf {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    os_str_to_v8(scope, self.as_os_str(), "Path")
+  }
+}
+
+/// Converts a path to a JS string, in the same way as [`PathBuf`].
+impl<'a, 'p> ToV8<'a> for &'p Path {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    os_str_to_v8(scope, self.as_os_str(), "Path")
+  }
+}
+
+/// Converts a JS string to a path.
+///
+/// On Windows, the UTF-16 contents are used as-is, so any JS string round-trips. Elsewhere,
+/// the string is converted to UTF-8, and unpaired surrogates are replaced with U+FFFD.
+impl<'a> FromV8<'a> for PathBuf {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    os_string_from_v8(scope, value).map(Into::into)
+  }
+}
+
+/// Converts an OS string, such as the value of an environment variable, to a JS string.
+///
+/// As with [`PathBuf`], this is lossless on Windows, where OS strings are UTF-16 and may
+/// contain unpaired surrogates, and elsewhere a string that isn't valid UTF-8 is an
+/// error rather than being converted lossily. Use [`OsStr::to_string_lossy`] first for
+/// strings that are only displayed.
+impl<'a> ToV8<'a> for OsString {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    os_str_to_v8(scope, &self, "String")
+  }
+}
+
+/// Converts an OS string to a JS string, in the same way as [`OsString`].
+impl<'a, 's> ToV8<'a> for &'s OsStr {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    os_str_to_v8(scope, self, "String")
+  }
+}
+
+/// Converts a JS string to an OS string, in the same way as [`PathBuf`]: losslessly on
+/// Windows, and elsewhere as UTF-8 with unpaired surrogates replaced with U+FFFD.
+impl<'a> FromV8<'a> for OsString {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    os_string_from_v8(scope, value)
+  }
+}
@@ -0,0 +1,65 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for [`SmallVec`], behind the `smallvec` feature.
+
+use super::array_from_v8;
+use super::expect_array;
+use super::iter_to_v8;
+use super::FromV8;
+use super::ToV8;
+use crate::error::StdAnyError;
+use smallvec::Array;
+use smallvec::SmallVec;
+use std::mem::MaybeUninit;
+
+/// Converts to a JS array, like `Vec<T>`.
+impl<'a, A> ToV8<'a> for SmallVec<A>
+where
+  A: Array,
+  A::Item: ToV8<'a>,
+{
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    Ok(iter_to_v8(scope, self.into_iter())?.into())
+  }
+}
+
+/// Converts from a JS array, like `Vec<T>`, but without a heap allocation when the array
+/// has no more elements than the inline capacity.
+///
+/// Elements are written in place into the inline buffer, like the `FromV8` impl for
+/// `[T; N]`, so if converting one fails, the elements converted so far are dropped.
+/// Longer arrays are converted as a `Vec`, which the `SmallVec` then takes over without
+/// copying.
+impl<'a, A> FromV8<'a> for SmallVec<A>
+where
+  A: Array,
+  A::Item: FromV8<'a>,
+{
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let arr = expect_array(value)?;
+    let len = arr.length() as usize;
+    if len > A::size() {
+      return Ok(SmallVec::from_vec(Vec::from_v8(scope, value)?));
+    }
+    let mut buf = MaybeUninit::<A>::uninit();
+    // SAFETY: `A` is an array of `A::size()` items, which is at least `len`, and
+    // `MaybeUninit` doesn't require initialization.
+    let items = unsafe {
+      std::slice::from_raw_parts_mut(
+        buf.as_mut_ptr() as *mut MaybeUninit<A::Item>,
+        len,
+      )
+    };
+    array_from_v8(scope, arr, "Array", items)?;
+    // SAFETY: The first `len` items were initialized by `array_from_v8`.
+    Ok(unsafe { SmallVec::from_buf_and_len_unchecked(buf, len) })
+  }
+}
@@ -0,0 +1,226 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Throwing values built with [`ToV8`] from ops.
+
+use super::field_key;
+use super::ConvertError;
+use super::ToV8;
+use crate::error::to_v8_error;
+use crate::error::StdAnyError;
+use crate::runtime::JsRealm;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::sync::Mutex;
+use v8::PropertyAttribute;
+
+/// An op error that is thrown to JS as the value `E` converts to, rather than as an
+/// error built from its class and message.
+///
+/// A sync op can return `Result<T, Throw<E>>`, or convert a `Throw` into an
+/// `anyhow::Error` with `?`, to control exactly what JS catches. Use [`ErrorObject`] to
+/// throw a JS `Error` with a given `name`, `message` and `cause`, or any other type to
+/// throw its JS representation.
+///
+/// If `E` fails to convert, the error is thrown as usual instead, using `E`'s
+/// `Display` output as the message. Errors from async ops are always reported by class
+/// and message, so `Throw` behaves like any other error there.
+///
+/// # Example
+///
+/// ```ignore
+/// #[op2(fast)]
+/// fn op_open(#[string] path: &str) -> Result<(), Throw<ErrorObject>> {
+///   if path.is_empty() {
+///     return Err(Throw(ErrorObject::new("InvalidPath", "Path is empty")));
+///   }
+///   Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Throw<E>(pub E);
+
+impl<E> From<Throw<E>> for anyhow::Error
+where
+  E: for<'a> ToV8<'a> + Display + Debug + Send + 'static,
+{
+  fn from(Throw(value): Throw<E>) -> Self {
+    let message = value.to_string();
+    let to_v8: ThrowFn = Box::new(|scope| {
+      value
+        .to_v8(scope)
+        .map_err(|e| anyhow::Error::msg(e.to_string()))
+    });
+    anyhow::Error::new(Thrown {
+      message,
+      to_v8: Mutex::new(Some(to_v8)),
+    })
+  }
+}
+
+type ThrowFn = Box<
+  dyn for<'s> FnOnce(
+      &mut v8::HandleScope<'s>,
+    ) -> Result<v8::Local<'s, v8::Value>, anyhow::Error>
+    + Send,
+>;
+
+/// A [`Throw`] with its value type erased, so that it can travel as an `anyhow::Error`.
+pub(crate) struct Thrown {
+  message: String,
+  to_v8: Mutex<Option<ThrowFn>>,
+}
+
+impl Thrown {
+  /// Converts the thrown value, or returns `None` if it was already taken or failed to
+  /// convert.
+  pub(crate) fn to_v8<'s>(
+    &self,
+    scope: &mut v8::HandleScope<'s>,
+  ) -> Option<v8::Local<'s, v8::Value>> {
+    let to_v8 = self.to_v8.lock().unwrap().take()?;
+    to_v8(scope).ok()
+  }
+}
+
+impl Debug for Thrown {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Thrown")
+      .field("message", &self.message)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Display for Thrown {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl std::error::Error for Thrown {}
+
+/// Converts to a JS `Error` object with the given `name`, `message` and, optionally,
+/// `cause`.
+///
+/// The names of the built-in error types (`TypeError`, `RangeError`, `SyntaxError` and
+/// `ReferenceError`) create an instance of that type. Any other name creates an `Error`
+/// with its own `name` property, so it is still `instanceof Error`. Its `stack` is left
+/// to V8, which captures it when the object is created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorObject {
+  pub name: Cow<'static, str>,
+  pub message: Cow<'static, str>,
+  pub cause: Option<Box<ErrorObject>>,
+}
+
+impl ErrorObject {
+  pub fn new(
+    name: impl Into<Cow<'static, str>>,
+    message: impl Into<Cow<'static, str>>,
+  ) -> Self {
+    Self {
+      name: name.into(),
+      message: message.into(),
+      cause: None,
+    }
+  }
+
+  /// Sets the `cause` of the error.
+  pub fn with_cause(self, cause: ErrorObject) -> Self {
+    Self {
+      cause: Some(Box::new(cause)),
+      ..self
+    }
+  }
+
+  /// Creates an `Error` with the message of `err`, and a chain of `cause`s from its
+  /// [`source`](std::error::Error::source)s.
+  pub fn from_error(err: &(dyn std::error::Error + 'static)) -> Self {
+    Self {
+      name: "Error".into(),
+      message: err.to_string().into(),
+      cause: err.source().map(|e| Box::new(Self::from_error(e))),
+    }
+  }
+}
+
+impl Display for ErrorObject {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.name, self.message)
+  }
+}
+
+impl<'a> ToV8<'a> for ErrorObject {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let message = v8::String::new(scope, &self.message).ok_or_else(|| {
+      ConvertError::out_of_range("String is too long to convert")
+    })?;
+    let error = match &*self.name {
+      "TypeError" => v8::Exception::type_error(scope, message),
+      "RangeError" => v8::Exception::range_error(scope, message),
+      "SyntaxError" => v8::Exception::syntax_error(scope, message),
+      "ReferenceError" => v8::Exception::reference_error(scope, message),
+      _ => v8::Exception::error(scope, message),
+    };
+    let object = v8::Local::<v8::Object>::try_from(error)
+      .map_err(|_| crate::error::type_error("Failed to create Error"))?;
+    if !matches!(
+      &*self.name,
+      "Error" | "TypeError" | "RangeError" | "SyntaxError" | "ReferenceError"
+    ) {
+      let key = field_key(scope, "name")?;
+      let name = self.name.to_v8(scope)?;
+      object.define_own_property(
+        scope,
+        key.into(),
+        name,
+        PropertyAttribute::DONT_ENUM,
+      );
+    }
+    // Like `new Error(message, { cause })`
+    if let Some(cause) = self.cause {
+      let key = field_key(scope, "cause")?;
+      let cause = (*cause).to_v8(scope)?;
+      object.define_own_property(
+        scope,
+        key.into(),
+        cause,
+        PropertyAttribute::DONT_ENUM,
+      );
+    }
+    Ok(error)
+  }
+}
+
+/// Converts to the same JS error that an op would throw for this error, without throwing
+/// it: an instance of the class the runtime's `get_error_class_fn` picks, with the
+/// error's message and, if it has one, its `code`. A [`Throw`] converts to its value.
+///
+/// This is for reporting errors as values, such as `Option<AnyError>` alongside an op's
+/// result, which converts to `null` for `None`.
+impl<'a> ToV8<'a> for anyhow::Error {
+  type Error = Infallible;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let get_class = JsRealm::state_from_scope(scope).get_error_class_fn;
+    Ok(to_v8_error(scope, get_class, &self))
+  }
+}
+
+/// Converts like `anyhow::Error`.
+impl<'a> ToV8<'a> for Box<dyn std::error::Error + Send + Sync> {
+  type Error = Infallible;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    anyhow::anyhow!(self).to_v8(scope)
+  }
+}
@@ -0,0 +1,235 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for [`SystemTime`] and [`Duration`], behind the `convert_time` feature.
+
+use super::element_error;
+use super::BigInt;
+use super::BigInteger;
+use super::ConvertError;
+use super::FromV8;
+use super::SafeNumber;
+use super::ToV8;
+use crate::error::StdAnyError;
+use std::convert::Infallible;
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// The largest absolute number of milliseconds since the epoch a JS `Date`
+/// can represent.
+const MAX_DATE_MILLIS: f64 = 8.64e15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a [`SystemTime`] as being serialized as a `v8::Date`.
+///
+/// Times before the unix epoch are supported and map to negative timestamps. Converting
+/// fails if the time is outside the range of a JS `Date`, or if the JS `Date` is invalid.
+#[repr(transparent)]
+pub struct Date(pub SystemTime);
+
+impl Date {
+  /// Creates a `Date` from milliseconds since the unix epoch, returning `None` if
+  /// the value is not a valid JS `Date` timestamp. Fractional milliseconds are
+  /// truncated, matching JS.
+  pub fn from_millis(millis: f64) -> Option<Self> {
+    if !millis.is_finite() || millis.abs() > MAX_DATE_MILLIS {
+      return None;
+    }
+    let offset = Duration::from_millis(millis.abs() as u64);
+    let time = if millis < 0.0 {
+      SystemTime::UNIX_EPOCH.checked_sub(offset)
+    } else {
+      SystemTime::UNIX_EPOCH.checked_add(offset)
+    };
+    time.map(Date)
+  }
+
+  /// Returns the number of milliseconds since the unix epoch, which is negative
+  /// for times before the epoch.
+  pub fn millis(&self) -> f64 {
+    match self.0.duration_since(SystemTime::UNIX_EPOCH) {
+      Ok(after) => after.as_secs_f64() * 1000.0,
+      Err(before) => -(before.duration().as_secs_f64() * 1000.0),
+    }
+  }
+}
+
+impl From<SystemTime> for Date {
+  fn from(time: SystemTime) -> Self {
+    Date(time)
+  }
+}
+
+impl<'a> ToV8<'a> for Date {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let millis = self.millis();
+    if millis.abs() > MAX_DATE_MILLIS {
+      return Err(
+        ConvertError::out_of_range("Time is out of range for a Date").into(),
+      );
+    }
+    v8::Date::new(scope, millis)
+      .map(Into::into)
+      .ok_or_else(|| crate::error::type_error("Failed to create Date").into())
+  }
+}
+
+impl<'a> FromV8<'a> for Date {
+  type Error = StdAnyError;
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(date) = v8::Local::<v8::Date>::try_from(value) else {
+      return Err(ConvertError::wrong_type("Date", &value).into());
+    };
+    Date::from_millis(date.value_of()).ok_or_else(|| {
+      ConvertError::out_of_range("Invalid Date or out of range for SystemTime")
+        .into()
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a [`Duration`] as being serialized as a number of milliseconds in a `v8::Number`.
+///
+/// Sub-millisecond precision is kept as a fractional number of milliseconds, so
+/// `Duration::from_micros(1500)` becomes `1.5`. When converting from JS, the value is
+/// rounded to the nearest nanosecond, and negative, non-finite, or too large values are
+/// rejected.
+#[repr(transparent)]
+pub struct Millis(pub Duration);
+
+impl<'a> ToV8<'a> for Millis {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let millis = self.0.as_secs() as f64 * 1000.0
+      + self.0.subsec_nanos() as f64 / 1_000_000.0;
+    Ok(v8::Number::new(scope, millis).into())
+  }
+}
+
+impl<'a> FromV8<'a> for Millis {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(millis) = v8::Local::<v8::Number>::try_from(value) else {
+      return Err(ConvertError::wrong_type("number", &value).into());
+    };
+    let nanos = (millis.value() * 1_000_000.0).round();
+    // `u64::MAX as f64` rounds up, so this must be a strict comparison
+    if !(0.0..(u64::MAX as f64)).contains(&nanos) {
+      return Err(
+        ConvertError::out_of_range(
+          "Expected a non-negative, finite number of milliseconds",
+        )
+        .into(),
+      );
+    }
+    Ok(Millis(Duration::from_nanos(nanos as u64)))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a [`Duration`] as being serialized as a number of nanoseconds in a `v8::BigInt`.
+///
+/// Unlike [`Millis`], this is lossless in both directions. Negative values, and values
+/// too large for a `Duration`, are rejected when converting from JS.
+#[repr(transparent)]
+pub struct Nanos(pub Duration);
+
+impl<'a> ToV8<'a> for Nanos {
+  type Error = Infallible;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    BigInt(self.0.as_nanos()).to_v8(scope)
+  }
+}
+
+impl<'a> FromV8<'a> for Nanos {
+  type Error = StdAnyError;
+  #[inline]
+  fn from_v8(
+    _scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let Ok(value) = v8::Local::<v8::BigInt>::try_from(value) else {
+      return Err(ConvertError::wrong_type("BigInt", &value).into());
+    };
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    u128::from_big_int(&value)
+      .and_then(|nanos| {
+        let secs = (nanos / NANOS_PER_SEC).try_into().ok()?;
+        Some(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+      })
+      .map(Nanos)
+      .ok_or_else(|| {
+        ConvertError::out_of_range(
+          "Expected a non-negative number of nanoseconds that fits in a Duration",
+        )
+        .into()
+      })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Marks a [`Duration`] as being serialized as a `[seconds, nanoseconds]` array, the
+/// high-resolution time format of Node's `process.hrtime()`.
+///
+/// Like [`Nanos`], this is lossless, but without needing a `BigInt`. Both elements must
+/// be non-negative safe integers, and the nanoseconds must be below `1e9`: a tuple such
+/// as `[1, 1500000000]` is rejected rather than normalized, since JS APIs that produce
+/// these never return one. A `Duration` with more seconds than the safe integer range
+/// fails to convert to JS.
+///
+/// For the difference between two JS `Date`s, which is a number of milliseconds, use
+/// [`Millis`].
+#[repr(transparent)]
+pub struct HrTime(pub Duration);
+
+impl<'a> ToV8<'a> for HrTime {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let secs = SafeNumber(self.0.as_secs())
+      .to_v8(scope)
+      .map_err(|e| element_error(0, e))?;
+    let nanos = v8::Integer::new_from_unsigned(scope, self.0.subsec_nanos());
+    Ok(v8::Array::new_with_elements(scope, &[secs, nanos.into()]).into())
+  }
+}
+
+impl<'a> FromV8<'a> for HrTime {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let [SafeNumber(secs), SafeNumber(nanos)] =
+      <[SafeNumber<u64>; 2]>::from_v8(scope, value)?;
+    if nanos >= 1_000_000_000 {
+      return Err(element_error(
+        1,
+        ConvertError::out_of_range(format!(
+          "Expected fewer than 1000000000 nanoseconds, got {nanos}"
+        )),
+      ));
+    }
+    Ok(HrTime(Duration::new(secs, nanos as u32)))
+  }
+}
@@ -0,0 +1,81 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Tracing for the nested conversions of the built-in impls, behind the
+//! `convert_tracing` feature.
+//!
+//! Collections, tuples, maps and other impls that convert values they contain do so
+//! through these functions. With the feature enabled, each of those conversions runs
+//! in a `TRACE` level span under the `deno_core::convert` target, with the Rust type
+//! being converted in its `ty` field, and a failed conversion emits a `DEBUG` event
+//! with the error. Since the spans nest, the span stack of a failure shows the type at
+//! each level of a nested structure:
+//!
+//! ```text
+//! from_v8{ty=alloc::vec::Vec<deno_core::convert::Smi<u32>>} > from_v8{ty=deno_core::convert::Smi<u32>}: conversion failed error=Expected u32
+//! from_v8{ty=alloc::vec::Vec<deno_core::convert::Smi<u32>>}: conversion failed error=at [1]: Expected u32
+//! ```
+//!
+//! That is the output for converting `[[1, 2], [3, 'four']]` to a `Vec<Vec<Smi<u32>>>`,
+//! from `examples/convert_tracing.rs`. The outermost conversion isn't traced, since it is
+//! called by the op or the caller directly. Without the feature these functions are
+//! plain calls to `from_v8` and `to_v8`, so they cost nothing.
+
+use super::FromV8;
+use super::ToV8;
+
+/// Converts like [`FromV8::from_v8`], in a span naming `T`.
+#[inline(always)]
+pub(crate) fn from_v8<'a, T: FromV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) -> Result<T, T::Error> {
+  from_v8_with::<T, _>(|| T::from_v8(scope, value))
+}
+
+/// Runs `convert` in a span naming `T`, for nested conversions that carry state down a
+/// recursive structure and so can't go through [`FromV8::from_v8`].
+#[inline(always)]
+pub(crate) fn from_v8_with<T, E: std::fmt::Display>(
+  convert: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+  #[cfg(feature = "convert_tracing")]
+  {
+    let span = tracing::trace_span!(
+      target: "deno_core::convert",
+      "from_v8",
+      ty = std::any::type_name::<T>()
+    );
+    let _entered = span.enter();
+    let result = convert();
+    if let Err(err) = &result {
+      tracing::debug!(target: "deno_core::convert", error = %err, "conversion failed");
+    }
+    result
+  }
+  #[cfg(not(feature = "convert_tracing"))]
+  convert()
+}
+
+/// Converts like [`ToV8::to_v8`], in a span naming `T`.
+#[inline(always)]
+pub(crate) fn to_v8<'a, T: ToV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: T,
+) -> Result<v8::Local<'a, v8::Value>, T::Error> {
+  #[cfg(feature = "convert_tracing")]
+  {
+    let span = tracing::trace_span!(
+      target: "deno_core::convert",
+      "to_v8",
+      ty = std::any::type_name::<T>()
+    );
+    let _entered = span.enter();
+    let result = value.to_v8(scope);
+    if let Err(err) = &result {
+      tracing::debug!(target: "deno_core::convert", error = %err, "conversion failed");
+    }
+    result
+  }
+  #[cfg(not(feature = "convert_tracing"))]
+  value.to_v8(scope)
+}
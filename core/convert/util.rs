@@ -0,0 +1,179 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Building blocks for writing [`FromV8`] impls without `unsafe` code.
+
+use super::element_error;
+use super::trace;
+use super::FromV8;
+use super::MAX_RESERVED_ELEMENTS;
+use crate::error::StdAnyError;
+use std::mem::MaybeUninit;
+
+/// Builds a `Vec` of exactly `len` elements by calling `f` with each index in order,
+/// stopping at the first error.
+///
+/// This is how the `FromV8` impl for `Vec<T>` converts arrays. Since `len` usually
+/// comes from JS, where a sparse array can claim any length without holding any
+/// elements, at most 1024 elements are reserved up front and the `Vec` grows from
+/// there. If `f` returns an error or panics, the elements converted so far are dropped
+/// and the allocation is freed, so nothing leaks and nothing is dropped twice.
+///
+/// # Example
+///
+/// ```ignore
+/// let elements = collect_exact(array.length() as usize, |i| {
+///   let value = array.get_index(scope, i as u32).unwrap();
+///   Foo::from_v8(scope, value)
+/// })?;
+/// ```
+pub fn collect_exact<T, E>(
+  len: usize,
+  mut f: impl FnMut(usize) -> Result<T, E>,
+) -> Result<Vec<T>, E> {
+  let mut out = Vec::with_capacity(len.min(MAX_RESERVED_ELEMENTS));
+  for i in 0..len {
+    out.push(f(i)?);
+  }
+  Ok(out)
+}
+
+/// Converts each of `values` with [`FromV8`], collecting them into a `Vec`. Errors name
+/// the index of the value that failed, like those from converting a JS array.
+///
+/// # Panics
+///
+/// Panics if the iterator yields a different number of values than its
+/// [`ExactSizeIterator::len`] reported.
+pub fn collect_from_v8<'a, T, I>(
+  scope: &mut v8::HandleScope<'a>,
+  values: I,
+) -> Result<Vec<T>, StdAnyError>
+where
+  T: FromV8<'a>,
+  I: IntoIterator<Item = v8::Local<'a, v8::Value>>,
+  I::IntoIter: ExactSizeIterator,
+{
+  let mut values = values.into_iter();
+  let out = collect_exact(values.len(), |i| {
+    let value = values.next().expect("iterator yielded too few values");
+    trace::from_v8::<T>(scope, value).map_err(|e| element_error(i, e))
+  })?;
+  assert!(values.next().is_none(), "iterator yielded too many values");
+  Ok(out)
+}
+
+/// Tracks the initialized prefix of a slice of `MaybeUninit<T>` that is being filled
+/// in place. If the conversion fails or panics partway through, the elements that were
+/// already written are dropped when the guard is dropped.
+pub(super) struct InitGuard<'s, T> {
+  slice: &'s mut [MaybeUninit<T>],
+  initialized: usize,
+}
+
+impl<'s, T> InitGuard<'s, T> {
+  pub(super) fn new(slice: &'s mut [MaybeUninit<T>]) -> Self {
+    Self {
+      slice,
+      initialized: 0,
+    }
+  }
+
+  pub(super) fn len(&self) -> usize {
+    self.slice.len()
+  }
+
+  pub(super) fn push(&mut self, value: T) {
+    self.slice[self.initialized].write(value);
+    self.initialized += 1;
+  }
+
+  /// Asserts that the slice is fully initialized and releases it without dropping any
+  /// of the elements.
+  pub(super) fn finish(self) {
+    assert_eq!(self.initialized, self.slice.len());
+    std::mem::forget(self);
+  }
+}
+
+impl<'s, T> Drop for InitGuard<'s, T> {
+  fn drop(&mut self) {
+    for value in &mut self.slice[..self.initialized] {
+      // SAFETY: The first `initialized` elements have been written.
+      unsafe { value.assume_init_drop() };
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::cell::Cell;
+  use std::rc::Rc;
+
+  /// Counts how many times values are dropped, so tests can check that each converted
+  /// element is dropped exactly once.
+  struct DropCounter(Rc<Cell<usize>>);
+
+  impl Drop for DropCounter {
+    fn drop(&mut self) {
+      self.0.set(self.0.get() + 1);
+    }
+  }
+
+  #[test]
+  fn test_collect_exact() {
+    let out = collect_exact(4, |i| Ok::<_, ()>(i * 2)).unwrap();
+    assert_eq!(out, [0, 2, 4, 6]);
+    assert_eq!(out.capacity(), 4);
+    let empty = collect_exact(0, |_| Err::<u32, _>("called")).unwrap();
+    assert!(empty.is_empty());
+  }
+
+  #[test]
+  fn test_collect_exact_huge_len_fails_before_allocating() {
+    // A length that could never be allocated fails on the first element instead
+    let res = collect_exact(usize::MAX, Err::<u64, _>);
+    assert_eq!(res.err(), Some(0));
+    let out = collect_exact(5000, Ok::<_, ()>).unwrap();
+    assert_eq!(out.len(), 5000);
+    assert_eq!(out[4999], 4999);
+  }
+
+  #[test]
+  fn test_collect_exact_error_drops_prefix() {
+    let drops = Rc::new(Cell::new(0));
+    let res = collect_exact(5, |i| {
+      if i == 3 {
+        return Err(i);
+      }
+      Ok(DropCounter(drops.clone()))
+    });
+    assert_eq!(res.err(), Some(3));
+    assert_eq!(drops.get(), 3);
+  }
+
+  #[test]
+  fn test_collect_exact_panic_drops_prefix() {
+    let drops = Rc::new(Cell::new(0));
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      collect_exact(5, |i| {
+        if i == 2 {
+          panic!("conversion panicked");
+        }
+        Ok::<_, ()>(DropCounter(drops.clone()))
+      })
+    }));
+    assert!(res.is_err());
+    assert_eq!(drops.get(), 2);
+  }
+
+  #[test]
+  fn test_collect_exact_success_drops_once() {
+    let drops = Rc::new(Cell::new(0));
+    let out =
+      collect_exact(3, |_| Ok::<_, ()>(DropCounter(drops.clone()))).unwrap();
+    assert_eq!(drops.get(), 0);
+    drop(out);
+    assert_eq!(drops.get(), 3);
+  }
+}
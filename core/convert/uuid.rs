@@ -0,0 +1,71 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Conversions for [`Uuid`](uuid::Uuid), behind the `uuid` feature.
+
+use super::ConvertError;
+use super::FromV8;
+use super::ToV8;
+use crate::error::StdAnyError;
+use std::borrow::Cow;
+
+/// Converts to the 36-character hyphenated form, such as
+/// `"67e55044-10b1-426f-9247-bb680e5fe0c8"`. Converting from JS also accepts the simple,
+/// braced, and URN forms, in either case. Use [`UuidBytes`] for a 16-byte `Uint8Array`
+/// instead.
+impl<'a> ToV8<'a> for uuid::Uuid {
+  type Error = StdAnyError;
+  #[inline]
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    let mut buf = uuid::Uuid::encode_buffer();
+    Cow::Borrowed(&*self.hyphenated().encode_lower(&mut buf)).to_v8(scope)
+  }
+}
+
+impl<'a> FromV8<'a> for uuid::Uuid {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let string = Cow::<str>::from_v8(scope, value)?;
+    uuid::Uuid::parse_str(&string).map_err(|e| {
+      ConvertError::invalid(format!("Invalid UUID '{string}': {e}")).into()
+    })
+  }
+}
+
+/// Marks a [`uuid::Uuid`] as being serialized as a 16-byte `Uint8Array`, rather than its
+/// string form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[repr(transparent)]
+pub struct UuidBytes(pub uuid::Uuid);
+
+impl<'a> ToV8<'a> for UuidBytes {
+  type Error = StdAnyError;
+  fn to_v8(
+    self,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+    self.0.as_bytes().as_slice().to_v8(scope)
+  }
+}
+
+impl<'a> FromV8<'a> for UuidBytes {
+  type Error = StdAnyError;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let bytes = Vec::<u8>::from_v8(scope, value)?;
+    uuid::Uuid::from_slice(&bytes).map(UuidBytes).map_err(|_| {
+      ConvertError::invalid(format!(
+        "Expected 16 bytes for a UUID, got {}",
+        bytes.len()
+      ))
+      .into()
+    })
+  }
+}
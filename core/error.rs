@@ -9,6 +9,8 @@ use std::fmt::Formatter;
 
 use anyhow::Error;
 
+use crate::convert::ConversionError;
+use crate::convert::ConvertError;
 use crate::runtime::JsRealm;
 use crate::runtime::JsRuntime;
 use crate::source_map::SourceMapApplication;
@@ -91,8 +93,24 @@ impl Display for CustomError {
 impl std::error::Error for CustomError {}
 
 /// If this error was crated with `custom_error()`, return the specified error
-/// class name. In all other cases this function returns `None`.
+/// class name. A `ConvertError` is a `RangeError` if it is `OutOfRange` and a
+/// `TypeError` otherwise. In all other cases this function returns `None`.
 pub fn get_custom_error_class(error: &Error) -> Option<&'static str> {
+  if let Some(error) = error.downcast_ref::<ConversionError>() {
+    return get_custom_error_class(error.error());
+  }
+  if let Some(error) = error.downcast_ref::<ConvertError>() {
+    return Some(match error {
+      ConvertError::OutOfRange { .. } => "RangeError",
+      ConvertError::ExpectedType { .. }
+      | ConvertError::MissingField { .. }
+      | ConvertError::MissingElement { .. }
+      | ConvertError::LengthMismatch { .. }
+      | ConvertError::Invalid { .. }
+      | ConvertError::Detached
+      | ConvertError::Unreadable { .. } => "TypeError",
+    });
+  }
   error.downcast_ref::<CustomError>().map(|e| e.class)
 }
 
@@ -128,6 +146,11 @@ pub fn to_v8_error<'a>(
   get_class: GetErrorClassFn,
   error: &Error,
 ) -> v8::Local<'a, v8::Value> {
+  if let Some(thrown) = error.downcast_ref::<crate::convert::Thrown>() {
+    if let Some(exception) = thrown.to_v8(scope) {
+      return exception;
+    }
+  }
   let tc_scope = &mut v8::TryCatch::new(scope);
   let cb = JsRealm::exception_state_from_scope(tc_scope)
     .js_build_custom_error_cb
@@ -0,0 +1,120 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+//!  This example shows the `tracing` output of the `convert_tracing` feature for a
+//!  conversion that fails deep in a nested array.
+//!
+//!  Run it with `cargo run --example convert_tracing --features convert_tracing`.
+//!  It prints each failure along with the spans it happened in, innermost last:
+//!
+//!  ```text
+//!  from_v8{ty=alloc::vec::Vec<deno_core::convert::Smi<u32>>} > from_v8{ty=deno_core::convert::Smi<u32>}: conversion failed error=Expected u32, got string
+//!  from_v8{ty=alloc::vec::Vec<deno_core::convert::Smi<u32>>}: conversion failed error=at [1]: Expected u32, got string
+//!  Error: at [1][1]: Expected u32, got string
+//!  ```
+
+use deno_core::convert::Smi;
+use deno_core::v8;
+use deno_core::FromV8;
+use deno_core::JsRuntime;
+use deno_core::RuntimeOptions;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::span;
+use tracing::Event;
+use tracing::Metadata;
+use tracing::Subscriber;
+
+fn main() {
+  tracing::subscriber::set_global_default(PrintSubscriber::default())
+    .expect("Failed to set the subscriber");
+
+  let mut runtime = JsRuntime::new(RuntimeOptions::default());
+  let value = runtime
+    .execute_script("<anon>", "[[1, 2], [3, 'four']]")
+    .expect("Eval failed");
+  let scope = &mut runtime.handle_scope();
+  let value = v8::Local::new(scope, value);
+  if let Err(err) = Vec::<Vec<Smi<u32>>>::from_v8(scope, value) {
+    println!("Error: {err}");
+  }
+}
+
+/// A minimal subscriber that prints every event with the stack of spans it is in.
+#[derive(Default)]
+struct PrintSubscriber {
+  next_id: AtomicU64,
+  spans: Mutex<HashMap<u64, String>>,
+}
+
+thread_local! {
+  static STACK: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Formats fields as `name=value`, with the message first and unlabelled.
+#[derive(Default)]
+struct Fields(String);
+
+impl Visit for Fields {
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    if field.name() == "message" {
+      self.0.insert_str(0, &format!("{value:?}"));
+    } else {
+      write!(self.0, " {}={value:?}", field.name()).unwrap();
+    }
+  }
+
+  fn record_str(&mut self, field: &Field, value: &str) {
+    write!(self.0, "{}={value}", field.name()).unwrap();
+  }
+}
+
+impl Subscriber for PrintSubscriber {
+  fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+    true
+  }
+
+  fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let mut fields = Fields::default();
+    span.record(&mut fields);
+    let name = format!("{}{{{}}}", span.metadata().name(), fields.0);
+    self.spans.lock().unwrap().insert(id, name);
+    span::Id::from_u64(id)
+  }
+
+  fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+  fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+  fn event(&self, event: &Event<'_>) {
+    let mut fields = Fields::default();
+    event.record(&mut fields);
+    let spans = self.spans.lock().unwrap();
+    let stack = STACK.with_borrow(|stack| {
+      stack
+        .iter()
+        .map(|id| spans[id].as_str())
+        .collect::<Vec<_>>()
+        .join(" > ")
+    });
+    println!("{stack}: {}", fields.0);
+  }
+
+  fn enter(&self, span: &span::Id) {
+    STACK.with_borrow_mut(|stack| stack.push(span.into_u64()));
+  }
+
+  fn exit(&self, _span: &span::Id) {
+    STACK.with_borrow_mut(|stack| stack.pop());
+  }
+
+  fn try_close(&self, id: span::Id) -> bool {
+    self.spans.lock().unwrap().remove(&id.into_u64());
+    true
+  }
+}
@@ -169,6 +169,20 @@ pub fn v8_version() -> &'static str {
 /// An internal module re-exporting functions used by the #[op] (`deno_ops`) macro
 #[doc(hidden)]
 pub mod _ops {
+  pub use super::convert::discriminant_from_v8;
+  pub use super::convert::field_array;
+  pub use super::convert::field_element;
+  pub use super::convert::field_error;
+  pub use super::convert::field_key;
+  pub use super::convert::field_keys;
+  pub use super::convert::field_object;
+  pub use super::convert::field_property;
+  pub use super::convert::field_property_at;
+  pub use super::convert::field_symbol_property;
+  pub use super::convert::flatten_error;
+  pub use super::convert::unknown_discriminant;
+  pub use super::convert::unknown_variant;
+  pub use super::convert::variant_tag;
   pub use super::error::throw_type_error;
   pub use super::error_codes::get_error_code;
   pub use super::extensions::Op;
@@ -13,6 +13,8 @@ use super::SnapshotStoreDataStore;
 use super::SnapshottedData;
 use crate::ascii_str;
 use crate::ascii_str_include;
+use crate::convert::CachedValues;
+use crate::convert::ObjectKeyCache;
 use crate::error::exception_to_err_result;
 use crate::error::AnyError;
 use crate::error::GetErrorClassFn;
@@ -174,6 +176,10 @@ impl InnerIsolateState {
 
   pub fn cleanup(&mut self) {
     self.prepare_for_cleanup();
+    // Keys cached by `ObjectBuilder` and values cached by `Cached` are global handles,
+    // which can't outlive the isolate or be left behind in a snapshot
+    self.v8_isolate.remove_slot::<ObjectKeyCache>();
+    self.v8_isolate.remove_slot::<CachedValues>();
 
     let state_ptr = self.v8_isolate.get_data(STATE_DATA_OFFSET);
     // SAFETY: We are sure that it's a valid pointer for whole lifetime of
@@ -515,11 +515,63 @@ pub fn to_v8_slice_any(
 #[allow(clippy::print_stdout, clippy::print_stderr, clippy::unused_async)]
 #[cfg(all(test, not(miri)))]
 mod tests {
+  use crate::convert::for_each_from_v8;
+  use crate::convert::from_v8_into;
+  use crate::convert::js_type_name;
+  use crate::convert::util::collect_from_v8;
+  use crate::convert::ArrayLike;
+  use crate::convert::Be;
+  use crate::convert::BigInt;
+  use crate::convert::BoundedVec;
+  use crate::convert::Cached;
+  use crate::convert::CheckedSmi;
+  use crate::convert::Clamp;
+  use crate::convert::Collect;
+  use crate::convert::ConvertError;
+  #[cfg(feature = "convert_time")]
+  use crate::convert::Date;
+  use crate::convert::DisplayString;
+  use crate::convert::Drained;
+  use crate::convert::Either;
+  use crate::convert::EnumConstants;
+  use crate::convert::ErrorObject;
+  use crate::convert::External;
+  use crate::convert::Frozen;
+  #[cfg(feature = "convert_time")]
+  use crate::convert::HrTime;
+  use crate::convert::Latin1String;
+  use crate::convert::Lazy;
+  use crate::convert::Le;
+  use crate::convert::Maybe;
+  #[cfg(feature = "convert_time")]
+  use crate::convert::Millis;
+  #[cfg(feature = "convert_time")]
+  use crate::convert::Nanos;
+  #[cfg(feature = "convert_net")]
+  use crate::convert::NetAddr;
+  use crate::convert::NewResource;
   use crate::convert::Number;
+  use crate::convert::ObjectBuilder;
+  use crate::convert::ObjectKeys;
+  use crate::convert::OutBuffer;
+  use crate::convert::PartialTuple;
+  use crate::convert::RangeObject;
+  use crate::convert::ResourceRef;
+  use crate::convert::SafeNumber;
+  use crate::convert::Sealed;
   use crate::convert::Smi;
+  use crate::convert::StrictNumber;
+  use crate::convert::TextSpan;
+  use crate::convert::Throw;
+  use crate::convert::ToV8Ext;
+  use crate::convert::TryLazy;
+  use crate::convert::V8Map;
+  use crate::convert::WithPrototype;
   use crate::error::generic_error;
+  use crate::error::type_error;
   use crate::error::AnyError;
   use crate::error::JsError;
+  use crate::error::StdAnyError;
   use crate::external;
   use crate::external::ExternalPointer;
   use crate::op2;
@@ -531,6 +583,7 @@ mod tests {
   use crate::ToV8;
   use anyhow::bail;
   use anyhow::Error;
+  use bytes::Bytes;
   use bytes::BytesMut;
   use futures::Future;
   use serde::Deserialize;
@@ -539,8 +592,34 @@ mod tests {
   use std::borrow::Cow;
   use std::cell::Cell;
   use std::cell::RefCell;
+  use std::collections::BTreeSet;
+  use std::collections::BinaryHeap;
+  use std::collections::HashMap;
+  use std::collections::HashSet;
+  use std::collections::LinkedList;
+  use std::collections::VecDeque;
+  #[cfg(feature = "convert_net")]
+  use std::net::IpAddr;
+  #[cfg(feature = "convert_net")]
+  use std::net::Ipv4Addr;
+  #[cfg(feature = "convert_net")]
+  use std::net::SocketAddr;
+  use std::num::NonZeroI32;
+  use std::num::NonZeroU32;
+  use std::num::NonZeroU64;
+  use std::num::Saturating;
+  use std::num::Wrapping;
+  use std::ops::Range;
+  use std::ops::RangeInclusive;
+  #[cfg(feature = "convert_path")]
+  use std::path::Path;
+  #[cfg(feature = "convert_path")]
+  use std::path::PathBuf;
   use std::rc::Rc;
+  use std::sync::Arc;
   use std::time::Duration;
+  #[cfg(feature = "convert_time")]
+  use std::time::SystemTime;
 
   /// Enough to get functions to JIT.
   pub const JIT_ITERATIONS: usize = 6000;
@@ -640,6 +719,124 @@ mod tests {
       op_smi_to_from_v8,
       op_number_to_from_v8,
       op_bool_to_from_v8,
+      op_option_to_from_v8,
+      op_hashmap_to_from_v8,
+      op_hashmap_int_keys_to_from_v8,
+      op_tuple_to_from_v8,
+      op_array_to_from_v8,
+      op_array_drop_counter,
+      op_vec_to_from_v8,
+      op_vec_drop_counter,
+      op_checked_smi_to_from_v8,
+      op_checked_smi_i64_to_v8,
+      op_safe_number_to_from_v8,
+      op_safe_number_u64_to_v8,
+      op_bigint_i128_to_from_v8,
+      op_bigint_u64_to_from_v8,
+      op_cow_str_to_from_v8,
+      op_derive_to_v8_tuple,
+      op_derive_to_v8_object,
+      op_derive_to_v8_fallible,
+      op_derive_round_trip_array,
+      op_derive_round_trip_object,
+      op_derive_enum,
+      op_derive_tagged_enum,
+      op_result_to_from_v8,
+      op_char_to_from_v8,
+      op_vec_deque_to_from_v8,
+      op_linked_list_to_from_v8,
+      op_hash_set_to_from_v8,
+      op_btree_set_to_from_v8,
+      op_vec_u8_from_v8,
+      op_vec_u32_from_v8,
+      op_vec_f64_from_v8,
+      op_vec_smi_from_v8,
+      op_serde_to_from_v8,
+      op_boxed_slice_to_from_v8,
+      op_rc_slice_to_from_v8,
+      op_arc_bytes_len,
+      op_non_zero_u32_to_from_v8,
+      op_non_zero_i32_to_from_v8,
+      op_non_zero_u64_to_from_v8,
+      op_stash_callback,
+      op_call_stashed_callback,
+      op_take_stashed_callback,
+      op_nested_from_v8,
+      op_v8map_squares,
+      op_v8map_double_keys,
+      op_for_each_sum,
+      op_derive_partial_object,
+      op_js_callback_call,
+      op_symbol_keyed_round_trip,
+      op_symbol_key_get,
+      op_string_keys_len,
+      op_wrapping_u8,
+      op_saturating_u8,
+      op_wrapping_i64,
+      op_saturating_u64,
+      op_unit_to_from_v8,
+      op_partial_tuple_from_v8,
+      op_lazy_to_v8,
+      op_try_lazy_to_v8,
+      op_derive_container,
+      op_range_to_from_v8,
+      op_range_inclusive_to_from_v8,
+      op_range_object_to_from_v8,
+      op_derive_discriminant,
+      op_counter_new,
+      op_counter_increment,
+      op_ordering_to_from_v8,
+      op_ordering_compare,
+      op_atomic_counter_increment,
+      op_atomic_counter_load,
+      op_atomic_to_from_v8,
+      op_latin1_to_from_v8,
+      op_latin1_bytes,
+      op_nested_vec_from_v8,
+      op_throw_error_object,
+      op_throw_any_error,
+      op_lenient_number,
+      op_strict_number,
+      op_boxed_slice_len,
+      op_boxed_slice_drop_counter,
+      op_frozen_config,
+      op_sealed_config,
+      op_frozen_number,
+      op_binary_heap_to_from_v8,
+      op_binary_heap_pop,
+      op_try_to_v8_mixed,
+      op_try_to_v8_infallible,
+      op_be_u32,
+      op_be_to_le_u32,
+      op_be_one,
+      op_le_i16,
+      op_vec_to_v8_len,
+      op_to_v8_array_len,
+      op_array_like_join,
+      op_optional_error,
+      op_optional_boxed_error,
+      op_flatten_round_trip,
+      op_cached_label,
+      op_bounded_vec_join,
+      op_clamp_gain,
+      op_clamp_u8,
+      op_clamp_to_v8,
+      op_display_string,
+      op_format_args,
+      op_either_describe,
+      op_either_round_trip,
+      op_derive_phantom,
+      op_derive_phantom_tuple,
+      op_derive_rename_all,
+      op_register_prototype,
+      op_with_prototype,
+      op_text_span,
+      op_fill_out_buffer,
+      op_maybe_round_trip,
+      op_derive_maybe_patch,
+      op_collect_squares,
+      op_collect_even,
+      op_collect_unsafe,
     ],
     state = |state| {
       state.put(1234u32);
@@ -647,6 +844,33 @@ mod tests {
     }
   );
 
+  #[cfg(feature = "convert_net")]
+  deno_core::extension!(
+    testing_convert_net,
+    ops = [
+      op_ip_addr_to_from_v8,
+      op_ipv4_addr_to_from_v8,
+      op_socket_addr_to_net_addr,
+      op_net_addr_to_socket_addr,
+    ],
+  );
+
+  #[cfg(feature = "convert_path")]
+  deno_core::extension!(testing_convert_path, ops = [op_path_to_from_v8,],);
+
+  #[cfg(feature = "convert_time")]
+  deno_core::extension!(
+    testing_convert_time,
+    ops = [
+      op_date_to_from_v8,
+      op_date_from_millis,
+      op_duration_millis_to_nanos,
+      op_duration_nanos_to_millis,
+      op_duration_hrtime_to_nanos,
+      op_duration_nanos_to_hrtime,
+    ],
+  );
+
   thread_local! {
     static FAIL: Cell<bool> = const { Cell::new(false) }
   }
@@ -664,7 +888,15 @@ mod tests {
   /// Run a test for a single op.
   fn run_test2(repeat: usize, op: &str, test: &str) -> Result<(), AnyError> {
     let mut runtime = JsRuntime::new(RuntimeOptions {
-      extensions: vec![testing::init_ops_and_esm()],
+      extensions: vec![
+        testing::init_ops_and_esm(),
+        #[cfg(feature = "convert_net")]
+        testing_convert_net::init_ops_and_esm(),
+        #[cfg(feature = "convert_path")]
+        testing_convert_path::init_ops_and_esm(),
+        #[cfg(feature = "convert_time")]
+        testing_convert_time::init_ops_and_esm(),
+      ],
       ..Default::default()
     });
     let err_mapper =
@@ -2424,4 +2656,4720 @@ mod tests {
     )?;
     Ok(())
   }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_option_to_from_v8(
+    #[from_v8] value: Option<Smi<u32>>,
+  ) -> Option<Smi<u32>> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_option_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_option_to_from_v8",
+      r"
+        assert(op_option_to_from_v8(1) === 1);
+        assert(op_option_to_from_v8(null) === null);
+        // undefined is accepted as `None`, which is returned as `null`
+        assert(op_option_to_from_v8(undefined) === null);
+        try {
+          op_option_to_from_v8('a');
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected u32');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_hashmap_to_from_v8(
+    #[from_v8] value: HashMap<String, Smi<u32>>,
+  ) -> HashMap<String, Smi<u32>> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_hashmap_int_keys_to_from_v8(
+    #[from_v8] value: HashMap<u32, Smi<u32>>,
+  ) -> HashMap<u32, Smi<u32>> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_hashmap_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_hashmap_to_from_v8, op_hashmap_int_keys_to_from_v8",
+      r"
+        let out = op_hashmap_to_from_v8({ a: 1, b: 2 });
+        assert(Object.keys(out).length == 2);
+        assert(out.a === 1 && out.b === 2);
+
+        // Symbol keys are skipped
+        out = op_hashmap_to_from_v8({ a: 1, [Symbol('b')]: 2 });
+        assert(Object.getOwnPropertySymbols(out).length == 0);
+        assert(Object.keys(out).length == 1);
+
+        // `__proto__` is an own property, not the prototype
+        out = op_hashmap_to_from_v8({ ['__proto__']: 1 });
+        assert(Object.getPrototypeOf(out) === Object.prototype);
+        assert(Object.getOwnPropertyDescriptor(out, '__proto__').value === 1);
+
+        out = op_hashmap_int_keys_to_from_v8({ 1: 1, 2: 2 });
+        assert(out[1] === 1 && out[2] === 2);
+        try {
+          op_hashmap_int_keys_to_from_v8({ 1: 1, a: 2 });
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, `Invalid key 'a'`);
+        }
+        try {
+          op_hashmap_int_keys_to_from_v8({ 1: 1, '01': 2 });
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, `Duplicate key '01'`);
+        }
+        try {
+          op_hashmap_to_from_v8(1);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected object');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_tuple_to_from_v8(
+    #[from_v8] value: (Smi<u32>, bool),
+  ) -> (Smi<u32>, bool) {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_tuple_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_tuple_to_from_v8",
+      r"
+        const out = op_tuple_to_from_v8([1, true]);
+        assert(Array.isArray(out) && out.length == 2);
+        assert(out[0] === 1 && out[1] === true);
+        try {
+          op_tuple_to_from_v8([1]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected array of length 2');
+        }
+        try {
+          op_tuple_to_from_v8(['a', true]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [0]: Expected u32');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_array_to_from_v8(#[from_v8] value: [Smi<u8>; 4]) -> [Smi<u8>; 4] {
+    value
+  }
+
+  thread_local! {
+    static DROP_COUNT: Cell<usize> = const { Cell::new(0) };
+  }
+
+  /// Converts from a non-negative smi, and counts how many times it is dropped.
+  struct DropCounter;
+
+  impl Drop for DropCounter {
+    fn drop(&mut self) {
+      DROP_COUNT.with(|c| c.set(c.get() + 1));
+    }
+  }
+
+  impl<'a> FromV8<'a> for DropCounter {
+    type Error = StdAnyError;
+
+    fn from_v8(
+      scope: &mut v8::HandleScope<'a>,
+      value: v8::Local<'a, v8::Value>,
+    ) -> Result<Self, Self::Error> {
+      let Smi(v) = Smi::<i32>::from_v8(scope, value)?;
+      if v < 0 {
+        return Err(generic_error("Negative value").into());
+      }
+      Ok(DropCounter)
+    }
+  }
+
+  #[op2]
+  pub fn op_array_drop_counter(#[from_v8] _value: [DropCounter; 4]) {}
+
+  #[tokio::test]
+  pub async fn test_op_array_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_array_to_from_v8",
+      r"
+        const out = op_array_to_from_v8([1, 2, 3, 255]);
+        assert(Array.isArray(out) && out.length == 4);
+        assert(out[0] === 1 && out[3] === 255);
+        try {
+          op_array_to_from_v8([1, 2, 3]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected array of length 4');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  pub async fn test_op_array_drop_on_failure(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    DROP_COUNT.with(|c| c.set(0));
+    run_test2(
+      1,
+      "op_array_drop_counter",
+      r"
+        try {
+          op_array_drop_counter([1, 2, -1, 4]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [2]: Negative value');
+        }",
+    )?;
+    // The two elements converted before the failure are dropped
+    assert_eq!(DROP_COUNT.with(|c| c.get()), 2);
+    run_test2(
+      1,
+      "op_array_drop_counter",
+      "op_array_drop_counter([1, 2, 3, 4])",
+    )?;
+    assert_eq!(DROP_COUNT.with(|c| c.get()), 6);
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_vec_to_from_v8(#[from_v8] value: Vec<Smi<u32>>) -> Vec<Smi<u32>> {
+    value
+  }
+
+  #[op2]
+  pub fn op_vec_drop_counter(#[from_v8] _value: Vec<DropCounter>) {}
+
+  #[tokio::test]
+  pub async fn test_op_vec_to_from_v8() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_vec_to_from_v8",
+      r"
+        let out = op_vec_to_from_v8([1, 2, 3]);
+        assert(Array.isArray(out) && out.length == 3);
+        assert(out[0] === 1 && out[1] === 2 && out[2] === 3);
+        out = op_vec_to_from_v8([]);
+        assert(Array.isArray(out) && out.length == 0);
+        try {
+          op_vec_to_from_v8([1, 'a']);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [1]: Expected u32');
+        }
+        // A sparse array fails on its first hole, without reserving room for its length
+        try {
+          op_vec_to_from_v8(new Array(2 ** 32 - 1));
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [0]: Expected u32, got undefined');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  pub async fn test_op_vec_drop_on_failure(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    DROP_COUNT.with(|c| c.set(0));
+    run_test2(
+      1,
+      "op_vec_drop_counter",
+      r"
+        try {
+          op_vec_drop_counter([1, 2, -1, 4]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [2]: Negative value');
+        }",
+    )?;
+    assert_eq!(DROP_COUNT.with(|c| c.get()), 2);
+    Ok(())
+  }
+
+  /// Panics when converting a negative smi.
+  #[allow(dead_code)]
+  struct PanicOnNegative(DropCounter);
+
+  impl<'a> FromV8<'a> for PanicOnNegative {
+    type Error = StdAnyError;
+
+    fn from_v8(
+      scope: &mut v8::HandleScope<'a>,
+      value: v8::Local<'a, v8::Value>,
+    ) -> Result<Self, Self::Error> {
+      let Smi(v) = Smi::<i32>::from_v8(scope, value)?;
+      if v < 0 {
+        panic!("Negative value");
+      }
+      Ok(PanicOnNegative(DropCounter))
+    }
+  }
+
+  #[test]
+  pub fn test_vec_drop_on_panic() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let value = runtime.execute_script("", "[1, 2, -1, 4]").unwrap();
+    let scope = &mut runtime.handle_scope();
+    let value = v8::Local::new(scope, value);
+    DROP_COUNT.with(|c| c.set(0));
+    // The panic must not cross a V8 frame, so we convert directly rather than via an op
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      Vec::<PanicOnNegative>::from_v8(scope, value)
+    }));
+    assert!(res.is_err());
+    // The two elements converted before the panic are dropped during unwinding
+    assert_eq!(DROP_COUNT.with(|c| c.get()), 2);
+  }
+
+  #[tokio::test]
+  pub async fn test_op_vec_adversarial(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_vec_to_from_v8, op_tuple_to_from_v8",
+      r"
+        const arr = [1, 2, 3];
+        Object.defineProperty(arr, 1, { get() { throw new Error('boom'); } });
+        try {
+          op_vec_to_from_v8(arr);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Array element 1 could not be read');
+        }
+        const tuple = [1, true];
+        Object.defineProperty(tuple, 0, { get() { throw new Error('boom'); } });
+        try {
+          op_tuple_to_from_v8(tuple);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Tuple element 0 could not be read');
+        }
+        // Proxies are not arrays, even if their target is
+        const proxy = new Proxy([1, 2, 3], { get() { throw new Error('boom'); } });
+        try {
+          op_vec_to_from_v8(proxy);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected array');
+        }
+        // Holes in sparse arrays read as undefined
+        try {
+          op_vec_to_from_v8([1, , 3]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [1]: Expected u32');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_checked_smi_to_from_v8(
+    #[from_v8] value: CheckedSmi<u32>,
+  ) -> CheckedSmi<u32> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_checked_smi_i64_to_v8(#[number] value: i64) -> CheckedSmi<i64> {
+    CheckedSmi(value)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_checked_smi_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_checked_smi_to_from_v8, op_checked_smi_i64_to_v8",
+      r"
+        for (const n of [0, 1, Math.pow(2, 31) - 1]) {
+          assert(op_checked_smi_to_from_v8(n) === n);
+        }
+        for (const n of [Math.pow(2, 31), Math.pow(2, 32) - 1, -1, 1.5, NaN]) {
+          try {
+            op_checked_smi_to_from_v8(n);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'Value out of range for u32');
+          }
+        }
+        for (const n of [-Math.pow(2, 31), -1, Math.pow(2, 31) - 1]) {
+          assert(op_checked_smi_i64_to_v8(n) === n);
+        }
+        try {
+          op_checked_smi_i64_to_v8(Math.pow(2, 31));
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'i64 value does not fit in a smi');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_safe_number_to_from_v8(
+    #[from_v8] value: SafeNumber<u64>,
+  ) -> SafeNumber<u64> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_safe_number_u64_to_v8(#[bigint] value: u64) -> SafeNumber<u64> {
+    SafeNumber(value)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_safe_number_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_safe_number_to_from_v8, op_safe_number_u64_to_v8",
+      r"
+        for (const n of [0, 1, Number.MAX_SAFE_INTEGER]) {
+          assert(op_safe_number_to_from_v8(n) === n);
+        }
+        // 9007199254740993 is not representable, and parses as 2 ** 53
+        for (const n of [9007199254740993, -1, 1.5, Infinity, NaN]) {
+          try {
+            op_safe_number_to_from_v8(n);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'Expected u64 that is a safe integer');
+          }
+        }
+        assert(op_safe_number_u64_to_v8(9007199254740991n) === Number.MAX_SAFE_INTEGER);
+        try {
+          op_safe_number_u64_to_v8(9007199254740993n);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'u64 value is not a safe integer');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_bigint_i128_to_from_v8(
+    #[from_v8] value: BigInt<i128>,
+  ) -> BigInt<i128> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_bigint_u64_to_from_v8(
+    #[from_v8] value: BigInt<u64>,
+  ) -> BigInt<u64> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_bigint_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_bigint_i128_to_from_v8, op_bigint_u64_to_from_v8",
+      r"
+        for (const n of [0n, 1n, -1n, 2n ** 64n, -(2n ** 64n), 2n ** 127n - 1n, -(2n ** 127n)]) {
+          assert(op_bigint_i128_to_from_v8(n) === n);
+        }
+        for (const n of [2n ** 127n, -(2n ** 127n) - 1n, 2n ** 200n]) {
+          try {
+            op_bigint_i128_to_from_v8(n);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'BigInt value does not fit in i128 without loss');
+          }
+        }
+        for (const n of [0n, 2n ** 64n - 1n]) {
+          assert(op_bigint_u64_to_from_v8(n) === n);
+        }
+        for (const n of [-1n, 2n ** 64n]) {
+          try {
+            op_bigint_u64_to_from_v8(n);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'BigInt value does not fit in u64 without loss');
+          }
+        }
+        try {
+          op_bigint_u64_to_from_v8(1);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected u64 BigInt');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[op2]
+  #[to_v8]
+  pub fn op_date_to_from_v8(#[from_v8] value: Date) -> Date {
+    value
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[op2]
+  #[to_v8]
+  pub fn op_date_from_millis(millis: f64) -> Date {
+    Date::from_millis(millis).unwrap()
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[tokio::test]
+  pub async fn test_op_date_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_date_to_from_v8, op_date_from_millis",
+      r"
+        for (const ms of [0, 1, -1, 1700000000123, -1700000000123, 8.64e15, -8.64e15]) {
+          const d = op_date_to_from_v8(new Date(ms));
+          assert(d instanceof Date);
+          assert(d.getTime() === ms);
+          assert(op_date_from_millis(ms).getTime() === ms);
+        }
+        for (const value of [new Date(NaN), 0, '2020-01-01']) {
+          try {
+            op_date_to_from_v8(value);
+            assert(false);
+          } catch (e) {
+            assert(e instanceof TypeError);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_cow_str_to_from_v8(
+    #[from_v8] value: Vec<Cow<str>>,
+  ) -> Vec<Cow<'static, str>> {
+    value
+      .into_iter()
+      .map(|s| Cow::Owned(format!("{s}!")))
+      .chain([Cow::Borrowed("static")])
+      .collect()
+  }
+
+  #[tokio::test]
+  pub async fn test_op_cow_str_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_cow_str_to_from_v8",
+      r"
+        const strings = [
+          '',
+          'ascii',
+          'café ÿ',
+          '\u{1F600} two-byte',
+          'a'.repeat(10000),
+          'é'.repeat(10000),
+          'a'.repeat(100) + '\xff\x80',
+        ];
+        const result = op_cow_str_to_from_v8(strings);
+        assert(result.length === strings.length + 1);
+        for (let i = 0; i < strings.length; i++) {
+          assert(result[i] === strings[i] + '!');
+        }
+        assert(result[strings.length] === 'static');
+        try {
+          op_cow_str_to_from_v8([1]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected string');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[derive(ToV8)]
+  pub struct DeriveTuple(Smi<u32>, bool, #[v8(skip)] (), Option<Smi<u8>>);
+
+  #[derive(ToV8)]
+  pub struct DeriveArray {
+    a: Smi<u32>,
+    #[v8(skip)]
+    #[allow(dead_code)]
+    skipped: u32,
+    b: Vec<bool>,
+  }
+
+  #[derive(ToV8)]
+  #[v8(object)]
+  pub struct DeriveObject<T> {
+    r#type: T,
+    nested: DeriveArray,
+  }
+
+  #[derive(ToV8)]
+  pub struct DeriveFallible(CheckedSmi<i64>);
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_to_v8_tuple() -> DeriveTuple {
+    DeriveTuple(Smi(1), true, (), None)
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_to_v8_object() -> DeriveObject<Smi<u8>> {
+    DeriveObject {
+      r#type: Smi(2),
+      nested: DeriveArray {
+        a: Smi(3),
+        skipped: 4,
+        b: vec![true, false],
+      },
+    }
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_to_v8_fallible() -> DeriveFallible {
+    DeriveFallible(CheckedSmi(i64::MAX))
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_to_v8() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_to_v8_tuple, op_derive_to_v8_object, op_derive_to_v8_fallible",
+      r#"
+        const tuple = op_derive_to_v8_tuple();
+        assert(Array.isArray(tuple));
+        assert(tuple.length === 3);
+        assert(tuple[0] === 1 && tuple[1] === true && tuple[2] === null);
+        const object = op_derive_to_v8_object();
+        assert(!Array.isArray(object));
+        assert(JSON.stringify(object) === '{"type":2,"nested":[3,[true,false]]}');
+        try {
+          op_derive_to_v8_fallible();
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, "at [0]: i64 value does not fit in a smi");
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  pub struct DeriveRoundTripArray(Smi<u32>, #[v8(skip)] (), Vec<bool>);
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object)]
+  pub struct DeriveRoundTripObject {
+    #[v8(rename = "camelCase")]
+    snake_case: Smi<u8>,
+    optional: Option<bool>,
+    nested: DeriveRoundTripArray,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_round_trip_array(
+    #[from_v8] value: DeriveRoundTripArray,
+  ) -> DeriveRoundTripArray {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_round_trip_object(
+    #[from_v8] value: DeriveRoundTripObject,
+  ) -> DeriveRoundTripObject {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_round_trip(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_round_trip_array, op_derive_round_trip_object",
+      r#"
+        const array = op_derive_round_trip_array([1, [true, false]]);
+        assert(JSON.stringify(array) === '[1,[true,false]]');
+        const object = op_derive_round_trip_object({ camelCase: 2, nested: [3, []] });
+        assert(JSON.stringify(object) === '{"camelCase":2,"optional":null,"nested":[3,[]]}');
+        const withOptional = op_derive_round_trip_object({ camelCase: 2, optional: true, nested: [3, []] });
+        assert(withOptional.optional === true);
+        for (const [value, message] of [
+          [{}, "Missing field 'camelCase'"],
+          [{ camelCase: 1 }, "Missing field 'nested'"],
+          [{ camelCase: 1, nested: [1] }, "at nested: Expected array of length 2"],
+          [{ camelCase: 'a', nested: [1, []] }, "at camelCase: Expected u8"],
+          [[], "Expected object"],
+        ]) {
+          try {
+            op_derive_round_trip_object(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }
+        try {
+          op_derive_round_trip_array({});
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, "Expected array");
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[derive(Debug, PartialEq, FromV8, ToV8)]
+  pub enum DeriveMessage {
+    Ping,
+    #[v8(rename = "text")]
+    Text(Smi<u32>),
+    Pair(Smi<u8>, bool),
+    Move {
+      x: Smi<i32>,
+      y: Smi<i32>,
+    },
+  }
+
+  #[derive(Debug, PartialEq, FromV8, ToV8)]
+  #[v8(tag = "type")]
+  pub enum DeriveTaggedMessage {
+    Ping,
+    Move {
+      x: Smi<i32>,
+      #[v8(rename = "yPos")]
+      y: Option<Smi<i32>>,
+    },
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_enum(#[from_v8] value: DeriveMessage) -> DeriveMessage {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_tagged_enum(
+    #[from_v8] value: DeriveTaggedMessage,
+  ) -> DeriveTaggedMessage {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_enum() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_enum, op_derive_tagged_enum",
+      r#"
+        for (const value of ["Ping", ["text", 1], ["Pair", [2, true]], ["Move", [-1, 3]]]) {
+          assert(JSON.stringify(op_derive_enum(value)) === JSON.stringify(value));
+        }
+        for (const value of ["Ping", { type: "Move", x: 1, yPos: 2 }]) {
+          assert(JSON.stringify(op_derive_tagged_enum(value)) === JSON.stringify(value));
+        }
+        assert(op_derive_tagged_enum({ type: "Move", x: 1 }).yPos === null);
+        for (const [op, value, message] of [
+          [op_derive_enum, "Pong", "Unknown variant 'Pong'"],
+          [op_derive_enum, "Text", "Unknown variant 'Text'"],
+          [op_derive_enum, ["Pong", 1], "Unknown variant 'Pong'"],
+          [op_derive_enum, ["Pair", [1]], "Expected array of length 2"],
+          [op_derive_enum, [1, 1], "Expected string tag"],
+          [op_derive_tagged_enum, { type: "Pong" }, "Unknown variant 'Pong'"],
+          [op_derive_tagged_enum, { x: 1 }, "Missing field 'type'"],
+          [op_derive_tagged_enum, { type: "Move" }, "Missing field 'x'"],
+        ]) {
+          try {
+            op(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_result_to_from_v8(
+    #[from_v8] value: Vec<Result<Smi<u32>, Cow<str>>>,
+  ) -> Vec<Result<Smi<u32>, Cow<'static, str>>> {
+    value
+      .into_iter()
+      .map(|item| match item {
+        Ok(Smi(n)) => Ok(Smi(n + 1)),
+        Err(err) => Err(Cow::Owned(format!("{err}!"))),
+      })
+      .collect()
+  }
+
+  #[tokio::test]
+  pub async fn test_op_result_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_result_to_from_v8",
+      r#"
+        const result = op_result_to_from_v8([{ ok: true, value: 1 }, { ok: false, error: "oops" }]);
+        assert(JSON.stringify(result) === '[{"ok":true,"value":2},{"ok":false,"error":"oops!"}]');
+        for (const [value, message] of [
+          [{ value: 1 }, "at [0]: Missing field 'ok'"],
+          [{ ok: 1, value: 1 }, "Expected boolean 'ok'"],
+          [{ ok: true, value: "a" }, "at value: Expected u32"],
+          [{ ok: false, error: 1 }, "at error: Expected string"],
+          [1, "Expected object"],
+        ]) {
+          try {
+            op_result_to_from_v8([value]);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_char_to_from_v8(#[from_v8] value: char) -> char {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_char_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_char_to_from_v8",
+      r"
+        for (const c of ['a', 'é', '中', '\u{1F600}']) {
+          assert(op_char_to_from_v8(c) === c);
+        }
+        for (const value of ['', 'ab', '\u{1F600}a', '\ud800', '\udc00\ud800']) {
+          try {
+            op_char_to_from_v8(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'Expected a single character');
+          }
+        }
+        try {
+          op_char_to_from_v8(1);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected string');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[op2]
+  #[to_v8]
+  pub fn op_duration_millis_to_nanos(#[from_v8] value: Millis) -> Nanos {
+    Nanos(value.0)
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[op2]
+  #[to_v8]
+  pub fn op_duration_nanos_to_millis(#[from_v8] value: Nanos) -> Millis {
+    Millis(value.0)
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[tokio::test]
+  pub async fn test_op_duration_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_duration_millis_to_nanos, op_duration_nanos_to_millis",
+      r"
+        // Sub-millisecond durations are kept as fractional milliseconds
+        assert(op_duration_nanos_to_millis(1500n) === 0.0015);
+        assert(op_duration_nanos_to_millis(1n) === 0.000001);
+        assert(op_duration_nanos_to_millis(2500000000n) === 2500);
+        // Milliseconds are rounded to the nearest nanosecond
+        assert(op_duration_millis_to_nanos(0.0000004) === 0n);
+        assert(op_duration_millis_to_nanos(0.0000006) === 1n);
+        assert(op_duration_millis_to_nanos(1.5) === 1500000n);
+        assert(op_duration_millis_to_nanos(-0) === 0n);
+        for (const value of [-1, NaN, Infinity, 1e300]) {
+          try {
+            op_duration_millis_to_nanos(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'Expected a non-negative, finite number of milliseconds');
+          }
+        }
+        for (const value of [-1n, 2n ** 128n]) {
+          try {
+            op_duration_nanos_to_millis(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'Expected a non-negative number of nanoseconds');
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "convert_net")]
+  #[op2]
+  #[to_v8]
+  pub fn op_ip_addr_to_from_v8(#[from_v8] value: IpAddr) -> IpAddr {
+    value
+  }
+
+  #[cfg(feature = "convert_net")]
+  #[op2]
+  #[to_v8]
+  pub fn op_ipv4_addr_to_from_v8(#[from_v8] value: Ipv4Addr) -> Ipv4Addr {
+    value
+  }
+
+  #[cfg(feature = "convert_net")]
+  #[op2]
+  #[to_v8]
+  pub fn op_socket_addr_to_net_addr(#[from_v8] value: SocketAddr) -> NetAddr {
+    NetAddr(value)
+  }
+
+  #[cfg(feature = "convert_net")]
+  #[op2]
+  #[to_v8]
+  pub fn op_net_addr_to_socket_addr(#[from_v8] value: NetAddr) -> SocketAddr {
+    value.0
+  }
+
+  #[cfg(feature = "convert_net")]
+  #[tokio::test]
+  pub async fn test_op_net_addr_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_ip_addr_to_from_v8, op_ipv4_addr_to_from_v8, op_socket_addr_to_net_addr, op_net_addr_to_socket_addr",
+      r"
+        for (const ip of ['127.0.0.1', '::1', '::ffff:1.2.3.4', 'fe80::1']) {
+          assert(op_ip_addr_to_from_v8(ip) === ip);
+        }
+        assert(op_ipv4_addr_to_from_v8('127.0.0.1') === '127.0.0.1');
+        for (const [op, value, message] of [
+          [op_ip_addr_to_from_v8, 'fe80::1%eth0', 'Invalid IP address'],
+          [op_ip_addr_to_from_v8, '256.0.0.1', 'Invalid IP address'],
+          [op_ipv4_addr_to_from_v8, '::1', 'Invalid IPv4 address'],
+          [op_socket_addr_to_net_addr, '127.0.0.1:99999', 'Invalid socket address'],
+          [op_socket_addr_to_net_addr, '127.0.0.1:abc', 'Invalid socket address'],
+          [op_socket_addr_to_net_addr, '::1:80', 'Invalid socket address'],
+          [op_net_addr_to_socket_addr, { hostname: 'localhost', port: 80 }, 'Invalid IP address'],
+          [op_net_addr_to_socket_addr, { hostname: '::1', port: 65536 }, 'Value out of range for u16'],
+          [op_net_addr_to_socket_addr, { hostname: '::1' }, 'Missing field'],
+        ]) {
+          try {
+            op(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }
+        let addr = op_socket_addr_to_net_addr('[::ffff:1.2.3.4]:8080');
+        assert(addr.hostname === '::ffff:1.2.3.4' && addr.port === 8080);
+        addr = op_socket_addr_to_net_addr('[fe80::1%2]:80');
+        assert(addr.hostname === 'fe80::1' && addr.port === 80);
+        assert(op_net_addr_to_socket_addr({ hostname: '::1', port: 443 }) === '[::1]:443');
+        assert(op_net_addr_to_socket_addr({ hostname: '127.0.0.1', port: 0 }) === '127.0.0.1:0');",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_vec_deque_to_from_v8(
+    #[from_v8] mut value: VecDeque<Smi<u32>>,
+  ) -> VecDeque<Smi<u32>> {
+    value.push_front(Smi(0));
+    value.push_back(Smi(100));
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_linked_list_to_from_v8(
+    #[from_v8] mut value: LinkedList<Smi<u32>>,
+  ) -> LinkedList<Smi<u32>> {
+    value.push_front(Smi(0));
+    value.push_back(Smi(100));
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_vec_deque_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_vec_deque_to_from_v8, op_linked_list_to_from_v8",
+      r"
+        for (const op of [op_vec_deque_to_from_v8, op_linked_list_to_from_v8]) {
+          assert(op([1, 2, 3]).join() === '0,1,2,3,100');
+          assert(op([]).join() === '0,100');
+          try {
+            op([1, 'a']);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'at [1]: Expected u32');
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_vec_deque_to_v8_order() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    // Push to both ends so the ring buffer wraps around
+    let mut deque = VecDeque::with_capacity(4);
+    deque.push_back(Smi(2u32));
+    deque.push_back(Smi(3));
+    deque.push_front(Smi(1));
+    deque.push_front(Smi(0));
+    let value = deque.to_v8(scope).unwrap();
+    let value = v8::Local::<v8::Array>::try_from(value).unwrap();
+    let elements = (0..value.length())
+      .map(|i| {
+        value
+          .get_index(scope, i)
+          .unwrap()
+          .uint32_value(scope)
+          .unwrap()
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(elements, [0, 1, 2, 3]);
+  }
+
+  #[test]
+  fn test_byte_buffer_to_v8_without_copy() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    fn data(value: v8::Local<v8::Value>) -> (*const u8, usize) {
+      let view = v8::Local::<v8::Uint8Array>::try_from(value).unwrap();
+      (view.data() as *const u8, view.byte_length())
+    }
+    // Spare capacity would force `into_boxed_slice` to reallocate
+    let mut vec = Vec::with_capacity(16);
+    vec.extend_from_slice(&[1u8, 2, 3]);
+    let ptr = vec.as_ptr();
+    let value = vec.to_v8(scope).unwrap();
+    assert_eq!(data(value), (ptr, 3));
+    let boxed: Box<[u8]> = vec![4u8; 8].into_boxed_slice();
+    let ptr = boxed.as_ptr();
+    let value = boxed.to_v8(scope).unwrap();
+    assert_eq!(data(value), (ptr, 8));
+    let value = Vec::<u8>::new().to_v8(scope).unwrap();
+    assert_eq!(data(value).1, 0);
+    // Round-trips through the bulk-copy `FromV8` path
+    let value = vec![5u8, 6, 7].to_v8(scope).unwrap();
+    assert_eq!(Vec::<u8>::from_v8(scope, value).unwrap(), [5, 6, 7]);
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_hash_set_to_from_v8(
+    #[from_v8] value: HashSet<Smi<u32>>,
+  ) -> HashSet<Smi<u32>> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_btree_set_to_from_v8(
+    #[from_v8] value: BTreeSet<Smi<u32>>,
+  ) -> BTreeSet<Smi<u32>> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_set_to_from_v8() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_hash_set_to_from_v8, op_btree_set_to_from_v8",
+      r"
+        assert([...op_btree_set_to_from_v8(new Set([3, 1, 2]))].join() === '1,2,3');
+        assert([...op_btree_set_to_from_v8([3, 1, 3, 2, 1])].join() === '1,2,3');
+        for (const input of [new Set([3, 1, 2]), [3, 1, 3, 2, 1]]) {
+          const set = op_hash_set_to_from_v8(input);
+          assert(set instanceof Set);
+          assert([...set].sort().join() === '1,2,3');
+        }
+        assert(op_hash_set_to_from_v8([]).size === 0);
+        for (const [value, message] of [
+          [new Set([1, 'a']), 'at [1]: Expected u32'],
+          [[1, 'a'], 'at [1]: Expected u32'],
+          [{}, 'Expected Set or array'],
+        ]) {
+          for (const op of [op_hash_set_to_from_v8, op_btree_set_to_from_v8]) {
+            try {
+              op(value);
+              assert(false);
+            } catch (e) {
+              assertErrorContains(e, message);
+            }
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_vec_u8_from_v8(#[from_v8] value: Vec<u8>) -> Vec<Smi<u8>> {
+    value.into_iter().map(Smi).collect()
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_vec_u32_from_v8(#[from_v8] value: Vec<u32>) -> Vec<Number<u32>> {
+    value.into_iter().map(Number).collect()
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_vec_f64_from_v8(#[from_v8] value: Vec<f64>) -> Vec<Number<f64>> {
+    value.into_iter().map(Number).collect()
+  }
+
+  #[op2]
+  pub fn op_vec_smi_from_v8(#[from_v8] value: Vec<Smi<u32>>) -> u32 {
+    value.len() as _
+  }
+
+  #[tokio::test]
+  pub async fn test_op_typed_vec_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_vec_u8_from_v8, op_vec_u32_from_v8, op_vec_f64_from_v8, op_vec_smi_from_v8",
+      r"
+        const littleEndian = new Uint8Array(new Uint16Array([1]).buffer)[0] === 1;
+        const words = new Uint32Array([0x01020304, 0xa0b0c0d0]);
+        assert(op_vec_u32_from_v8(words).join() === '16909060,2695938256');
+        const bytes = op_vec_u8_from_v8(words).join();
+        assert(bytes === (littleEndian ? '4,3,2,1,208,192,176,160' : '1,2,3,4,160,176,192,208'));
+        assert(op_vec_u8_from_v8(words.buffer).join() === bytes);
+        assert(op_vec_u8_from_v8(new DataView(words.buffer, 4)).join() === bytes.split(',').slice(4).join());
+        assert(op_vec_u32_from_v8(words.subarray(1)).join() === '2695938256');
+        assert(op_vec_u8_from_v8(new Uint8Array([1, 2, 3])).join() === '1,2,3');
+        assert(op_vec_u8_from_v8([1, 2, 3]).join() === '1,2,3');
+        assert(op_vec_u32_from_v8([1, 2, 3]).join() === '1,2,3');
+        const floats = new Float64Array([1.5, -0.25, 1e300]);
+        assert(op_vec_f64_from_v8(floats).join() === floats.join());
+        assert(op_vec_f64_from_v8([1.5, -0.25]).join() === '1.5,-0.25');
+        assert(op_vec_u8_from_v8(new Uint8Array()).length === 0);
+        assert(op_vec_u32_from_v8(new Uint16Array([1, 65535])).join() === '1,65535');
+        assert(op_vec_u32_from_v8(new Uint8ClampedArray([255])).join() === '255');
+        assert(op_vec_f64_from_v8(new Float32Array([1.5, -0.25])).join() === '1.5,-0.25');
+        assert(op_vec_f64_from_v8(new Int32Array([-1, 2 ** 31 - 1])).join() === '-1,2147483647');
+        // Widening respects the view's offset and length
+        assert(op_vec_f64_from_v8(new Uint8Array([1, 2, 3]).subarray(1, 2)).join() === '2');
+        const detached = new Uint8Array(8);
+        detached.buffer.transfer();
+        // Views created before the transfer, including ones that would be widened
+        const detachedWords = new Uint32Array(2);
+        const detachedHalves = new Uint16Array(detachedWords.buffer);
+        const detachedView = new DataView(detachedWords.buffer);
+        detachedWords.buffer.transfer();
+        for (const [op, value, message] of [
+          [op_vec_u8_from_v8, detached, 'ArrayBuffer is detached'],
+          [op_vec_u8_from_v8, detached.buffer, 'ArrayBuffer is detached'],
+          [op_vec_u8_from_v8, detachedView, 'ArrayBuffer is detached'],
+          [op_vec_u32_from_v8, detachedWords, 'ArrayBuffer is detached'],
+          [op_vec_u32_from_v8, detachedHalves, 'ArrayBuffer is detached'],
+          [op_vec_f64_from_v8, detachedHalves, 'ArrayBuffer is detached'],
+          [op_vec_u8_from_v8, {}, 'Expected ArrayBuffer, ArrayBufferView, or array'],
+          [op_vec_u8_from_v8, [1, 256], 'at [1]: Expected u8'],
+          [op_vec_u32_from_v8, new Array(2 ** 32 - 1), 'at [0]: Expected u32'],
+          [op_vec_u32_from_v8, words.buffer, 'Expected Uint32Array or array'],
+          [op_vec_u32_from_v8, new Float64Array([1, 2]), 'Expected Uint32Array, got Float64Array'],
+          [op_vec_u32_from_v8, new Int32Array(1), 'Expected Uint32Array, got Int32Array'],
+          [op_vec_u32_from_v8, new DataView(words.buffer), 'Expected Uint32Array, got DataView'],
+          [op_vec_f64_from_v8, new BigInt64Array(1), 'Expected Float64Array, got BigInt64Array'],
+          [op_vec_smi_from_v8, new Uint32Array(1), 'Expected array, got Uint32Array'],
+        ]) {
+          try {
+            op(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "convert_path")]
+  #[op2]
+  #[to_v8]
+  pub fn op_path_to_from_v8(#[from_v8] value: PathBuf) -> PathBuf {
+    value.join("file.txt")
+  }
+
+  #[cfg(feature = "convert_path")]
+  #[tokio::test]
+  pub async fn test_op_path_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_path_to_from_v8",
+      r"
+        const sep = op_path_to_from_v8('a').slice(1, 2);
+        assert(op_path_to_from_v8('dir') === `dir${sep}file.txt`);
+        assert(op_path_to_from_v8('é\u{1F600}') === `é\u{1F600}${sep}file.txt`);
+        try {
+          op_path_to_from_v8(1);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected string');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(all(unix, feature = "convert_path"))]
+  #[test]
+  fn test_path_to_from_v8_non_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let path = Path::new(std::ffi::OsStr::from_bytes(b"dir/\xffname"));
+    let err = path.to_v8(scope).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Path is not valid UTF-8: 'dir/\u{FFFD}name'"
+    );
+    let err = path.to_path_buf().to_v8(scope).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Path is not valid UTF-8: 'dir/\u{FFFD}name'"
+    );
+    // A lone surrogate has no UTF-8 encoding, so it is replaced
+    let value = v8::String::new_from_two_byte(
+      scope,
+      &[0xd800],
+      v8::NewStringType::Normal,
+    )
+    .unwrap();
+    let path = PathBuf::from_v8(scope, value.into()).unwrap();
+    assert_eq!(path, Path::new("\u{FFFD}"));
+  }
+
+  #[cfg(all(unix, feature = "convert_path"))]
+  #[test]
+  fn test_os_string_to_from_v8_non_utf8() {
+    use std::ffi::OsStr;
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStrExt;
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let value = OsStr::new("é\u{1F600}").to_v8(scope).unwrap();
+    assert_eq!(value.to_rust_string_lossy(scope), "é\u{1F600}");
+    assert_eq!(OsString::from_v8(scope, value).unwrap(), "é\u{1F600}");
+    let os_str = OsStr::from_bytes(b"a\xffb");
+    let err = os_str.to_v8(scope).unwrap_err();
+    assert_eq!(err.to_string(), "String is not valid UTF-8: 'a\u{FFFD}b'");
+    let err = os_str.to_os_string().to_v8(scope).unwrap_err();
+    assert_eq!(err.to_string(), "String is not valid UTF-8: 'a\u{FFFD}b'");
+  }
+
+  #[cfg(all(windows, feature = "convert_path"))]
+  #[test]
+  fn test_os_string_to_from_v8_invalid_utf16() {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::ffi::OsStringExt;
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    // An unpaired surrogate isn't valid UTF-16, but is a valid OS string and JS string
+    let units = [0x61, 0xd800, 0x62];
+    let os_string = OsString::from_wide(&units);
+    assert!(os_string.to_str().is_none());
+    let value = os_string.as_os_str().to_v8(scope).unwrap();
+    let string = v8::Local::<v8::String>::try_from(value).unwrap();
+    let mut buf = [0; 3];
+    string.write(scope, &mut buf, 0, v8::WriteOptions::NO_NULL_TERMINATION);
+    assert_eq!(buf, units);
+    let round_trip = OsString::from_v8(scope, value).unwrap();
+    assert_eq!(round_trip.encode_wide().collect::<Vec<_>>(), units);
+  }
+
+  #[test]
+  fn test_external_to_from_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let mut target = 1234u32;
+    let value = External(&mut target as *mut u32).to_v8(scope).unwrap();
+    assert!(value.is_external());
+    let External(ptr) = External::<u32>::from_v8(scope, value).unwrap();
+    assert_eq!(ptr, &mut target as *mut u32);
+    let value = v8::Object::new(scope).into();
+    let err = External::<u32>::from_v8(scope, value).err().unwrap();
+    assert_eq!(err.to_string(), "Expected external, got object");
+  }
+
+  #[derive(Serialize, Deserialize)]
+  #[serde(rename_all = "camelCase")]
+  pub struct SerdeInner {
+    label: String,
+    values: Vec<u32>,
+  }
+
+  #[derive(Serialize, Deserialize)]
+  pub struct SerdeOuter {
+    name: String,
+    inner: HashMap<String, SerdeInner>,
+    extra: Option<f64>,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_serde_to_from_v8(
+    #[from_v8] value: crate::convert::Serde<SerdeOuter>,
+  ) -> crate::convert::Serde<SerdeOuter> {
+    let crate::convert::Serde(mut value) = value;
+    for inner in value.inner.values_mut() {
+      inner.values.push(0);
+    }
+    crate::convert::Serde(value)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_serde_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_serde_to_from_v8",
+      r"
+        const value = op_serde_to_from_v8({
+          name: 'outer',
+          inner: { a: { label: 'x', values: [1, 2] }, b: { label: 'y', values: [] } },
+          extra: null,
+        });
+        assert(value.name === 'outer');
+        assert(Object.keys(value.inner).sort().join() === 'a,b');
+        assert(value.inner.a.label === 'x' && value.inner.a.values.join() === '1,2,0');
+        assert(value.inner.b.label === 'y' && value.inner.b.values.join() === '0');
+        assert(value.extra === null);
+        try {
+          op_serde_to_from_v8({ name: 'outer', inner: { a: { label: 1, values: [] } } });
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'expected: string');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_to_v8_array() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    // `filter` has no lower size bound, so that array grows as it is filled
+    for (iter, expected) in [
+      (
+        Box::new(0..5u32) as Box<dyn Iterator<Item = u32>>,
+        vec![0, 1, 2, 3, 4],
+      ),
+      (Box::new((0..10).filter(|i| i % 3 == 0)), vec![0, 3, 6, 9]),
+    ] {
+      let array = crate::convert::to_v8_array(scope, iter.map(Smi)).unwrap();
+      let elements = (0..array.length())
+        .map(|i| {
+          array
+            .get_index(scope, i)
+            .unwrap()
+            .uint32_value(scope)
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+      assert_eq!(elements, expected);
+    }
+    #[cfg(feature = "convert_time")]
+    {
+      let out_of_range =
+        SystemTime::UNIX_EPOCH + Duration::from_secs(10u64.pow(13));
+      let err = crate::convert::to_v8_array(
+        scope,
+        [Date(SystemTime::UNIX_EPOCH), Date(out_of_range)],
+      )
+      .unwrap_err();
+      assert_eq!(err.to_string(), "at [1]: Time is out of range for a Date");
+    }
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_boxed_slice_to_from_v8(
+    #[from_v8] value: Box<[Smi<u32>]>,
+  ) -> Box<[Smi<u32>]> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_rc_slice_to_from_v8(
+    #[from_v8] value: Rc<[Smi<u32>]>,
+  ) -> (Smi<usize>, Rc<[Smi<u32>]>) {
+    (Smi(value.len()), value)
+  }
+
+  #[op2]
+  pub fn op_arc_bytes_len(#[from_v8] value: Arc<[u8]>) -> u32 {
+    value.len() as _
+  }
+
+  #[tokio::test]
+  pub async fn test_op_slice_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_boxed_slice_to_from_v8, op_rc_slice_to_from_v8, op_arc_bytes_len",
+      r"
+        assert(op_boxed_slice_to_from_v8([1, 2, 3]).join() === '1,2,3');
+        assert(op_boxed_slice_to_from_v8([]).length === 0);
+        const [len, values] = op_rc_slice_to_from_v8([4, 5]);
+        assert(len === 2 && values.join() === '4,5');
+        assert(op_arc_bytes_len(new Uint8Array(1024)) === 1024);
+        assert(op_arc_bytes_len(new Uint32Array(4)) === 16);
+        assert(op_arc_bytes_len([1, 2, 3]) === 3);
+        try {
+          op_rc_slice_to_from_v8([1, 'a']);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [1]: Expected u32');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_arc_slice_from_v8_does_not_share_memory() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let value = runtime
+      .execute_script("", "globalThis.bytes = new Uint8Array([1, 2, 3])")
+      .unwrap();
+    let bytes = {
+      let scope = &mut runtime.handle_scope();
+      let value = v8::Local::new(scope, value);
+      Arc::<[u8]>::from_v8(scope, value).unwrap()
+    };
+    // Overwrite and then detach the JS buffer
+    runtime
+      .execute_script(
+        "",
+        "bytes.fill(0); bytes.buffer.transfer(); bytes = null",
+      )
+      .unwrap();
+    assert_eq!(&*bytes, [1, 2, 3]);
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_non_zero_u32_to_from_v8(
+    #[from_v8] value: NonZeroU32,
+  ) -> NonZeroU32 {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_non_zero_i32_to_from_v8(
+    #[from_v8] value: NonZeroI32,
+  ) -> NonZeroI32 {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_non_zero_u64_to_from_v8(
+    #[from_v8] value: NonZeroU64,
+  ) -> NonZeroU64 {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_non_zero_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_non_zero_u32_to_from_v8, op_non_zero_i32_to_from_v8, op_non_zero_u64_to_from_v8",
+      r"
+        assert(op_non_zero_u32_to_from_v8(1) === 1);
+        assert(op_non_zero_u32_to_from_v8(0xffffffff) === 0xffffffff);
+        assert(op_non_zero_i32_to_from_v8(-1) === -1);
+        assert(op_non_zero_u64_to_from_v8(2n ** 64n - 1n) === 2n ** 64n - 1n);
+        for (const [op, value, message] of [
+          [op_non_zero_u32_to_from_v8, 0, 'Expected a non-zero u32'],
+          [op_non_zero_u32_to_from_v8, -0, 'Expected a non-zero u32'],
+          [op_non_zero_u32_to_from_v8, -1, 'Expected u32 that is a safe integer'],
+          [op_non_zero_i32_to_from_v8, 0, 'Expected a non-zero i32'],
+          [op_non_zero_u64_to_from_v8, 0n, 'Expected a non-zero u64'],
+          [op_non_zero_u64_to_from_v8, -1n, 'BigInt value does not fit in u64 without loss'],
+          [op_non_zero_u64_to_from_v8, 1, 'Expected u64 BigInt'],
+        ]) {
+          try {
+            op(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object)]
+  pub struct StashedCallback {
+    callback: v8::Global<v8::Value>,
+  }
+
+  #[op2]
+  pub fn op_stash_callback(
+    state: &mut OpState,
+    #[from_v8] value: StashedCallback,
+  ) {
+    state.put(value);
+  }
+
+  #[op2]
+  pub fn op_call_stashed_callback(
+    scope: &mut v8::HandleScope,
+    state: &mut OpState,
+  ) -> u32 {
+    let StashedCallback { callback } = state.borrow();
+    let callback = v8::Local::new(scope, callback);
+    let callback = v8::Local::<v8::Function>::try_from(callback).unwrap();
+    let recv = v8::undefined(scope).into();
+    let result = callback.call(scope, recv, &[]).unwrap();
+    result.uint32_value(scope).unwrap()
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_take_stashed_callback(state: &mut OpState) -> StashedCallback {
+    state.take()
+  }
+
+  #[tokio::test]
+  pub async fn test_op_global_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_stash_callback, op_call_stashed_callback, op_take_stashed_callback",
+      r"
+        let calls = 0;
+        const callback = () => ++calls;
+        op_stash_callback({ callback });
+        assert(op_call_stashed_callback() === 1);
+        assert(op_call_stashed_callback() === 2);
+        assert(op_take_stashed_callback().callback === callback);",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  pub fn op_nested_from_v8(
+    #[from_v8] value: Vec<HashMap<String, Vec<Smi<u32>>>>,
+  ) -> u32 {
+    value.len() as u32
+  }
+
+  #[tokio::test]
+  pub async fn test_op_nested_from_v8_error_path(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_nested_from_v8",
+      r#"
+        assert(op_nested_from_v8([{}, { foo: [1] }]) === 2);
+        for (const [value, message] of [
+          [[{}, {}, { foo: [1, 'a'] }], 'at [2].foo[1]: Expected u32'],
+          [[{ 'a b': ['x'] }], 'at [0]["a b"][0]: Expected u32'],
+          [[{ 7: [] }, 1], 'at [1]: Expected object'],
+        ]) {
+          try {
+            op_nested_from_v8(value);
+            assert(false);
+          } catch (e) {
+            assert(e instanceof TypeError);
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_v8map_squares(len: u32) -> V8Map<Smi<u32>, Smi<u32>> {
+    V8Map((0..len).map(|i| (Smi(i), Smi(i * i))).collect())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_v8map_double_keys(
+    #[from_v8] map: V8Map<Smi<u32>, Smi<u8>>,
+  ) -> V8Map<Smi<u32>, Smi<u8>> {
+    V8Map(map.0.into_iter().map(|(k, v)| (Smi(k.0 * 2), v)).collect())
+  }
+
+  #[tokio::test]
+  pub async fn test_op_v8map_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_v8map_squares, op_v8map_double_keys",
+      r#"
+        const squares = op_v8map_squares(4);
+        assert(squares instanceof Map);
+        assert(squares.size === 4);
+        assert(squares.get(3) === 9);
+        assert(!squares.has('3'));
+        const doubled = op_v8map_double_keys(new Map([[1, 10], [2, 20]]));
+        assert(doubled instanceof Map);
+        assert(doubled.get(2) === 10 && doubled.get(4) === 20);
+        assert(!doubled.has('2'));
+        assert(op_v8map_double_keys(new Map()).size === 0);
+        for (const [value, message] of [
+          [{ 1: 10 }, 'Expected Map'],
+          [new Map([[1, 10], ['1', 20]]), 'at [1]: Expected u32'],
+          [new Map([[1, 10], [2, 'a']]), 'at [1]: Expected u8'],
+        ]) {
+          try {
+            op_v8map_double_keys(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[number]
+  pub fn op_for_each_sum<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    value: v8::Local<'s, v8::Value>,
+    limit: u32,
+  ) -> Result<u64, AnyError> {
+    let mut sum = 0;
+    for_each_from_v8(scope, value, |i, Smi(n): Smi<u32>| {
+      if i as u32 >= limit {
+        return Err(type_error(format!("Stopped at {i}")));
+      }
+      sum += n as u64;
+      Ok(())
+    })?;
+    Ok(sum)
+  }
+
+  #[tokio::test]
+  pub async fn test_for_each_from_v8_sums_large_array(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      1,
+      "op_for_each_sum",
+      r#"
+        const arr = Array.from({ length: 1_000_000 }, (_, i) => i);
+        assert(op_for_each_sum(arr, 1_000_000) === 499_999_500_000);
+        assert(op_for_each_sum([], 0) === 0);
+        for (const [value, limit, message] of [
+          [arr, 10, 'Stopped at 10'],
+          [[0, 1, 2, 'a', 4], 5, 'at [3]: Expected u32'],
+          [{ length: 1 }, 1, 'Expected array'],
+        ]) {
+          try {
+            op_for_each_sum(value, limit);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_slice_to_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let owned = vec![Smi(1u32), Smi(2), Smi(3)];
+    let array = owned.as_slice().to_v8(scope).unwrap();
+    let array = v8::Local::<v8::Array>::try_from(array).unwrap();
+    let elements = (0..array.length())
+      .map(|i| {
+        array
+          .get_index(scope, i)
+          .unwrap()
+          .uint32_value(scope)
+          .unwrap()
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(elements, [1, 2, 3]);
+    // The slice is still owned by the caller
+    assert_eq!(owned.len(), 3);
+
+    let bytes = [1u8, 2, 3];
+    let view = bytes.as_slice().to_v8(scope).unwrap();
+    let view = v8::Local::<v8::Uint8Array>::try_from(view).unwrap();
+    let mut copy = [0; 3];
+    view.copy_contents(&mut copy);
+    assert_eq!(copy, bytes);
+
+    let empty: &[Smi<u32>] = &[];
+    let array = empty.to_v8(scope).unwrap();
+    assert_eq!(v8::Local::<v8::Array>::try_from(array).unwrap().length(), 0);
+  }
+
+  #[derive(FromV8)]
+  #[v8(object)]
+  pub struct DerivePartialObject {
+    required: Smi<u32>,
+    optional: Option<Smi<u32>>,
+    nullable: Option<Option<Smi<u32>>>,
+  }
+
+  #[op2]
+  #[string]
+  pub fn op_derive_partial_object(
+    #[from_v8] value: DerivePartialObject,
+  ) -> String {
+    format!(
+      "{} {:?} {:?}",
+      value.required.0,
+      value.optional.map(|v| v.0),
+      value.nullable.map(|v| v.map(|v| v.0))
+    )
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_partial_object(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_partial_object",
+      r#"
+        for (const [value, expected] of [
+          [{ required: 1 }, '1 None None'],
+          [{ required: 1, optional: null, nullable: undefined }, '1 None None'],
+          [{ required: 1, nullable: null }, '1 None Some(None)'],
+          [{ required: 1, optional: 2, nullable: 3 }, '1 Some(2) Some(Some(3))'],
+        ]) {
+          assert(op_derive_partial_object(value) === expected);
+        }
+        for (const [value, message] of [
+          [{}, "Missing field 'required'"],
+          [{ required: 1, nullable: 'a' }, 'at nullable: Expected u32'],
+        ]) {
+          try {
+            op_derive_partial_object(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  pub fn op_js_callback_call(
+    scope: &mut v8::HandleScope,
+    #[from_v8] callback: crate::convert::JsCallback<(Smi<u32>,), Smi<u32>>,
+    value: u32,
+  ) -> Result<u32, AnyError> {
+    Ok(callback.call(scope, (Smi(value),))?.0)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_js_callback() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_js_callback_call",
+      r#"
+        assert(op_js_callback_call((x) => x * 2, 21) === 42);
+        let thisValue = null;
+        op_js_callback_call(function (x) { 'use strict'; thisValue = this; return x; }, 1);
+        assert(thisValue === undefined);
+        for (const [callback, message] of [
+          [() => { throw new Error('boom'); }, 'boom'],
+          [() => 'a', 'Expected u32'],
+          [1, 'Expected function'],
+          [{}, 'Expected function'],
+        ]) {
+          try {
+            op_js_callback_call(callback, 1);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object)]
+  pub struct SymbolKeyed {
+    name: Smi<u32>,
+    #[v8(symbol = "deno.internal")]
+    internal: Option<Smi<u32>>,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_symbol_keyed_round_trip(
+    #[from_v8] value: SymbolKeyed,
+  ) -> SymbolKeyed {
+    SymbolKeyed {
+      name: value.name,
+      internal: value.internal.map(|Smi(v)| Smi(v + 1)),
+    }
+  }
+
+  #[op2]
+  pub fn op_symbol_key_get<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    object: v8::Local<'s, v8::Object>,
+  ) -> Result<u32, AnyError> {
+    let Smi(value) = crate::convert::SymbolKey::Registered("deno.internal")
+      .get::<Smi<u32>>(scope, object)?;
+    Ok(value)
+  }
+
+  #[op2]
+  pub fn op_string_keys_len(
+    #[from_v8] value: HashMap<String, Smi<u32>>,
+  ) -> u32 {
+    value.len() as _
+  }
+
+  #[tokio::test]
+  pub async fn test_op_symbol_key() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_symbol_keyed_round_trip, op_symbol_key_get, op_string_keys_len",
+      r#"
+        const internal = Symbol.for('deno.internal');
+        const value = { name: 1, [internal]: 2 };
+        assert(op_symbol_key_get(value) === 2);
+        assert(op_string_keys_len(value) === 1);
+        const out = op_symbol_keyed_round_trip(value);
+        assert(out.name === 1 && out[internal] === 3);
+        assert(JSON.stringify(Object.keys(out)) === '["name"]');
+        assert(out.internal === undefined);
+        assert(op_symbol_keyed_round_trip({ name: 1, internal: 2 })[internal] === null);
+        try {
+          op_symbol_key_get({ [internal]: 'a' });
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [Symbol.for("deno.internal")]: Expected u32');
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_try_from_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let string: v8::Local<v8::Value> =
+      v8::String::new(scope, "a").unwrap().into();
+    let fraction: v8::Local<v8::Value> = v8::Number::new(scope, 1.5).into();
+    let one: v8::Local<v8::Value> = v8::Integer::new(scope, 1).into();
+    let big: v8::Local<v8::Value> = v8::BigInt::new_from_u64(scope, 1).into();
+    let null: v8::Local<v8::Value> = v8::null(scope).into();
+
+    assert_eq!(Smi::<u32>::try_from_v8(scope, one), Some(Smi(1)));
+    assert_eq!(Smi::<u32>::try_from_v8(scope, string), None);
+    assert_eq!(
+      CheckedSmi::<i32>::try_from_v8(scope, one),
+      Some(CheckedSmi(1))
+    );
+    assert_eq!(CheckedSmi::<i32>::try_from_v8(scope, fraction), None);
+    assert_eq!(
+      Number::<f64>::try_from_v8(scope, fraction),
+      Some(Number(1.5))
+    );
+    assert_eq!(Number::<f64>::try_from_v8(scope, string), None);
+    assert_eq!(
+      SafeNumber::<u64>::try_from_v8(scope, one),
+      Some(SafeNumber(1))
+    );
+    assert_eq!(SafeNumber::<u64>::try_from_v8(scope, fraction), None);
+    assert_eq!(BigInt::<u64>::try_from_v8(scope, big), Some(BigInt(1)));
+    assert_eq!(BigInt::<u64>::try_from_v8(scope, one), None);
+    assert_eq!(NonZeroU32::try_from_v8(scope, one), NonZeroU32::new(1));
+    assert_eq!(Option::<Smi<u32>>::try_from_v8(scope, null), Some(None));
+    assert_eq!(Option::<Smi<u32>>::try_from_v8(scope, string), None);
+    // Impls without an override fall back to `from_v8`
+    assert_eq!(
+      <[Smi<u32>; 1]>::try_from_v8(scope, one).map(|[Smi(v)]| v),
+      None
+    );
+    let array: v8::Local<v8::Value> =
+      v8::Array::new_with_elements(scope, &[one]).into();
+    assert_eq!(
+      <[Smi<u32>; 1]>::try_from_v8(scope, array).map(|[Smi(v)]| v),
+      Some(1)
+    );
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_wrapping_u8(#[from_v8] value: Wrapping<u8>) -> Wrapping<u8> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_saturating_u8(#[from_v8] value: Saturating<u8>) -> Saturating<u8> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_wrapping_i64(#[from_v8] value: Wrapping<i64>) -> Wrapping<i64> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_saturating_u64(
+    #[from_v8] value: Saturating<u64>,
+  ) -> Saturating<u64> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_wrapping_saturating(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_wrapping_u8, op_saturating_u8, op_wrapping_i64, op_saturating_u64",
+      r#"
+        for (const [value, wrapped, saturated] of [
+          [0, 0, 0],
+          [255, 255, 255],
+          [256, 0, 255],
+          [257, 1, 255],
+          [1000, 232, 255],
+          [-1, 255, 0],
+          [-257, 255, 0],
+          [1.9, 1, 1],
+          [-0.5, 0, 0],
+          [NaN, 0, 0],
+          [Infinity, 0, 255],
+          [-Infinity, 0, 0],
+          [2 ** 200 + 2 ** 148, 0, 255],
+        ]) {
+          assert(op_wrapping_u8(value) === wrapped);
+          assert(op_saturating_u8(value) === saturated);
+        }
+        assert(op_wrapping_i64(2n ** 63n) === -(2n ** 63n));
+        assert(op_wrapping_i64(2n ** 64n + 5n) === 5n);
+        assert(op_saturating_u64(2n ** 64n) === 2n ** 64n - 1n);
+        assert(op_saturating_u64(-1n) === 0n);
+        assert(op_saturating_u64(7n) === 7n);
+        for (const [op, value, message] of [
+          [op_wrapping_u8, 'a', 'Expected u8'],
+          [op_saturating_u8, 1n, 'Expected u8'],
+          [op_wrapping_i64, 1, 'Expected i64 BigInt'],
+        ]) {
+          try {
+            op(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_unit_to_from_v8(
+    #[from_v8] value: ((), Smi<u32>),
+  ) -> ((), Smi<u32>) {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_unit_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_unit_to_from_v8",
+      r#"
+        const out = op_unit_to_from_v8([undefined, 1]);
+        assert(out.length === 2 && out[0] === undefined && out[1] === 1);
+        assert(op_unit_to_from_v8([null, 2])[0] === undefined);
+        try {
+          op_unit_to_from_v8([0, 1]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [0]: Expected undefined or null');
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  type PartialArgs = (Smi<u32>, Option<Smi<u32>>, Option<bool>);
+
+  #[op2]
+  #[to_v8]
+  pub fn op_partial_tuple_from_v8(
+    #[from_v8] value: PartialTuple<PartialArgs>,
+  ) -> PartialArgs {
+    value.0
+  }
+
+  #[tokio::test]
+  pub async fn test_op_partial_tuple_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_partial_tuple_from_v8",
+      r#"
+        let out = op_partial_tuple_from_v8([1, 2, true, 'extra', {}]);
+        assert(out.length === 3);
+        assert(out[0] === 1 && out[1] === 2 && out[2] === true);
+        out = op_partial_tuple_from_v8([1]);
+        assert(out[0] === 1 && out[1] === null && out[2] === null);
+        out = op_partial_tuple_from_v8([1, , false]);
+        assert(out[0] === 1 && out[1] === null && out[2] === false);
+        try {
+          op_partial_tuple_from_v8([]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Missing tuple element 0');
+        }
+        try {
+          op_partial_tuple_from_v8([1, 'two']);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [1]:');
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_bytes_to_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    // A uniquely owned `Bytes` becomes the backing store
+    let unique = Bytes::from(vec![1u8, 2, 3]);
+    let ptr = unique.as_ptr();
+    let view = unique.to_v8(scope).unwrap();
+    let view = v8::Local::<v8::Uint8Array>::try_from(view).unwrap();
+    let buffer = view.buffer(scope).unwrap();
+    assert_eq!(buffer.data().unwrap().as_ptr() as *const u8, ptr);
+    let mut copy = [0; 3];
+    view.copy_contents(&mut copy);
+    assert_eq!(copy, [1, 2, 3]);
+
+    // A shared `Bytes` is copied, and the moved handle is released right away
+    let shared = Bytes::from(vec![4u8, 5, 6]);
+    let view = shared.clone().to_v8(scope).unwrap();
+    assert!(shared.is_unique());
+    let view = v8::Local::<v8::Uint8Array>::try_from(view).unwrap();
+    let buffer = view.buffer(scope).unwrap();
+    assert_ne!(
+      buffer.data().unwrap().as_ptr() as *const u8,
+      shared.as_ptr()
+    );
+    assert_eq!(Bytes::from_v8(scope, view.into()).unwrap(), shared);
+
+    // Static slices are never written through
+    let view = Bytes::from_static(b"abc").to_v8(scope).unwrap();
+    assert_eq!(Bytes::from_v8(scope, view).unwrap(), &b"abc"[..]);
+
+    let view = BytesMut::from(&b"xyz"[..]).to_v8(scope).unwrap();
+    assert_eq!(BytesMut::from_v8(scope, view).unwrap(), &b"xyz"[..]);
+  }
+
+  #[test]
+  fn test_bytes_to_v8_detach() {
+    let mut runtime = JsRuntime::new(Default::default());
+    {
+      let scope = &mut runtime.handle_scope();
+      let view = Bytes::from(vec![1u8, 2, 3]).to_v8(scope).unwrap();
+      let view = v8::Local::<v8::Uint8Array>::try_from(view).unwrap();
+      let buffer = view.buffer(scope).unwrap();
+      buffer.detach(None).unwrap();
+      assert_eq!(view.byte_length(), 0);
+      assert!(Bytes::from_v8(scope, view.into()).is_err());
+    }
+    // The `Bytes` is dropped exactly once, by the backing store deleter
+    runtime.v8_isolate().low_memory_notification();
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_lazy_to_v8(
+    x: u32,
+    y: u32,
+  ) -> Lazy<
+    impl for<'s> FnOnce(&mut v8::HandleScope<'s>) -> v8::Local<'s, v8::Value>,
+  > {
+    Lazy::new(move |scope| {
+      let object = v8::Object::new(scope);
+      for (key, value) in [("x", x), ("y", y)] {
+        let key = v8::String::new(scope, key).unwrap();
+        let value = v8::Integer::new_from_unsigned(scope, value);
+        object.set(scope, key.into(), value.into());
+      }
+      object.into()
+    })
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_try_lazy_to_v8(
+    fail: bool,
+  ) -> TryLazy<
+    impl for<'s> FnOnce(
+      &mut v8::HandleScope<'s>,
+    ) -> Result<v8::Local<'s, v8::Value>, StdAnyError>,
+  > {
+    TryLazy::new(move |scope| {
+      if fail {
+        return Err(type_error("Lazy failure").into());
+      }
+      Ok(v8::Array::new(scope, 0).into())
+    })
+  }
+
+  #[tokio::test]
+  pub async fn test_op_lazy_to_v8() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_lazy_to_v8, op_try_lazy_to_v8",
+      r"
+        const point = op_lazy_to_v8(1, 2);
+        assert(point.x === 1 && point.y === 2);
+        assert(Array.isArray(op_try_lazy_to_v8(false)));
+        try {
+          op_try_lazy_to_v8(true);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Lazy failure');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object)]
+  pub struct DerivePoint {
+    x: Smi<i32>,
+    y: Smi<i32>,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_container(
+    #[from_v8] points: Vec<DerivePoint>,
+  ) -> Option<DerivePoint> {
+    points.into_iter().max_by_key(|point| point.x.0)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_container(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_container",
+      r"
+        const point = op_derive_container([{ x: 1, y: 2 }, { x: 3, y: 4 }]);
+        assert(point.x === 3 && point.y === 4);
+        assert(op_derive_container([]) === null);
+        try {
+          op_derive_container([{ x: 1, y: 2 }, { x: 3 }]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [1].y:');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_range_to_from_v8(
+    #[from_v8] range: Range<Smi<u32>>,
+  ) -> (Smi<u32>, Range<Smi<u32>>) {
+    (Smi(range.end.0 - range.start.0), range)
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_range_inclusive_to_from_v8(
+    #[from_v8] range: RangeInclusive<Smi<u32>>,
+  ) -> RangeInclusive<Smi<u32>> {
+    range
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_range_object_to_from_v8(
+    #[from_v8] range: RangeObject<RangeInclusive<Smi<u32>>>,
+  ) -> RangeObject<Range<Smi<u32>>> {
+    let (start, end) = range.0.into_inner();
+    RangeObject(start..Smi(end.0 + 1))
+  }
+
+  #[tokio::test]
+  pub async fn test_op_range_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_range_to_from_v8, op_range_inclusive_to_from_v8, op_range_object_to_from_v8",
+      r"
+        const [len, range] = op_range_to_from_v8([0, 10]);
+        assert(len === 10 && range.join() === '0,10');
+        assert(op_range_inclusive_to_from_v8([3, 3]).join() === '3,3');
+        const object = op_range_object_to_from_v8({ start: 0, end: 9 });
+        assert(object.start === 0 && object.end === 10);
+        for (const [op, value, message] of [
+          [op_range_inclusive_to_from_v8, [10, 0], 'Expected range start to be at most its end'],
+          [op_range_object_to_from_v8, { start: 10, end: 0 }, 'Expected range start to be at most its end'],
+          [op_range_object_to_from_v8, { start: 0 }, 'Missing field'],
+          [op_range_to_from_v8, [0], 'Expected'],
+        ]) {
+          try {
+            op(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(discriminant)]
+  #[repr(i32)]
+  pub enum DeriveDiscriminant {
+    A = 1,
+    B = 5,
+    C,
+    D = -1,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_discriminant(
+    #[from_v8] value: DeriveDiscriminant,
+  ) -> DeriveDiscriminant {
+    match value {
+      DeriveDiscriminant::A => DeriveDiscriminant::B,
+      DeriveDiscriminant::B => DeriveDiscriminant::C,
+      DeriveDiscriminant::C => DeriveDiscriminant::D,
+      DeriveDiscriminant::D => DeriveDiscriminant::A,
+    }
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_discriminant(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_discriminant",
+      r"
+        assert(op_derive_discriminant(1) === 5);
+        assert(op_derive_discriminant(5) === 6);
+        assert(op_derive_discriminant(6) === -1);
+        assert(op_derive_discriminant(-1) === 1);
+        for (const [value, message] of [
+          [2, 'Unknown discriminant 2, expected one of: A = 1, B = 5, C = 6, D = -1'],
+          [0, 'Unknown discriminant 0'],
+          [1.5, 'Expected i32'],
+          ['A', 'Expected i32'],
+        ]) {
+          try {
+            op_derive_discriminant(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  pub struct TestCounter(Cell<u32>);
+
+  impl crate::Resource for TestCounter {}
+
+  #[op2]
+  #[to_v8]
+  pub fn op_counter_new(state: &mut OpState) -> NewResource<TestCounter> {
+    // The state is released before the return value is converted
+    let _ = state.resource_table.names();
+    NewResource(TestCounter(Cell::new(0)))
+  }
+
+  #[op2]
+  pub fn op_counter_increment(
+    state: &mut OpState,
+    #[from_v8] counter: ResourceRef<TestCounter>,
+  ) -> u32 {
+    // The conversion has released its borrow of the state by now
+    let _ = state.resource_table.names();
+    let ResourceRef(counter) = counter;
+    let count = counter.0.get() + 1;
+    counter.0.set(count);
+    count
+  }
+
+  #[tokio::test]
+  pub async fn test_op_resource_ref() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      1,
+      "op_counter_new, op_counter_increment",
+      r"
+        const rid = op_counter_new();
+        assert(typeof rid === 'number');
+        assert(op_counter_increment(rid) === 1);
+        assert(op_counter_increment(rid) === 2);
+        const other = op_counter_new();
+        assert(other !== rid);
+        assert(op_counter_increment(other) === 1);
+        assert(op_counter_increment(rid) === 3);
+        for (const [value, message] of [
+          [rid + 1000, 'Bad resource ID'],
+          [-1, 'Expected u32'],
+        ]) {
+          try {
+            op_counter_increment(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_new_resource_while_borrowed() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let state = runtime.op_state();
+    let scope = &mut runtime.handle_scope();
+
+    let borrowed = state.borrow_mut();
+    let err = NewResource(TestCounter(Cell::new(0)))
+      .to_v8(scope)
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Failed to add a resource: the OpState is already borrowed"
+    );
+    drop(borrowed);
+
+    let rid = NewResource(TestCounter(Cell::new(0))).to_v8(scope).unwrap();
+    let rid = Smi::<u32>::from_v8(scope, rid).unwrap().0;
+    assert!(state.borrow().resource_table.has(rid));
+  }
+
+  #[test]
+  fn test_resource_ref_while_borrowed() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let state = runtime.op_state();
+    let scope = &mut runtime.handle_scope();
+
+    let rid = NewResource(TestCounter(Cell::new(0))).to_v8(scope).unwrap();
+    let borrowed = state.borrow_mut();
+    let err = ResourceRef::<TestCounter>::from_v8(scope, rid).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Failed to look up a resource: the OpState is already borrowed"
+    );
+    drop(borrowed);
+
+    // Shared borrows don't conflict with the lookup
+    let _shared = state.borrow();
+    let counter = ResourceRef::<TestCounter>::from_v8(scope, rid).unwrap();
+    assert_eq!(counter.0 .0.get(), 0);
+  }
+
+  #[test]
+  fn test_local_to_from_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let number: v8::Local<v8::Value> = v8::Number::new(scope, 1.5).into();
+    let object: v8::Local<v8::Value> = v8::Object::new(scope).into();
+
+    let value = v8::Local::<v8::Value>::from_v8(scope, number).unwrap();
+    assert!(value.strict_equals(number));
+    assert!(value.to_v8(scope).unwrap().strict_equals(number));
+
+    let typed = v8::Local::<v8::Object>::from_v8(scope, object).unwrap();
+    assert!(typed.to_v8(scope).unwrap().strict_equals(object));
+    let err = v8::Local::<v8::Object>::from_v8(scope, number).unwrap_err();
+    assert_eq!(err.to_string(), "Expected object, got number");
+    assert!(v8::Local::<v8::Object>::try_from_v8(scope, number).is_none());
+    let err = v8::Local::<v8::String>::from_v8(scope, object).unwrap_err();
+    assert_eq!(err.to_string(), "Expected string, got object");
+
+    // Locals compose with the other conversions as a base case
+    let array: v8::Local<v8::Value> =
+      v8::Array::new_with_elements(scope, &[object, number]).into();
+    let (_, n) =
+      <(v8::Local<v8::Object>, v8::Local<v8::Number>)>::from_v8(scope, array)
+        .unwrap();
+    assert_eq!(n.value(), 1.5);
+    let err = <Vec<v8::Local<v8::Object>>>::from_v8(scope, array).unwrap_err();
+    assert_eq!(err.to_string(), "at [1]: Expected object, got number");
+  }
+
+  #[test]
+  fn test_collect_from_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let one: v8::Local<v8::Value> = v8::Integer::new(scope, 1).into();
+    let two: v8::Local<v8::Value> = v8::Integer::new(scope, 2).into();
+    let string: v8::Local<v8::Value> =
+      v8::String::new(scope, "a").unwrap().into();
+
+    let out: Vec<Smi<u32>> = collect_from_v8(scope, [one, two]).unwrap();
+    assert_eq!(out, [Smi(1), Smi(2)]);
+    let err = collect_from_v8::<Smi<u32>, _>(scope, [one, string]).unwrap_err();
+    assert_eq!(err.to_string(), "at [1]: Expected u32, got string");
+  }
+
+  #[cfg(feature = "uuid")]
+  #[test]
+  fn test_uuid_to_from_v8() {
+    use crate::convert::UuidBytes;
+    use uuid::Uuid;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    let nil = Uuid::nil().to_v8(scope).unwrap();
+    assert_eq!(
+      nil.to_rust_string_lossy(scope),
+      "00000000-0000-0000-0000-000000000000"
+    );
+    assert_eq!(Uuid::from_v8(scope, nil).unwrap(), Uuid::nil());
+    let bytes = UuidBytes(Uuid::nil()).to_v8(scope).unwrap();
+    assert!(bytes.is_uint8_array());
+    assert_eq!(Vec::<u8>::from_v8(scope, bytes).unwrap(), [0; 16]);
+
+    let v4 = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let uuid = Uuid::parse_str(v4).unwrap();
+    let value = uuid.to_v8(scope).unwrap();
+    assert_eq!(value.to_rust_string_lossy(scope), v4);
+    assert_eq!(Uuid::from_v8(scope, value).unwrap(), uuid);
+    let bytes = UuidBytes(uuid).to_v8(scope).unwrap();
+    assert_eq!(UuidBytes::from_v8(scope, bytes).unwrap(), UuidBytes(uuid));
+    let upper = v8::String::new(scope, &v4.to_uppercase()).unwrap().into();
+    assert_eq!(Uuid::from_v8(scope, upper).unwrap(), uuid);
+
+    let malformed: v8::Local<v8::Value> =
+      v8::String::new(scope, "67e55044-10b1-426f-9247-bb680e5fe0cZ")
+        .unwrap()
+        .into();
+    let err = Uuid::from_v8(scope, malformed).unwrap_err();
+    assert!(err
+      .to_string()
+      .starts_with("Invalid UUID '67e55044-10b1-426f-9247-bb680e5fe0cZ': "));
+    let number = v8::Number::new(scope, 1.0).into();
+    assert!(Uuid::from_v8(scope, number).is_err());
+    let short = v8::ArrayBuffer::new(scope, 15);
+    let short = v8::Uint8Array::new(scope, short, 0, 15).unwrap().into();
+    let err = UuidBytes::from_v8(scope, short).unwrap_err();
+    assert_eq!(err.to_string(), "Expected 16 bytes for a UUID, got 15");
+  }
+
+  #[derive(FromV8)]
+  #[v8(object)]
+  pub struct DeriveOptionalTags {
+    #[allow(dead_code)]
+    tags: Option<Vec<Smi<u32>>>,
+  }
+
+  #[test]
+  fn test_convert_error() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let assert_error = |err: StdAnyError, message: &str, kind: ConvertError| {
+      let class = match kind {
+        ConvertError::OutOfRange { .. } => "RangeError",
+        _ => "TypeError",
+      };
+      assert_eq!(err.to_string(), message);
+      assert_eq!(ConvertError::find(&err.0), Some(&kind));
+      assert_eq!(crate::error::get_custom_error_class(&err.0), Some(class));
+    };
+
+    let number: v8::Local<v8::Value> = v8::Number::new(scope, 256.0).into();
+    let err = Cow::<str>::from_v8(scope, number).unwrap_err();
+    assert_error(
+      err,
+      "Expected string, got number",
+      ConvertError::ExpectedType {
+        expected: "string".into(),
+        got: Some("number".into()),
+      },
+    );
+    let err = CheckedSmi::<u8>::from_v8(scope, number).unwrap_err();
+    assert_error(
+      err,
+      "Value out of range for u8",
+      ConvertError::out_of_range("Value out of range for u8"),
+    );
+
+    let array: v8::Local<v8::Value> =
+      v8::Array::new_with_elements(scope, &[number, number, number]).into();
+    let err = <(Smi<u32>, Smi<u32>)>::from_v8(scope, array).unwrap_err();
+    assert_error(
+      err,
+      "Expected array of length 2",
+      ConvertError::LengthMismatch {
+        expected: 2,
+        actual: 3,
+      },
+    );
+    let err = <[Smi<u32>; 4]>::from_v8(scope, array).unwrap_err();
+    assert_error(
+      err,
+      "Expected array of length 4",
+      ConvertError::LengthMismatch {
+        expected: 4,
+        actual: 3,
+      },
+    );
+    let bytes = v8::ArrayBuffer::new(scope, 4);
+    let bytes = v8::Uint8Array::new(scope, bytes, 0, 4).unwrap().into();
+    let err = Vec::<Cow<str>>::from_v8(scope, bytes).unwrap_err();
+    assert_error(
+      err,
+      "Expected array, got Uint8Array",
+      ConvertError::ExpectedType {
+        expected: "array".into(),
+        got: Some("Uint8Array".into()),
+      },
+    );
+
+    // Nested errors keep their kind behind the path
+    let err = Vec::<Cow<str>>::from_v8(scope, array).unwrap_err();
+    assert_error(
+      err,
+      "at [0]: Expected string, got number",
+      ConvertError::ExpectedType {
+        expected: "string".into(),
+        got: Some("number".into()),
+      },
+    );
+    // Including through an `Option`
+    let object = v8::Object::new(scope);
+    let tags = v8::String::new(scope, "tags").unwrap();
+    let one = v8::Integer::new(scope, 1).into();
+    let x = v8::String::new(scope, "x").unwrap().into();
+    let array = v8::Array::new_with_elements(scope, &[one, x]);
+    object.set(scope, tags.into(), array.into()).unwrap();
+    let err = DeriveOptionalTags::from_v8(scope, object.into())
+      .map(|_| ())
+      .unwrap_err();
+    assert_error(
+      err,
+      "at tags[1]: Expected u32, got string",
+      ConvertError::ExpectedType {
+        expected: "u32".into(),
+        got: Some("string".into()),
+      },
+    );
+    let object = v8::Object::new(scope);
+    let err =
+      crate::convert::field_property(scope, object, "x", false).unwrap_err();
+    assert_error(
+      err,
+      "Missing field 'x'",
+      ConvertError::MissingField { name: "x".into() },
+    );
+    let inverted = (Smi(2u32), Smi(1u32)).to_v8(scope).unwrap();
+    let err = RangeInclusive::<Smi<u32>>::from_v8(scope, inverted).unwrap_err();
+    assert_error(
+      err,
+      "Expected range start to be at most its end",
+      ConvertError::out_of_range("Expected range start to be at most its end"),
+    );
+
+    // A read that throws is replaced by an error of its own
+    let source = "Object.defineProperty([1], 0, { get() { throw 1; } })";
+    let source = v8::String::new(scope, source).unwrap();
+    let script = v8::Script::compile(scope, source, None).unwrap();
+    let array = script.run(scope).unwrap();
+    let scope = &mut v8::TryCatch::new(scope);
+    let err = Vec::<Smi<u32>>::from_v8(scope, array).unwrap_err();
+    assert_error(
+      err,
+      "Array element 0 could not be read",
+      ConvertError::unreadable("Array element 0 could not be read"),
+    );
+
+    // Errors from elsewhere have no kind
+    let err = StdAnyError(type_error("Expected string"));
+    assert_eq!(ConvertError::find(&err.0), None);
+  }
+
+  #[test]
+  fn test_from_v8_into() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let values = (0..8)
+      .map(|i| v8::Integer::new(scope, i).into())
+      .collect::<Vec<v8::Local<v8::Value>>>();
+    let long = v8::Array::new_with_elements(scope, &values).into();
+    let short = v8::Array::new_with_elements(scope, &values[..3]).into();
+
+    let mut out = Vec::<Smi<u32>>::new();
+    from_v8_into(scope, long, &mut out).unwrap();
+    assert_eq!(out, (0..8).map(Smi).collect::<Vec<_>>());
+    let (ptr, capacity) = (out.as_ptr(), out.capacity());
+
+    // Shorter arrays reuse the allocation
+    from_v8_into(scope, short, &mut out).unwrap();
+    assert_eq!(out, [Smi(0), Smi(1), Smi(2)]);
+    assert_eq!((out.as_ptr(), out.capacity()), (ptr, capacity));
+
+    // A failure partway through leaves the vector empty
+    let string = v8::String::new(scope, "a").unwrap().into();
+    let mixed =
+      v8::Array::new_with_elements(scope, &[values[0], values[1], string])
+        .into();
+    let err = from_v8_into(scope, mixed, &mut out).unwrap_err();
+    assert_eq!(err.to_string(), "at [2]: Expected u32, got string");
+    assert!(out.is_empty());
+    assert_eq!(out.capacity(), capacity);
+
+    out.push(Smi(1));
+    let err = from_v8_into(scope, string, &mut out).unwrap_err();
+    assert_eq!(err.to_string(), "Expected array, got string");
+    assert!(out.is_empty());
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_ordering_to_from_v8(
+    #[from_v8] value: std::cmp::Ordering,
+  ) -> std::cmp::Ordering {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_ordering_compare(a: u32, b: u32) -> std::cmp::Ordering {
+    a.cmp(&b)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_ordering_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_ordering_to_from_v8, op_ordering_compare",
+      r"
+        assert(op_ordering_compare(1, 2) === -1);
+        assert(op_ordering_compare(2, 2) === 0);
+        assert(op_ordering_compare(3, 2) === 1);
+        assert([3, 1, 2].sort(op_ordering_compare).join() === '1,2,3');
+        for (const [value, expected] of [
+          [-1, -1], [0, 0], [1, 1],
+          [-0.5, -1], [0.25, 1], [-1e300, -1], [Infinity, 1],
+          [-0, 0], [NaN, 0],
+        ]) {
+          assert(op_ordering_to_from_v8(value) === expected);
+        }
+        try {
+          op_ordering_to_from_v8('1');
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected number');
+        }",
+    )?;
+    Ok(())
+  }
+
+  static ATOMIC_COUNTER: std::sync::atomic::AtomicU32 =
+    std::sync::atomic::AtomicU32::new(0);
+
+  #[op2(fast)]
+  pub fn op_atomic_counter_increment() {
+    ATOMIC_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+  }
+
+  #[op2]
+  pub fn op_atomic_counter_load<'s>(
+    scope: &mut v8::HandleScope<'s>,
+  ) -> Result<v8::Local<'s, v8::Value>, AnyError> {
+    Ok((&ATOMIC_COUNTER).to_v8(scope)?)
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_atomic_to_from_v8(
+    #[from_v8] value: (
+      std::sync::atomic::AtomicUsize,
+      std::sync::atomic::AtomicI64,
+    ),
+  ) -> (std::sync::atomic::AtomicUsize, std::sync::atomic::AtomicI64) {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_atomic_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_atomic_counter_increment, op_atomic_counter_load, op_atomic_to_from_v8",
+      r"
+        const start = op_atomic_counter_load();
+        for (let i = 0; i < 10; i++) {
+          op_atomic_counter_increment();
+        }
+        assert(op_atomic_counter_load() === start + 10);
+        const [size, big] = op_atomic_to_from_v8([2 ** 40, -(2n ** 60n)]);
+        assert(size === 2 ** 40);
+        assert(big === -(2n ** 60n));
+        try {
+          op_atomic_to_from_v8([-1, 0n]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [0]: Expected usize');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_latin1_to_from_v8(#[from_v8] value: Latin1String) -> Latin1String {
+    value
+  }
+
+  #[op2]
+  #[buffer]
+  pub fn op_latin1_bytes(#[from_v8] value: Latin1String) -> Vec<u8> {
+    value.0
+  }
+
+  #[tokio::test]
+  pub async fn test_op_latin1_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_latin1_to_from_v8, op_latin1_bytes",
+      r"
+        for (const s of ['', 'content-type', 'éÿ\u0000']) {
+          assert(op_latin1_to_from_v8(s) === s);
+        }
+        const bytes = op_latin1_bytes('aé');
+        assert(bytes.length === 2 && bytes[0] === 0x61 && bytes[1] === 0xe9);
+        // Only latin1 characters are left, whatever the representation
+        const narrowed = 'Āé'.slice(1);
+        assert(op_latin1_to_from_v8(narrowed) === 'é');
+        for (const s of ['Ā', 'a\u{1F600}']) {
+          try {
+            op_latin1_to_from_v8(s);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, 'Expected a string containing only latin1 characters');
+          }
+        }
+        try {
+          op_latin1_to_from_v8(1);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected string');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  pub fn op_nested_vec_from_v8(#[from_v8] matrix: Vec<Vec<u32>>) -> u32 {
+    matrix.iter().flatten().sum()
+  }
+
+  #[tokio::test]
+  pub async fn test_op_nested_vec_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_nested_vec_from_v8",
+      r"
+        assert(op_nested_vec_from_v8([]) === 0);
+        assert(op_nested_vec_from_v8([[1, 2], [], [3]]) === 6);
+        // Rows can be typed arrays, which are copied in bulk, mixed with plain arrays
+        assert(op_nested_vec_from_v8([new Uint32Array([1, 2]), new Uint8Array([3]), [4]]) === 10);
+        for (const [value, message] of [
+          [[[1, 2], [3, 4, 'x']], 'at [1][2]: Expected u32'],
+          [[[1], new Float64Array(1)], 'at [1]: Expected Uint32Array, got Float64Array'],
+          [[[1], 2], 'at [1]: Expected Uint32Array or array, got number'],
+          [[[1], [-1]], 'at [1][0]: Expected u32'],
+          [new Uint32Array(1), 'Expected array, got Uint32Array'],
+        ]) {
+          try {
+            op_nested_vec_from_v8(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_cow_bytes_to_from_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let read = |scope: &mut v8::HandleScope, value: v8::Local<v8::Value>| {
+      let view = v8::Local::<v8::Uint8Array>::try_from(value).unwrap();
+      let mut out = vec![0; view.byte_length()];
+      view.copy_contents(&mut out);
+      (out, view.buffer(scope).unwrap().data())
+    };
+
+    // Borrowed bytes are copied
+    let borrowed = [1u8, 2, 3];
+    let value = Cow::Borrowed(&borrowed[..]).to_v8(scope).unwrap();
+    let (contents, data) = read(scope, value);
+    assert_eq!(contents, [1, 2, 3]);
+    assert_ne!(data.unwrap().as_ptr() as *const u8, borrowed.as_ptr());
+
+    // Owned bytes are handed over
+    let owned = vec![4u8, 5, 6];
+    let ptr = owned.as_ptr();
+    let value = Cow::<[u8]>::Owned(owned).to_v8(scope).unwrap();
+    let (contents, data) = read(scope, value);
+    assert_eq!(contents, [4, 5, 6]);
+    assert_eq!(data.unwrap().as_ptr() as *const u8, ptr);
+
+    let bytes = Cow::<[u8]>::from_v8(scope, value).unwrap();
+    assert!(matches!(bytes, Cow::Owned(ref b) if b == &[4, 5, 6]));
+    let empty = Cow::Borrowed(&[][..]).to_v8(scope).unwrap();
+    let bytes = Cow::<[u8]>::from_v8(scope, empty).unwrap();
+    assert!(matches!(bytes, Cow::Borrowed([])));
+    let array = v8::Array::new(scope, 0).into();
+    let bytes = Cow::<[u8]>::from_v8(scope, array).unwrap();
+    assert!(matches!(bytes, Cow::Borrowed([])));
+    let number = v8::Number::new(scope, 1.0).into();
+    let err = Cow::<[u8]>::from_v8(scope, number).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Expected ArrayBuffer, ArrayBufferView, or array, got number"
+    );
+  }
+
+  #[test]
+  fn test_string_to_from_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    for s in ["", "abc", "é中\u{1F600}"] {
+      let value = s.to_v8(scope).unwrap();
+      assert_eq!(value.to_rust_string_lossy(scope), s);
+      let value = s.to_owned().to_v8(scope).unwrap();
+      assert_eq!(String::from_v8(scope, value).unwrap(), s);
+    }
+
+    // Generic code can use strings like any other value
+    let value = vec!["a".to_owned(), "b".to_owned()].to_v8(scope).unwrap();
+    assert_eq!(Vec::<String>::from_v8(scope, value).unwrap(), ["a", "b"]);
+
+    let number = v8::Number::new(scope, 1.0).into();
+    let err = String::from_v8(scope, number).unwrap_err();
+    assert_eq!(err.to_string(), "Expected string, got number");
+
+    // The zeroed allocation is never touched, since V8 checks the length first
+    let long =
+      String::from_utf8(vec![0; v8::String::max_length() + 1]).unwrap();
+    let err = long.as_str().to_v8(scope).unwrap_err();
+    assert_eq!(err.to_string(), "String is too long to convert");
+    assert_eq!(
+      crate::error::get_custom_error_class(&err.0),
+      Some("RangeError")
+    );
+  }
+
+  #[op2(fast)]
+  pub fn op_throw_error_object(
+    #[string] name: String,
+  ) -> Result<(), Throw<ErrorObject>> {
+    Err(Throw(
+      ErrorObject::new(name, "boom")
+        .with_cause(ErrorObject::new("TypeError", "inner")),
+    ))
+  }
+
+  #[op2(fast)]
+  pub fn op_throw_any_error() -> Result<(), AnyError> {
+    let error = ErrorObject::new("MyError", "boom");
+    Err(Throw(error))?
+  }
+
+  #[tokio::test]
+  pub async fn test_op_throw_error_object(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_throw_error_object, op_throw_any_error",
+      r"
+        try {
+          op_throw_error_object('MyError');
+          assert(false);
+        } catch (e) {
+          assert(e instanceof Error);
+          assert(e.name === 'MyError');
+          assert(e.message === 'boom');
+          assert(String(e) === 'MyError: boom');
+          assert(typeof e.stack === 'string');
+          assert(e.cause instanceof TypeError);
+          assert(e.cause.message === 'inner');
+        }
+        try {
+          op_throw_error_object('RangeError');
+          assert(false);
+        } catch (e) {
+          assert(e instanceof RangeError);
+          assert(!Object.hasOwn(e, 'name'));
+        }
+        try {
+          op_throw_any_error();
+          assert(false);
+        } catch (e) {
+          assert(e instanceof Error);
+          assert(e.name === 'MyError');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "half")]
+  #[test]
+  fn test_f16_to_from_v8() {
+    use half::f16;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    let from_f64 = |scope: &mut v8::HandleScope, n: f64| {
+      let value = v8::Number::new(scope, n).into();
+      Number::<f16>::from_v8(scope, value).unwrap().0
+    };
+
+    // Every f16 widens exactly
+    for v in [f16::MIN_POSITIVE_SUBNORMAL, f16::MIN_POSITIVE, f16::MAX] {
+      let value = Number(v).to_v8(scope).unwrap();
+      assert_eq!(value.number_value(scope), Some(v.to_f64()));
+      assert_eq!(from_f64(scope, v.to_f64()), v);
+    }
+    assert_eq!(f16::MIN_POSITIVE_SUBNORMAL.to_f64(), 2f64.powi(-24));
+
+    // Subnormals round to the nearest step, with ties to even
+    let step = 2f64.powi(-24);
+    assert_eq!(from_f64(scope, step * 1.4), f16::MIN_POSITIVE_SUBNORMAL);
+    assert_eq!(from_f64(scope, step * 2.5).to_bits(), 2);
+    assert_eq!(from_f64(scope, step * 3.5).to_bits(), 4);
+    assert_eq!(from_f64(scope, step * 0.5), f16::ZERO);
+    let negative_zero = from_f64(scope, -step * 0.4);
+    assert_eq!(negative_zero, f16::NEG_ZERO);
+    assert!(negative_zero.is_sign_negative());
+
+    // Values outside of the f16 range narrow to infinity
+    assert_eq!(from_f64(scope, 65519.0), f16::MAX);
+    assert_eq!(from_f64(scope, 65520.0), f16::INFINITY);
+    assert_eq!(from_f64(scope, -1e10), f16::NEG_INFINITY);
+    assert!(from_f64(scope, f64::NAN).is_nan());
+
+    let string = v8::String::new(scope, "1").unwrap().into();
+    let err = Number::<f16>::from_v8(scope, string).unwrap_err();
+    assert_eq!(err.to_string(), "Expected f16, got string");
+
+    // Vectors go through a `Uint16Array` of the bits
+    let values = vec![f16::ONE, f16::NEG_INFINITY, f16::MIN_POSITIVE_SUBNORMAL];
+    let array = values.clone().to_v8(scope).unwrap();
+    assert!(array.is_uint16_array());
+    let bits = v8::Local::<v8::Uint16Array>::try_from(array).unwrap();
+    assert_eq!(bits.length(), 3);
+    assert_eq!(Vec::<f16>::from_v8(scope, array).unwrap(), values);
+    assert!(Vec::<f16>::new().to_v8(scope).unwrap().is_uint16_array());
+
+    let one = v8::Number::new(scope, 1.0).into();
+    let big = v8::Number::new(scope, 1e6).into();
+    let plain = v8::Array::new_with_elements(scope, &[one, big]).into();
+    assert_eq!(
+      Vec::<f16>::from_v8(scope, plain).unwrap(),
+      [f16::ONE, f16::INFINITY]
+    );
+    let buffer = v8::ArrayBuffer::new(scope, 8);
+    let floats = v8::Float32Array::new(scope, buffer, 0, 2).unwrap().into();
+    let err = Vec::<f16>::from_v8(scope, floats).unwrap_err();
+    assert_eq!(err.to_string(), "Expected Uint16Array, got Float32Array");
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_lenient_number(#[from_v8] value: Number<u32>) -> Number<u32> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_strict_number(
+    #[from_v8] value: StrictNumber<u32>,
+  ) -> StrictNumber<u32> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_strict_number() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_lenient_number, op_strict_number",
+      r"
+        for (const op of [op_lenient_number, op_strict_number]) {
+          assert(op(42) === 42);
+          assert(op(1.5) === 1);
+          assert(op(-1) === 2 ** 32 - 1);
+          assert(op(-1.5) === 0);
+          assert(op(2 ** 40) === 2 ** 32 - 1);
+          // Neither one coerces other types
+          for (const value of ['42', true, null, undefined, {}]) {
+            try {
+              op(value);
+              assert(false);
+            } catch (e) {
+              assertErrorContains(e, 'Expected u32');
+            }
+          }
+        }
+        assert(op_lenient_number(42n) === 42);
+        try {
+          op_strict_number(42n);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected u32');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_object_builder() {
+    static KEYS: ObjectKeys = ObjectKeys::new(&["x", "__proto__"]);
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    // The second object reuses the keys cached by the first
+    for _ in 0..2 {
+      let mut object = ObjectBuilder::new(scope, &KEYS).unwrap();
+      object.field(scope, "x", Smi(1)).unwrap();
+      object.field(scope, "__proto__", Smi(2)).unwrap();
+      object.field(scope, "uncached", Smi(3)).unwrap();
+      let object = object.build();
+      let names = object
+        .get_own_property_names(scope, Default::default())
+        .unwrap();
+      let names = Vec::<String>::from_v8(scope, names.into()).unwrap();
+      assert_eq!(names, ["x", "__proto__", "uncached"]);
+      let key = v8::String::new(scope, "__proto__").unwrap();
+      let proto = object.get(scope, key.into()).unwrap();
+      assert_eq!(Smi::<u32>::from_v8(scope, proto).unwrap(), Smi(2));
+    }
+
+    // Keys can be picked by index, as derived impls do
+    let mut object = ObjectBuilder::new(scope, &KEYS).unwrap();
+    let value = v8::Integer::new(scope, 4).into();
+    let object = object.property_at(scope, 1, value).object();
+    let key = v8::String::new(scope, "__proto__").unwrap();
+    assert!(object.has_own_property(scope, key.into()).unwrap());
+    let proto = object.get(scope, key.into()).unwrap();
+    assert_eq!(Smi::<u32>::from_v8(scope, proto).unwrap(), Smi(4));
+
+    let mut object = ObjectBuilder::new(scope, &KEYS).unwrap();
+    let long =
+      String::from_utf8(vec![0; v8::String::max_length() + 1]).unwrap();
+    let err = object
+      .field(scope, "x", long.as_str())
+      .map(|_| ())
+      .unwrap_err();
+    assert_eq!(err.to_string(), "at x: String is too long to convert");
+  }
+
+  #[op2]
+  #[allow(clippy::boxed_local)]
+  pub fn op_boxed_slice_len(#[from_v8] value: Box<[Smi<u32>]>) -> u32 {
+    value.len() as _
+  }
+
+  #[op2]
+  #[allow(clippy::boxed_local)]
+  pub fn op_boxed_slice_drop_counter(#[from_v8] _value: Box<[DropCounter]>) {}
+
+  #[tokio::test]
+  pub async fn test_op_boxed_slice_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_boxed_slice_len",
+      r"
+        assert(op_boxed_slice_len([]) === 0);
+        assert(op_boxed_slice_len([1, 2, 3]) === 3);
+        assert(op_boxed_slice_len(new Uint32Array(1000)) === 1000);",
+    )?;
+    DROP_COUNT.with(|c| c.set(0));
+    run_test2(
+      1,
+      "op_boxed_slice_drop_counter",
+      r"
+        try {
+          op_boxed_slice_drop_counter([1, 2, 3, -1]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [3]: Negative value');
+        }",
+    )?;
+    assert_eq!(DROP_COUNT.with(|c| c.get()), 3);
+    Ok(())
+  }
+
+  #[test]
+  fn test_vec_from_v8_exact_capacity() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    // So that `Box<[T]>` doesn't reallocate in `into_boxed_slice`
+    let elements = [1, 2, 3].map(|v| v8::Integer::new(scope, v).into());
+    let array = v8::Array::new_with_elements(scope, &elements).into();
+    let vec = Vec::<Smi<u32>>::from_v8(scope, array).unwrap();
+    assert_eq!(vec.capacity(), 3);
+    let buffer = v8::ArrayBuffer::new(scope, 12);
+    let typed = v8::Uint32Array::new(scope, buffer, 0, 3).unwrap().into();
+    let vec = Vec::<u32>::from_v8(scope, typed).unwrap();
+    assert_eq!(vec.capacity(), 3);
+  }
+
+  #[derive(ToV8)]
+  #[v8(object)]
+  pub struct Config {
+    port: Smi<u16>,
+    hosts: Vec<String>,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_frozen_config() -> Frozen<Config> {
+    Frozen(Config {
+      port: Smi(8080),
+      hosts: vec!["localhost".to_owned()],
+    })
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_sealed_config() -> Sealed<Config> {
+    Sealed(Config {
+      port: Smi(8080),
+      hosts: vec![],
+    })
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_frozen_number() -> Frozen<Smi<u32>> {
+    Frozen(Smi(1))
+  }
+
+  #[tokio::test]
+  pub async fn test_op_frozen_sealed() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_frozen_config, op_sealed_config, op_frozen_number",
+      r"
+        const config = op_frozen_config();
+        assert(Object.isFrozen(config));
+        // Sloppy mode writes are silently ignored
+        config.port = 1;
+        config.extra = true;
+        delete config.hosts;
+        assert(config.port === 8080 && config.extra === undefined);
+        assert(config.hosts.length === 1);
+        // Strict mode writes throw
+        const strictWrite = (object) => {
+          'use strict';
+          try {
+            object.port = 1;
+            return false;
+          } catch (e) {
+            return e instanceof TypeError;
+          }
+        };
+        assert(strictWrite(config));
+        // Freezing is shallow
+        config.hosts.push('example.com');
+        assert(config.hosts.length === 2);
+
+        const sealed = op_sealed_config();
+        assert(Object.isSealed(sealed) && !Object.isFrozen(sealed));
+        sealed.port = 1;
+        sealed.extra = true;
+        assert(sealed.port === 1 && sealed.extra === undefined);
+        assert(!strictWrite(sealed));
+
+        assert(op_frozen_number() === 1);",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_binary_heap_to_from_v8(
+    #[from_v8] value: BinaryHeap<Smi<u32>>,
+  ) -> BinaryHeap<Smi<u32>> {
+    value
+  }
+
+  #[op2]
+  pub fn op_binary_heap_pop(#[from_v8] mut value: BinaryHeap<Smi<u32>>) -> i32 {
+    value.pop().map_or(-1, |Smi(v)| v as _)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_binary_heap_to_from_v8(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_binary_heap_to_from_v8, op_binary_heap_pop",
+      r"
+        const sorted = op_binary_heap_to_from_v8([3, 1, 4, 1, 5, 9, 2, 6]);
+        assert(Array.isArray(sorted));
+        assert(sorted.join() === '9,6,5,4,3,2,1,1');
+        assert(op_binary_heap_to_from_v8([]).length === 0);
+        assert(op_binary_heap_pop([3, 1, 4, 1, 5]) === 5);
+        assert(op_binary_heap_pop([]) === -1);
+        try {
+          op_binary_heap_pop([1, 'a']);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [1]: Expected u32');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  pub fn op_try_to_v8_mixed<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    #[string] name: String,
+  ) -> Result<v8::Local<'s, v8::Value>, AnyError> {
+    // `Smi` can't fail, while `CheckedSmi` and `String` can
+    let elements = [
+      Smi(1u32).try_to_v8(scope)?,
+      CheckedSmi(2u32).try_to_v8(scope)?,
+      name.try_to_v8(scope)?,
+      CheckedSmi(u32::MAX).try_to_v8(scope)?,
+    ];
+    Ok(v8::Array::new_with_elements(scope, &elements).into())
+  }
+
+  #[op2]
+  pub fn op_try_to_v8_infallible<'s>(
+    scope: &mut v8::HandleScope<'s>,
+  ) -> Result<v8::Local<'s, v8::Value>, AnyError> {
+    let elements = [Smi(1u32).try_to_v8(scope)?, "a".try_to_v8(scope)?];
+    Ok(v8::Array::new_with_elements(scope, &elements).into())
+  }
+
+  #[tokio::test]
+  pub async fn test_op_try_to_v8() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_try_to_v8_mixed, op_try_to_v8_infallible",
+      r"
+        const array = op_try_to_v8_infallible();
+        assert(array.length === 2 && array[0] === 1 && array[1] === 'a');
+        try {
+          op_try_to_v8_mixed('a');
+          assert(false);
+        } catch (e) {
+          // The class of the error is kept
+          assert(e instanceof RangeError);
+          assertErrorContains(e, 'u32 value does not fit in a smi');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_be_u32(#[from_v8] value: Be<u32>) -> Be<u32> {
+    value
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_be_to_le_u32(#[from_v8] Be(value): Be<u32>) -> Le<u32> {
+    Le(value)
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_be_one() -> Be<u32> {
+    Be(1)
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_le_i16(#[from_v8] value: Le<i16>) -> Le<i16> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_be_le_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_be_u32, op_be_to_le_u32, op_be_one, op_le_i16",
+      r"
+        const one = op_be_one();
+        assert(one instanceof Uint8Array);
+        assert(one.join() === '0,0,0,1');
+        assert(op_be_u32(new Uint8Array([1, 2, 3, 4])).join() === '1,2,3,4');
+        assert(op_be_to_le_u32([1, 2, 3, 4]).join() === '4,3,2,1');
+        assert(new DataView(op_be_one().buffer).getUint32(0) === 1);
+        assert(op_le_i16(new Uint8Array([0xfe, 0xff])).join() === '254,255');
+        for (const bytes of [new Uint8Array(3), new Uint8Array(5)]) {
+          try {
+            op_be_u32(bytes);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, `Expected 4 bytes for a big-endian u32, got ${bytes.length}`);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "json")]
+  #[test]
+  fn test_json_value_to_from_v8() {
+    use serde_json::json;
+    use serde_json::Value;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let value = runtime
+      .execute_script(
+        "",
+        "({
+          name: 'deno',
+          nested: { list: [1, -2.5, null, true, [], {}], empty: '' },
+          skipped: undefined,
+          holes: [1, , 3],
+          big: 2 ** 53,
+          safe: Number.MAX_SAFE_INTEGER,
+          negZero: -0,
+          nan: NaN,
+          inf: -Infinity,
+          1: 'index',
+        })",
+      )
+      .unwrap();
+    let scope = &mut runtime.handle_scope();
+    let value = v8::Local::new(scope, value);
+    let json = Value::from_v8(scope, value).unwrap();
+    let expected = json!({
+      "1": "index",
+      "name": "deno",
+      "nested": { "list": [1, -2.5, null, true, [], {}], "empty": "" },
+      "holes": [1, null, 3],
+      "big": 9007199254740992.0,
+      "safe": 9007199254740991i64,
+      "negZero": 0,
+      "nan": null,
+      "inf": null,
+    });
+    assert_eq!(json, expected);
+    // Integer keys come first, like `JSON.stringify`
+    assert_eq!(
+      json.as_object().unwrap().keys().collect::<Vec<_>>(),
+      expected.as_object().unwrap().keys().collect::<Vec<_>>()
+    );
+    assert!(json["big"].is_f64());
+    assert!(json["safe"].is_i64());
+
+    // Round-trips through V8 without changes
+    let round_trip = json.clone().to_v8(scope).unwrap();
+    assert_eq!(Value::from_v8(scope, round_trip).unwrap(), json);
+    // And matches `JSON.parse(JSON.stringify(value))`. Parsing the string with serde
+    // instead would read `2 ** 53` back as an integer rather than a float.
+    let string = v8::json::stringify(scope, round_trip).unwrap();
+    let parsed = v8::json::parse(scope, string).unwrap();
+    assert_eq!(Value::from_v8(scope, parsed).unwrap(), json);
+
+    // Numbers that don't fit in an i32 become doubles
+    for n in [json!(1), json!(u64::MAX), json!(i64::MIN), json!(0.1)] {
+      let value = n.clone().to_v8(scope).unwrap();
+      assert_eq!(value.is_int32(), n == json!(1));
+      assert_eq!(value.number_value(scope), n.as_f64());
+    }
+
+    // `__proto__` is an own property rather than the prototype
+    let proto = json!({ "__proto__": { "polluted": true } }).to_v8(scope);
+    let proto = v8::Local::<v8::Object>::try_from(proto.unwrap()).unwrap();
+    let key = v8::String::new(scope, "__proto__").unwrap();
+    assert!(proto.has_own_property(scope, key.into()).unwrap());
+    assert_eq!(
+      Value::from_v8(scope, proto.into()).unwrap(),
+      json!({ "__proto__": { "polluted": true } })
+    );
+
+    let values = [
+      (
+        "({ a: [1, 2n] })",
+        "at a[1]: Expected JSON value, got bigint",
+      ),
+      ("[Symbol()]", "at [0]: Expected JSON value, got symbol"),
+      ("(() => {})", "Expected JSON value, got function"),
+    ];
+    for (source, message) in values {
+      let source = v8::String::new(scope, source).unwrap();
+      let script = v8::Script::compile(scope, source, None).unwrap();
+      let value = script.run(scope).unwrap();
+      let err = Value::from_v8(scope, value).unwrap_err();
+      assert_eq!(err.to_string(), message);
+    }
+  }
+
+  #[cfg(feature = "json")]
+  #[test]
+  fn test_json_value_from_v8_cycles() {
+    use serde_json::json;
+    use serde_json::Value;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let values = [
+      (
+        "const a = {}; a.self = a; a",
+        "at self: Circular reference to an enclosing value",
+      ),
+      (
+        "const b = [1]; b.push(b); b",
+        "at [1]: Circular reference to an enclosing value",
+      ),
+      (
+        "const c = { list: [{}] }; c.list[0].parent = c; c",
+        "at list[0].parent: Circular reference to an enclosing value",
+      ),
+    ];
+    for (source, message) in values {
+      let source = v8::String::new(scope, source).unwrap();
+      let script = v8::Script::compile(scope, source, None).unwrap();
+      let value = script.run(scope).unwrap();
+      let err = Value::from_v8(scope, value).unwrap_err();
+      assert_eq!(err.to_string(), message);
+      assert!(matches!(
+        ConvertError::find(&err.0),
+        Some(ConvertError::Invalid { .. })
+      ));
+    }
+
+    // Values that are shared without containing themselves are converted each time
+    let source = "const x = { n: 1 }; [x, { y: x }, x]";
+    let source = v8::String::new(scope, source).unwrap();
+    let script = v8::Script::compile(scope, source, None).unwrap();
+    let value = script.run(scope).unwrap();
+    assert_eq!(
+      Value::from_v8(scope, value).unwrap(),
+      json!([{ "n": 1 }, { "y": { "n": 1 } }, { "n": 1 }])
+    );
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_vec_to_v8_len(len: u32) -> Vec<Smi<u32>> {
+    (0..len).map(Smi).collect()
+  }
+
+  #[op2]
+  pub fn op_to_v8_array_len<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    len: u32,
+  ) -> Result<v8::Local<'s, v8::Array>, AnyError> {
+    Ok(crate::convert::to_v8_array(scope, (0..len).map(Smi))?)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_vec_to_v8_elements(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_vec_to_v8_len, op_to_v8_array_len",
+      r"
+        let calls = 0;
+        Object.defineProperty(Array.prototype, 1, {
+          configurable: true,
+          set(_) { calls++; },
+        });
+        try {
+          // Both paths define every element as an own data property, so the
+          // inherited setter never runs
+          for (const op of [op_vec_to_v8_len, op_to_v8_array_len]) {
+            for (const len of [0, 1, 3, 10000]) {
+              const array = op(len);
+              assert(array.length === len);
+              for (let i = 0; i < len; i++) {
+                assert(Object.hasOwn(array, i) && array[i] === i);
+              }
+            }
+          }
+          assert(calls === 0);
+        } finally {
+          delete Array.prototype[1];
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[string]
+  pub fn op_array_like_join(
+    #[from_v8] ArrayLike(values): ArrayLike<Option<String>>,
+  ) -> String {
+    values
+      .into_iter()
+      .map(|value| value.unwrap_or_else(|| "_".to_owned()))
+      .collect::<Vec<_>>()
+      .join(",")
+  }
+
+  #[tokio::test]
+  pub async fn test_op_array_like() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_array_like_join",
+      r"
+        assert(op_array_like_join({ 0: 'a', 1: 'b', length: 2 }) === 'a,b');
+        assert(op_array_like_join(['a', 'b', 'c']) === 'a,b,c');
+        (function () {
+          assert(op_array_like_join(arguments) === 'x,y');
+        })('x', 'y');
+        // Missing indices read as `undefined`, and extra ones are ignored
+        assert(op_array_like_join({ 1: 'b', 5: 'f', length: 3 }) === '_,b,_');
+        // `length` is coerced like `Array.from` does
+        assert(op_array_like_join({ 0: 'a', 1: 'b', length: 1.9 }) === 'a');
+        assert(op_array_like_join({ 0: 'a', length: '1' }) === 'a');
+        assert(op_array_like_join({ 0: 'a', length: { valueOf: () => 1 } }) === 'a');
+        assert(op_array_like_join({ 0: 'a', length: -1 }) === '');
+        assert(op_array_like_join({ 0: 'a', length: NaN }) === '');
+        assert(op_array_like_join({ 0: 'a' }) === '');
+        assert(op_array_like_join({ length: 2, get 0() { return 'g'; } }) === 'g,_');
+        try {
+          op_array_like_join({ length: 2 ** 32 });
+          assert(false);
+        } catch (e) {
+          assert(e.message === 'Array-like length 4294967296 is too large');
+        }
+        try {
+          op_array_like_join({ 0: 'a', 1: 2, length: 2 });
+          assert(false);
+        } catch (e) {
+          assert(e instanceof TypeError);
+          assert(e.message.startsWith('at [1]: '));
+        }
+        try {
+          op_array_like_join('ab');
+          assert(false);
+        } catch (e) {
+          assert(e.message === 'Expected array-like object, got string');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_optional_error(fail: bool) -> (Smi<u32>, Option<AnyError>) {
+    if fail {
+      (Smi(0), Some(generic_error("Failed to parse")))
+    } else {
+      (Smi(1), None)
+    }
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_optional_boxed_error(
+    fail: bool,
+  ) -> Option<Box<dyn std::error::Error + Send + Sync>> {
+    fail.then(|| "boxed".into())
+  }
+
+  #[tokio::test]
+  pub async fn test_op_optional_error() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_optional_error, op_optional_boxed_error",
+      r"
+        let [value, error] = op_optional_error(false);
+        assert(value === 1 && error === null);
+        [value, error] = op_optional_error(true);
+        assert(value === 0);
+        assert(error instanceof Error);
+        assert(error.message === 'Failed to parse');
+        assert(op_optional_boxed_error(false) === null);
+        const boxed = op_optional_boxed_error(true);
+        assert(boxed instanceof Error && boxed.message === 'boxed');",
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object)]
+  pub struct BaseEvent {
+    r#type: String,
+    timestamp: Number<f64>,
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object)]
+  pub struct ClickEvent {
+    #[v8(flatten)]
+    base: BaseEvent,
+    x: Smi<i32>,
+    y: Smi<i32>,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_flatten_round_trip(#[from_v8] event: ClickEvent) -> ClickEvent {
+    ClickEvent {
+      x: Smi(event.x.0 + 1),
+      ..event
+    }
+  }
+
+  #[tokio::test]
+  pub async fn test_op_flatten() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_flatten_round_trip",
+      r"
+        const event = op_flatten_round_trip({ type: 'click', timestamp: 1.5, x: 1, y: 2 });
+        assert(Object.keys(event).join() === 'type,timestamp,x,y');
+        assert(event.type === 'click' && event.timestamp === 1.5);
+        assert(event.x === 2 && event.y === 2);
+        try {
+          op_flatten_round_trip({ timestamp: 1.5, x: 1, y: 2 });
+          assert(false);
+        } catch (e) {
+          assert(e instanceof TypeError);
+          assert(e.message === 'Missing field \'type\'');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "glam")]
+  #[test]
+  fn test_glam_to_from_v8() {
+    use glam::Mat4;
+    use glam::Quat;
+    use glam::Vec3;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    let v = Vec3::new(1.0, -2.5, 3.25);
+    let value = v.to_v8(scope).unwrap();
+    let array = v8::Local::<v8::Float32Array>::try_from(value).unwrap();
+    assert_eq!(array.length(), 3);
+    assert_eq!(Vec3::from_v8(scope, value).unwrap(), v);
+
+    // Matrices are column-major, so the translation is in the last column
+    let m = Mat4::from_scale_rotation_translation(
+      Vec3::new(2.0, 2.0, 2.0),
+      Quat::from_rotation_z(0.5),
+      Vec3::new(10.0, 20.0, 30.0),
+    );
+    let value = m.to_v8(scope).unwrap();
+    let array = v8::Local::<v8::Float32Array>::try_from(value).unwrap();
+    assert_eq!(array.length(), 16);
+    let translation = (12..15)
+      .map(|i| array.get_index(scope, i).unwrap().number_value(scope))
+      .collect::<Vec<_>>();
+    assert_eq!(translation, [Some(10.0), Some(20.0), Some(30.0)]);
+    assert_eq!(Mat4::from_v8(scope, value).unwrap(), m);
+
+    let numbers = [1.0, 2.0, 3.0].map(|n| v8::Number::new(scope, n).into());
+    let plain = v8::Array::new_with_elements(scope, &numbers).into();
+    assert_eq!(
+      Vec3::from_v8(scope, plain).unwrap(),
+      Vec3::new(1.0, 2.0, 3.0)
+    );
+
+    let err = Vec3::from_v8(scope, value).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Expected Float32Array of length 3 for a Vec3, got 16"
+    );
+    let buffer = v8::ArrayBuffer::new(scope, 24);
+    let doubles = v8::Float64Array::new(scope, buffer, 0, 3).unwrap().into();
+    let err = Vec3::from_v8(scope, doubles).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Expected Float32Array or array, got Float64Array"
+    );
+  }
+
+  #[op2]
+  #[to_v8]
+  fn op_cached_label(open: bool) -> Cached<&'static str> {
+    Cached(if open { "open" } else { "closed" })
+  }
+
+  #[tokio::test]
+  pub async fn test_op_cached() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_cached_label",
+      r"
+      assert(op_cached_label(true) === 'open');
+      assert(op_cached_label(false) === 'closed');
+      assert(op_cached_label(true) === 'open');
+      ",
+    )?;
+    Ok(())
+  }
+
+  thread_local! {
+    static LABEL_CONVERSIONS: Cell<usize> = const { Cell::new(0) };
+  }
+
+  #[derive(Clone, PartialEq, Eq, Hash)]
+  struct CountedLabel(&'static str);
+
+  impl<'a> ToV8<'a> for CountedLabel {
+    type Error = StdAnyError;
+
+    fn to_v8(
+      self,
+      scope: &mut v8::HandleScope<'a>,
+    ) -> Result<v8::Local<'a, v8::Value>, Self::Error> {
+      LABEL_CONVERSIONS.with(|c| c.set(c.get() + 1));
+      self.0.to_v8(scope)
+    }
+  }
+
+  #[test]
+  fn test_cached_to_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    // Equal values are converted once and then reused. Comparing the handles wouldn't
+    // show this, since `==` on values compares strings by their contents.
+    LABEL_CONVERSIONS.with(|c| c.set(0));
+    let a = Cached(CountedLabel("label")).to_v8(scope).unwrap();
+    let b = Cached(CountedLabel("label")).to_v8(scope).unwrap();
+    assert!(a == b);
+    assert_eq!(LABEL_CONVERSIONS.with(|c| c.get()), 1);
+    let other = Cached(CountedLabel("other")).to_v8(scope).unwrap();
+    assert!(a != other);
+    assert_eq!(other.to_rust_string_lossy(scope), "other");
+    assert_eq!(LABEL_CONVERSIONS.with(|c| c.get()), 2);
+
+    // Objects are converted each time
+    let a = Cached(vec!["label"]).to_v8(scope).unwrap();
+    let b = Cached(vec!["label"]).to_v8(scope).unwrap();
+    assert!(a.is_array());
+    assert!(a != b);
+  }
+
+  #[op2]
+  #[string]
+  pub fn op_bounded_vec_join(
+    #[from_v8] BoundedVec(values): BoundedVec<Option<String>, 4>,
+  ) -> String {
+    values
+      .into_iter()
+      .map(|value| value.unwrap_or_else(|| "_".to_owned()))
+      .collect::<Vec<_>>()
+      .join(",")
+  }
+
+  #[tokio::test]
+  pub async fn test_op_bounded_vec() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_bounded_vec_join",
+      r"
+        assert(op_bounded_vec_join([]) === '');
+        assert(op_bounded_vec_join(['a', 'b', 'c', 'd']) === 'a,b,c,d');
+        assert(op_bounded_vec_join(new Array(4)) === '_,_,_,_');
+        try {
+          op_bounded_vec_join(['a', 'b', 'c', 'd', 'e']);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Array too large: expected at most 4 elements, got 5');
+        }
+        // A sparse array can claim any length without allocating its elements
+        try {
+          op_bounded_vec_join(new Array(2 ** 32 - 1));
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Array too large: expected at most 4 elements, got 4294967295');
+        }
+        // A proxy can report any length at all, but isn't converted as an array
+        const proxy = new Proxy([], {
+          get: (target, key) => key === 'length' ? Number.MAX_SAFE_INTEGER : target[key],
+        });
+        assert(proxy.length === Number.MAX_SAFE_INTEGER);
+        try {
+          op_bounded_vec_join(proxy);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected array');
+        }
+        try {
+          op_bounded_vec_join([1]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [0]: Expected string');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(discriminant, constants)]
+  pub enum DeriveLevel {
+    Debug = 10,
+    #[v8(rename = "information")]
+    Info = 20,
+    Warn,
+    Negative = -1,
+  }
+
+  #[test]
+  fn test_derive_enum_constants() {
+    let mut runtime = JsRuntime::new(Default::default());
+    {
+      let scope = &mut runtime.handle_scope();
+      let global = scope.get_current_context().global(scope);
+      DeriveLevel::install_constants(scope, global).unwrap();
+    }
+    // The object is frozen
+    let err = runtime
+      .execute_script(
+        "",
+        r"
+        'use strict';
+        DeriveLevel.Debug = 0;
+        ",
+      )
+      .map(|_| ())
+      .unwrap_err();
+    assert!(err.to_string().contains("read only property 'Debug'"));
+    let value = runtime
+      .execute_script(
+        "",
+        r"
+        JSON.stringify([
+          Object.isFrozen(DeriveLevel),
+          DeriveLevel.Debug,
+          DeriveLevel.information,
+          DeriveLevel.Warn,
+          DeriveLevel.Negative,
+          DeriveLevel.Info,
+          DeriveLevel[10],
+          DeriveLevel[20],
+          DeriveLevel[21],
+          DeriveLevel[-1],
+          Object.keys(DeriveLevel),
+        ])
+        ",
+      )
+      .unwrap();
+    let scope = &mut runtime.handle_scope();
+    let value = v8::Local::new(scope, value);
+    assert_eq!(
+      value.to_rust_string_lossy(scope),
+      r#"[true,10,20,21,-1,null,"Debug","information","Warn","Negative",["10","20","21","Debug","information","Warn","Negative","-1"]]"#
+    );
+  }
+
+  #[op2]
+  pub fn op_clamp_gain(#[from_v8] Clamp(gain): Clamp<f64, 0, 2>) -> f64 {
+    gain
+  }
+
+  #[op2]
+  pub fn op_clamp_u8(#[from_v8] Clamp(value): Clamp<u8, 10, 200>) -> u32 {
+    value as u32
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_clamp_to_v8(value: i32) -> Clamp<i32, -5, 5> {
+    Clamp(value)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_clamp() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_clamp_gain, op_clamp_u8, op_clamp_to_v8",
+      r"
+        // Below the minimum
+        assert(op_clamp_gain(-0.5) === 0);
+        assert(op_clamp_gain(-Infinity) === 0);
+        assert(op_clamp_u8(-1000) === 10);
+        assert(op_clamp_u8(9.9) === 10);
+        // In range
+        assert(op_clamp_gain(0) === 0);
+        assert(op_clamp_gain(1.25) === 1.25);
+        assert(op_clamp_gain(2) === 2);
+        assert(op_clamp_u8(10) === 10);
+        assert(op_clamp_u8(99.9) === 99);
+        assert(op_clamp_u8(200) === 200);
+        // Above the maximum
+        assert(op_clamp_gain(2.5) === 2);
+        assert(op_clamp_gain(Infinity) === 2);
+        assert(op_clamp_u8(256) === 200);
+        assert(op_clamp_u8(1e300) === 200);
+        // Converting to JS clamps too
+        assert(op_clamp_to_v8(-100) === -5);
+        assert(op_clamp_to_v8(3) === 3);
+        assert(op_clamp_to_v8(100) === 5);
+        for (const [value, message] of [
+          [NaN, 'Expected a number between 0 and 2, got NaN'],
+          ['1', 'Expected f64'],
+          [1n, 'Expected f64'],
+          [null, 'Expected f64'],
+        ]) {
+          try {
+            op_clamp_gain(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "bitflags")]
+  bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TestMode: u32 {
+      const READ = 1;
+      const WRITE = 1 << 1;
+      const APPEND = 1 << 2;
+    }
+  }
+
+  #[cfg(feature = "bitflags")]
+  #[test]
+  fn test_bitflags_to_from_v8() {
+    use crate::convert::BitFlags;
+    use crate::convert::TruncatedBitFlags;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    let mode = TestMode::READ | TestMode::APPEND;
+    let value = BitFlags(mode).to_v8(scope).unwrap();
+    assert_eq!(value.uint32_value(scope), Some(5));
+    assert_eq!(BitFlags::<TestMode>::from_v8(scope, value).unwrap().0, mode);
+    let value = v8::Integer::new(scope, 0).into();
+    assert_eq!(
+      BitFlags::<TestMode>::from_v8(scope, value).unwrap().0,
+      TestMode::empty()
+    );
+
+    // Bit 3 isn't a flag
+    let value = v8::Integer::new(scope, 0b1011).into();
+    let err = BitFlags::<TestMode>::from_v8(scope, value).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown flag bits 0x8 are set");
+    assert_eq!(
+      TruncatedBitFlags::<TestMode>::from_v8(scope, value)
+        .unwrap()
+        .0,
+      TestMode::READ | TestMode::WRITE
+    );
+
+    for value in [-1.0, 1.5, 2f64.powi(32)] {
+      let value = v8::Number::new(scope, value).into();
+      let err = BitFlags::<TestMode>::from_v8(scope, value).unwrap_err();
+      assert_eq!(err.to_string(), "Expected u32 that is a safe integer");
+    }
+  }
+
+  pub struct TestPoint {
+    x: i32,
+    y: i32,
+  }
+
+  impl std::fmt::Display for TestPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      write!(f, "({}, {})", self.x, self.y)
+    }
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_display_string(x: i32, y: i32) -> DisplayString<TestPoint> {
+    DisplayString(TestPoint { x, y })
+  }
+
+  #[op2]
+  pub fn op_format_args<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    n: u32,
+  ) -> Result<v8::Local<'s, v8::Value>, AnyError> {
+    Ok(if n == 0 {
+      format_args!("none").to_v8(scope)?
+    } else {
+      format_args!("{n} item{}", if n == 1 { "" } else { "s" }).to_v8(scope)?
+    })
+  }
+
+  #[tokio::test]
+  pub async fn test_op_display_string() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_display_string, op_format_args",
+      r"
+        assert(op_display_string(1, -2) === '(1, -2)');
+        assert(op_display_string(0, 0) === '(0, 0)');
+        assert(op_format_args(0) === 'none');
+        assert(op_format_args(1) === '1 item');
+        assert(op_format_args(3) === '3 items');
+        ",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[op2]
+  #[to_v8]
+  pub fn op_duration_hrtime_to_nanos(#[from_v8] value: HrTime) -> Nanos {
+    Nanos(value.0)
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[op2]
+  #[to_v8]
+  pub fn op_duration_nanos_to_hrtime(#[from_v8] value: Nanos) -> HrTime {
+    HrTime(value.0)
+  }
+
+  #[cfg(feature = "convert_time")]
+  #[tokio::test]
+  pub async fn test_op_duration_hrtime(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_duration_hrtime_to_nanos, op_duration_nanos_to_hrtime",
+      r"
+        assert(op_duration_hrtime_to_nanos([0, 0]) === 0n);
+        assert(op_duration_hrtime_to_nanos([1, 500]) === 1000000500n);
+        assert(op_duration_hrtime_to_nanos([2, 999999999]) === 2999999999n);
+        const large = op_duration_hrtime_to_nanos([Number.MAX_SAFE_INTEGER, 1]);
+        assert(large === BigInt(Number.MAX_SAFE_INTEGER) * 1000000000n + 1n);
+        const time = op_duration_nanos_to_hrtime(2999999999n);
+        assert(Array.isArray(time) && time.length === 2);
+        assert(time[0] === 2 && time[1] === 999999999);
+        // Nanoseconds that overflow into seconds aren't normalized
+        for (const [value, message] of [
+          [[1, 1000000000], 'at [1]: Expected fewer than 1000000000 nanoseconds, got 1000000000'],
+          [[0, 1500000000], 'at [1]: Expected fewer than 1000000000 nanoseconds, got 1500000000'],
+          [[-1, 0], 'at [0]: Expected u64 that is a safe integer'],
+          [[1, 0.5], 'at [1]: Expected u64 that is a safe integer'],
+          [[1], 'Expected array of length 2'],
+          [1000, 'Expected array'],
+        ]) {
+          try {
+            op_duration_hrtime_to_nanos(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }
+        try {
+          op_duration_nanos_to_hrtime(2n ** 64n * 1000000000n - 1n);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [0]: u64 value is not a safe integer');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[string]
+  pub fn op_either_describe(
+    #[from_v8] value: Either<String, Number<f64>>,
+  ) -> String {
+    match value {
+      Either::Left(s) => format!("string {s}"),
+      Either::Right(Number(n)) => format!("number {n}"),
+    }
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_either_round_trip(
+    #[from_v8] value: Either<Smi<i32>, Either<String, Vec<Smi<u32>>>>,
+  ) -> Either<Smi<i32>, Either<String, Vec<Smi<u32>>>> {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_either() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_either_describe, op_either_round_trip",
+      r"
+        assert(op_either_describe('abc') === 'string abc');
+        assert(op_either_describe(1.5) === 'number 1.5');
+        assert(op_either_describe(-2) === 'number -2');
+        assert(op_either_round_trip(3) === 3);
+        assert(op_either_round_trip('a') === 'a');
+        assert(op_either_round_trip([1, 2]).join() === '1,2');
+        for (const [op, value, message] of [
+          [op_either_describe, true, 'Expected string or f64'],
+          [op_either_describe, null, 'Expected string or f64'],
+          [op_either_round_trip, true, 'Expected i32 or string or array'],
+          [op_either_round_trip, [1, 'a'], 'Failed to convert as either type: Expected i32, got array; Failed to convert as either type: Expected string, got array; at [1]: Expected u32, got string'],
+        ]) {
+          try {
+            op(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  /// A marker that implements neither `ToV8` nor `FromV8`.
+  pub struct UserMarker;
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object)]
+  pub struct DeriveTypedId<T> {
+    id: Smi<u32>,
+    marker: std::marker::PhantomData<T>,
+  }
+
+  #[derive(FromV8, ToV8)]
+  pub struct DeriveTypedTuple<T>(Smi<u32>, std::marker::PhantomData<T>, bool);
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_phantom(
+    #[from_v8] value: DeriveTypedId<UserMarker>,
+  ) -> DeriveTypedId<UserMarker> {
+    DeriveTypedId {
+      id: Smi(value.id.0 + 1),
+      marker: value.marker,
+    }
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_phantom_tuple(
+    #[from_v8] value: DeriveTypedTuple<UserMarker>,
+  ) -> DeriveTypedTuple<UserMarker> {
+    DeriveTypedTuple(value.0, value.1, !value.2)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_phantom() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_phantom, op_derive_phantom_tuple",
+      r"
+        const value = op_derive_phantom({ id: 1 });
+        assert(value.id === 2);
+        assert(Object.keys(value).join() === 'id');
+        // The marker isn't part of the JS representation
+        const tuple = op_derive_phantom_tuple([1, true]);
+        assert(tuple.length === 2 && tuple[0] === 1 && tuple[1] === false);
+        try {
+          op_derive_phantom_tuple([1, null, true]);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected array of length 2');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[derive(FromV8, ToV8)]
+  #[v8(object, rename_all = "camelCase")]
+  pub struct DeriveCamelCase {
+    byte_length: Smi<u32>,
+    is_shared: bool,
+    #[v8(rename = "maxByteLength")]
+    max_length: Option<Smi<u32>>,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_derive_rename_all(
+    #[from_v8] value: DeriveCamelCase,
+  ) -> DeriveCamelCase {
+    value
+  }
+
+  #[tokio::test]
+  pub async fn test_op_derive_rename_all(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_derive_rename_all",
+      r#"
+        const value = op_derive_rename_all({ byteLength: 4, isShared: true, maxByteLength: 8 });
+        assert(JSON.stringify(value) === '{"byteLength":4,"isShared":true,"maxByteLength":8}');
+        try {
+          op_derive_rename_all({ byte_length: 4, is_shared: true });
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, "Missing field 'byteLength'");
+        }"#,
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_drained_to_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(8);
+    for item in ["a", "b", "c", "d", "e"] {
+      sender.try_send(item).unwrap();
+    }
+    let length = |scope: &mut v8::HandleScope, drained: Drained<&str>| {
+      let value = drained.to_v8(scope).unwrap();
+      v8::Local::<v8::Array>::try_from(value).unwrap().length()
+    };
+
+    // At most `max` items are taken, in the order they were sent
+    let first = Drained::from_receiver(&mut receiver, 3);
+    assert_eq!(first.clone().into_items(), vec!["a", "b", "c"]);
+    assert_eq!(length(scope, first), 3);
+    assert_eq!(length(scope, Drained::from_receiver(&mut receiver, 3)), 2);
+    // An empty channel that is still open gives an empty array
+    let empty = Drained::from_receiver(&mut receiver, 3);
+    assert!(!empty.is_closed());
+    assert_eq!(length(scope, empty), 0);
+
+    // The remaining items are delivered after the channel is closed, then `null`
+    sender.try_send("f").unwrap();
+    drop(sender);
+    let last = Drained::from_receiver(&mut receiver, 3);
+    assert!(!last.is_closed());
+    assert_eq!(last.into_items(), vec!["f"]);
+    let closed = Drained::from_receiver(&mut receiver, 3);
+    assert!(closed.is_closed());
+    assert!(closed.to_v8(scope).unwrap().is_null());
+  }
+
+  struct TestPrototype(v8::Global<v8::Object>);
+
+  #[op2]
+  pub fn op_register_prototype(
+    state: &mut OpState,
+    #[global] prototype: v8::Global<v8::Object>,
+  ) {
+    state.put(TestPrototype(prototype));
+  }
+
+  #[derive(ToV8)]
+  #[v8(object)]
+  pub struct DeriveInstance {
+    code: Smi<u32>,
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_with_prototype(
+    state: &mut OpState,
+    code: u32,
+  ) -> WithPrototype<DeriveInstance> {
+    let prototype = state.borrow::<TestPrototype>().0.clone();
+    WithPrototype::new(prototype, DeriveInstance { code: Smi(code) })
+  }
+
+  #[tokio::test]
+  pub async fn test_op_with_prototype() -> Result<(), Box<dyn std::error::Error>>
+  {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_register_prototype, op_with_prototype",
+      r"
+        class MyClass {
+          describe() {
+            return 'code ' + this.code;
+          }
+        }
+        op_register_prototype(MyClass.prototype);
+        const value = op_with_prototype(7);
+        assert(value instanceof MyClass);
+        assert(Object.getPrototypeOf(value) === MyClass.prototype);
+        assert(value.code === 7);
+        assert(Object.keys(value).join() === 'code');
+        assert(value.describe() === 'code 7');",
+    )?;
+    Ok(())
+  }
+
+  #[cfg(feature = "smallvec")]
+  #[test]
+  fn test_smallvec_to_from_v8() {
+    use smallvec::smallvec;
+    use smallvec::SmallVec;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    let inline: SmallVec<[String; 2]> = smallvec!["a".into(), "b".into()];
+    let value = inline.clone().to_v8(scope).unwrap();
+    let converted = SmallVec::<[String; 2]>::from_v8(scope, value).unwrap();
+    assert!(!converted.spilled());
+    assert_eq!(converted, inline);
+
+    // Longer arrays spill to the heap
+    let spilled = SmallVec::<[String; 1]>::from_v8(scope, value).unwrap();
+    assert!(spilled.spilled());
+    assert_eq!(spilled.as_slice(), inline.as_slice());
+
+    // The strings converted before the error are dropped, not leaked
+    let one = v8::String::new(scope, "a").unwrap().into();
+    let two = v8::Number::new(scope, 2.0).into();
+    let mixed = v8::Array::new_with_elements(scope, &[one, two]).into();
+    let err = SmallVec::<[String; 4]>::from_v8(scope, mixed).unwrap_err();
+    assert!(err.to_string().starts_with("at [1]: "), "{err}");
+    let empty = v8::Array::new(scope, 0).into();
+    assert!(SmallVec::<[String; 4]>::from_v8(scope, empty)
+      .unwrap()
+      .is_empty());
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_text_span(#[from_v8] span: TextSpan) -> TextSpan {
+    TextSpan {
+      start: span.start,
+      end: span.end + span.len(),
+    }
+  }
+
+  #[tokio::test]
+  pub async fn test_op_text_span() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_text_span",
+      r"
+        const span = op_text_span({ start: 2, end: 5 });
+        assert(span.start === 2 && span.end === 8);
+        const empty = op_text_span({ start: 3, end: 3 });
+        assert(empty.start === 3 && empty.end === 3);
+        for (const [value, message] of [
+          [{ start: 5, end: 2 }, 'Expected span start to be at most its end, got 5 > 2'],
+          [{ start: -1, end: 2 }, 'at start: '],
+          [{ start: 0.5, end: 2 }, 'at start: '],
+          [{ start: 1 }, 'Missing field'],
+        ]) {
+          try {
+            op_text_span(value);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_text_span_to_v8_inverted() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    let err = TextSpan { start: 2, end: 1 }.to_v8(scope).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Expected span start to be at most its end, got 2 > 1"
+    );
+    assert_eq!(
+      ConvertError::find(&err.0),
+      Some(&ConvertError::invalid(
+        "Expected span start to be at most its end, got 2 > 1"
+      ))
+    );
+    assert_eq!(Range::from(TextSpan { start: 1, end: 4 }), 1..4);
+  }
+
+  #[test]
+  fn test_op_return() {
+    use crate::convert::op_return;
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    // As if returned from an op marked `#[number]`, rather than a `BigInt`
+    let value = op_return::number(scope, 1u64 << 40);
+    assert!(value.is_number());
+    assert_eq!(value.number_value(scope), Some((1u64 << 40) as f64));
+    assert!(op_return::value(scope, 1u64 << 40).is_big_int());
+    // `#[smi]` casts to an `i32`
+    let value = op_return::smi(scope, u32::MAX);
+    assert_eq!(value.int32_value(scope), Some(-1));
+    assert!(op_return::value(scope, None::<bool>).is_null());
+
+    let value = op_return::string(scope, "é").unwrap();
+    assert_eq!(value.to_rust_string_lossy(scope), "é");
+    let value = op_return::buffer(scope, vec![1u8, 2, 3]).unwrap();
+    assert!(value.is_uint8_array());
+    let value = op_return::arraybuffer(scope, vec![1u8, 2, 3]);
+    assert!(value.is_array_buffer());
+    let value = op_return::serde(scope, vec![1, 2]).unwrap();
+    assert!(value.is_array());
+
+    // Failures are the `TypeError` that the op would throw
+    let err = op_return::to_v8(scope, CheckedSmi(i64::MAX)).unwrap_err();
+    assert_eq!(
+      crate::error::get_custom_error_class(&err.0),
+      Some("TypeError")
+    );
+  }
+
+  #[op2]
+  pub fn op_fill_out_buffer(
+    scope: &mut v8::HandleScope,
+    #[from_v8] mut out: OutBuffer,
+    #[smi] value: u32,
+  ) -> u32 {
+    let bytes = out.bytes_mut(scope);
+    bytes.fill(value as u8);
+    bytes.len() as u32
+  }
+
+  #[tokio::test]
+  pub async fn test_op_out_buffer() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_fill_out_buffer",
+      r"
+        const buf = new Uint8Array(4);
+        assert(op_fill_out_buffer(buf, 7) === 4);
+        assert(buf.every((b) => b === 7));
+        // Only the bytes of a subarray are written
+        const whole = new Uint8Array(6);
+        assert(op_fill_out_buffer(whole.subarray(2, 4), 1) === 2);
+        assert(whole.join() === '0,0,1,1,0,0');
+        assert(op_fill_out_buffer(new Uint8Array(0), 1) === 0);
+        const detached = new Uint8Array(4);
+        detached.buffer.transfer();
+        for (const [value, message] of [
+          [detached, 'ArrayBuffer is detached'],
+          [new Uint8Array(new SharedArrayBuffer(4)), 'SharedArrayBuffer'],
+          [new Uint16Array(4), 'Expected Uint8Array, got Uint16Array'],
+          [[1, 2], 'Expected Uint8Array'],
+        ]) {
+          try {
+            op_fill_out_buffer(value, 1);
+            assert(false);
+          } catch (e) {
+            assertErrorContains(e, message);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_maybe_round_trip(
+    #[from_v8] value: Maybe<Smi<u32>>,
+  ) -> Maybe<Smi<u32>> {
+    value
+  }
+
+  #[derive(FromV8)]
+  #[v8(object)]
+  pub struct DeriveMaybePatch {
+    name: Maybe<String>,
+  }
+
+  #[op2]
+  #[string]
+  pub fn op_derive_maybe_patch(#[from_v8] patch: DeriveMaybePatch) -> String {
+    format!("{:?}", patch.name)
+  }
+
+  #[tokio::test]
+  pub async fn test_op_maybe() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_maybe_round_trip, op_derive_maybe_patch",
+      r#"
+        assert(op_maybe_round_trip(undefined) === undefined);
+        assert(op_maybe_round_trip() === undefined);
+        assert(op_maybe_round_trip(null) === null);
+        assert(op_maybe_round_trip(3) === 3);
+        try {
+          op_maybe_round_trip("a");
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'Expected u32');
+        }
+        assert(op_derive_maybe_patch({}) === 'Undefined');
+        assert(op_derive_maybe_patch({ name: undefined }) === 'Undefined');
+        assert(op_derive_maybe_patch({ name: null }) === 'Null');
+        assert(op_derive_maybe_patch({ name: "a" }) === 'Present("a")');"#,
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_maybe_to_from_v8() {
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+    for maybe in [Maybe::Undefined, Maybe::Null, Maybe::Present(true)] {
+      let value = maybe.to_v8(scope).unwrap();
+      assert_eq!(value.is_undefined(), maybe.is_undefined());
+      assert_eq!(value.is_null(), maybe.is_null());
+      assert_eq!(Maybe::<bool>::from_v8(scope, value).unwrap(), maybe);
+    }
+    let undefined = v8::undefined(scope).into();
+    assert_eq!(Option::<bool>::from_v8(scope, undefined).unwrap(), None);
+    let null = None::<bool>.to_v8(scope).unwrap();
+    assert!(null.is_null());
+  }
+
+  #[cfg(feature = "ndarray")]
+  #[test]
+  fn test_ndarray_to_from_v8() {
+    use ndarray::Array1;
+    use ndarray::Array2;
+
+    let mut runtime = JsRuntime::new(Default::default());
+    let scope = &mut runtime.handle_scope();
+
+    let vector = Array1::from_vec(vec![1.5, -2.0, 3.25]);
+    let value = vector.clone().to_v8(scope).unwrap();
+    let array = v8::Local::<v8::Float64Array>::try_from(value).unwrap();
+    assert_eq!(array.length(), 3);
+    assert_eq!(Array1::<f64>::from_v8(scope, value).unwrap(), vector);
+
+    // Reshaped into two rows of three, which are laid out one after the other
+    let matrix = Array1::from_iter((0..6).map(f64::from))
+      .into_shape_with_order((2, 3))
+      .unwrap();
+    let value = matrix.clone().to_v8(scope).unwrap();
+    let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+    let key = v8::String::new(scope, "data").unwrap().into();
+    let data = object.get(scope, key).unwrap();
+    let data = v8::Local::<v8::Float64Array>::try_from(data).unwrap();
+    assert_eq!(data.length(), 6);
+    let second_row = data.get_index(scope, 3).unwrap().number_value(scope);
+    assert_eq!(second_row, Some(3.0));
+    let key = v8::String::new(scope, "shape").unwrap().into();
+    let shape = object.get(scope, key).unwrap();
+    let [SafeNumber(rows), SafeNumber(cols)] =
+      <[SafeNumber<usize>; 2]>::from_v8(scope, shape).unwrap();
+    assert_eq!((rows, cols), (2, 3));
+    let round_trip = Array2::<f64>::from_v8(scope, value).unwrap();
+    assert_eq!(round_trip, matrix);
+    assert_eq!(round_trip[[1, 2]], 5.0);
+
+    // A transposed view isn't contiguous in row-major order
+    let err = matrix.t().to_v8(scope).unwrap_err();
+    assert!(err.to_string().contains("standard layout"), "{err}");
+
+    let key = v8::String::new(scope, "shape").unwrap().into();
+    let wrong_shape =
+      [SafeNumber(4u32), SafeNumber(2u32)].to_v8(scope).unwrap();
+    object.set(scope, key, wrong_shape);
+    let err = Array2::<f64>::from_v8(scope, value).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Expected 4 * 2 elements for shape [4, 2], got 6"
+    );
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_collect_squares(
+    len: u32,
+  ) -> Collect<impl Iterator<Item = Smi<u32>>> {
+    Collect((0..len).map(|i| Smi(i * i)))
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_collect_even(len: u32) -> Collect<impl Iterator<Item = Smi<u32>>> {
+    // A filtered iterator only has a lower size bound of zero, so the array grows
+    Collect((0..len).filter(|i| i % 2 == 0).map(Smi))
+  }
+
+  #[op2]
+  #[to_v8]
+  pub fn op_collect_unsafe(
+    #[number] value: u64,
+  ) -> Collect<impl Iterator<Item = SafeNumber<u64>>> {
+    Collect([1, value].into_iter().map(SafeNumber))
+  }
+
+  #[tokio::test]
+  pub async fn test_op_collect() -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_collect_squares, op_collect_even, op_collect_unsafe",
+      r"
+        const squares = op_collect_squares(10);
+        assert(Array.isArray(squares) && squares.length === 10);
+        assert(squares.join() === '0,1,4,9,16,25,36,49,64,81');
+        assert(op_collect_squares(0).length === 0);
+        assert(op_collect_even(10).join() === '0,2,4,6,8');
+        assert(op_collect_unsafe(2).join() === '1,2');
+        try {
+          op_collect_unsafe(2 ** 53);
+          assert(false);
+        } catch (e) {
+          assertErrorContains(e, 'at [1]: ');
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  pub async fn test_op_from_v8_type_names(
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    run_test2(
+      JIT_ITERATIONS,
+      "op_smi_to_from_v8",
+      r"
+        for (const [value, got] of [
+          ['1', 'string'],
+          [null, 'null'],
+          [undefined, 'undefined'],
+          [true, 'boolean'],
+          [Symbol(), 'symbol'],
+          [{}, 'object'],
+          [[1], 'array'],
+          [() => 1, 'function'],
+          [new Uint8Array(1), 'Uint8Array'],
+        ]) {
+          try {
+            op_smi_to_from_v8(value);
+            assert(false);
+          } catch (e) {
+            assert(e instanceof TypeError);
+            assert(e.message === `Expected i32, got ${got}`);
+          }
+        }",
+    )?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_js_type_name() {
+    let mut runtime = JsRuntime::new(Default::default());
+    for (source, name) in [
+      ("1.5", "number"),
+      ("'a'", "string"),
+      ("null", "null"),
+      ("undefined", "undefined"),
+      ("false", "boolean"),
+      ("1n", "bigint"),
+      ("Symbol()", "symbol"),
+      ("({})", "object"),
+      ("new Date()", "object"),
+      ("[]", "array"),
+      ("(class {})", "function"),
+      ("new DataView(new ArrayBuffer(1))", "DataView"),
+    ] {
+      let value = runtime.execute_script("", source).unwrap();
+      let scope = &mut runtime.handle_scope();
+      let value = v8::Local::new(scope, value);
+      assert_eq!(js_type_name(&value), name, "{source}");
+    }
+  }
 }
@@ -27,6 +27,7 @@
 use bytes::BytesMut;
 use libc::c_void;
 use std::borrow::Cow;
+use std::convert::Infallible;
 use std::marker::PhantomData;
 use std::rc::Rc;
 
@@ -420,6 +421,67 @@ impl<'a, T: crate::ToV8<'a>> RustToV8Fallible<'a>
   }
 }
 
+/// Returned by [`ToV8InfallibleKind::to_v8_kind`] for a [`ToV8`](crate::ToV8) value whose
+/// `Error` is [`Infallible`].
+///
+/// The conversion keeps the `Infallible` error type rather than boxing it into a
+/// [`serde_v8::Error`], so the caller's error branch has an uninhabited payload and is
+/// removed at compile time, even in unoptimized builds.
+pub struct ToV8InfallibleTag;
+
+/// Returned by [`ToV8FallibleKind::to_v8_kind`] for any other [`ToV8`](crate::ToV8) value.
+pub struct ToV8FallibleTag;
+
+/// Selects whether a [`ToV8`](crate::ToV8) conversion can fail by its `Error` type, without
+/// specialization. With both this trait and [`ToV8FallibleKind`] in scope,
+/// `(&&value).to_v8_kind()` picks this impl whenever its bound holds, because it matches
+/// the receiver with one fewer auto-deref. Otherwise it falls back to [`ToV8FallibleKind`].
+///
+/// `#[op2]` uses this for `#[to_v8]` return values.
+pub trait ToV8InfallibleKind {
+  #[inline(always)]
+  fn to_v8_kind(&self) -> ToV8InfallibleTag {
+    ToV8InfallibleTag
+  }
+}
+
+impl<'a, T: crate::ToV8<'a, Error = Infallible>> ToV8InfallibleKind
+  for &RustToV8Marker<ToV8Marker, T>
+{
+}
+
+/// The fallback for [`ToV8InfallibleKind`].
+pub trait ToV8FallibleKind {
+  #[inline(always)]
+  fn to_v8_kind(&self) -> ToV8FallibleTag {
+    ToV8FallibleTag
+  }
+}
+
+impl<T> ToV8FallibleKind for RustToV8Marker<ToV8Marker, T> {}
+
+impl ToV8InfallibleTag {
+  #[inline(always)]
+  pub fn to_v8<'a, T: crate::ToV8<'a, Error = Infallible>>(
+    self,
+    value: RustToV8Marker<ToV8Marker, T>,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> Result<v8::Local<'a, v8::Value>, Infallible> {
+    value.0.to_v8(scope)
+  }
+}
+
+impl ToV8FallibleTag {
+  #[inline(always)]
+  pub fn to_v8<'a, T: crate::ToV8<'a>>(
+    self,
+    value: RustToV8Marker<ToV8Marker, T>,
+    scope: &mut v8::HandleScope<'a>,
+  ) -> serde_v8::Result<v8::Local<'a, v8::Value>> {
+    value.to_v8_fallible(scope)
+  }
+}
+
 //
 // SMI
 //
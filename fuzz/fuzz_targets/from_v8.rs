@@ -0,0 +1,370 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Feeds arbitrary JS values into the built-in `FromV8` impls, checking that every
+//! conversion either succeeds or returns an error, without panicking, leaking or
+//! double-dropping the elements it converted.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use deno_core::convert::BigInt;
+use deno_core::convert::BoundedVec;
+use deno_core::convert::CheckedSmi;
+use deno_core::convert::Clamp;
+use deno_core::convert::Either;
+use deno_core::convert::FromV8;
+use deno_core::convert::Number;
+use deno_core::convert::RangeObject;
+use deno_core::convert::SafeNumber;
+use deno_core::convert::Smi;
+use deno_core::convert::TextSpan;
+use deno_core::serde_json;
+use deno_core::v8;
+use deno_core::JsRuntime;
+use libfuzzer_sys::fuzz_target;
+use smallvec::SmallVec;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Values nested deeper than this are written as `undefined`, so that the generated
+/// source can't overflow the parser's stack.
+const MAX_DEPTH: usize = 32;
+
+/// A JS value, written out as JS source so that values only JS can create, such as
+/// holes, proxies and throwing getters, are covered too.
+#[derive(Arbitrary, Debug)]
+enum Value {
+  Undefined,
+  Null,
+  Bool(bool),
+  Int(i32),
+  Number(f64),
+  BigInt(i64),
+  String(String),
+  Symbol(String),
+  Array(Vec<Value>),
+  /// An array where `None` elements are holes.
+  Holey(Vec<Option<Value>>),
+  /// An array with extra properties, or with an overridden `length`.
+  ArrayWith(Vec<Value>, Vec<(Key, Value)>),
+  Object(Vec<(Key, Value)>),
+  /// An object whose property throws when it is read.
+  ThrowingGetter(Key),
+  /// A proxy that forwards to its target.
+  Proxy(Box<Value>),
+  /// A proxy whose traps all throw.
+  ThrowingProxy(Box<Value>),
+  TypedArray(TypedArrayKind, Vec<u8>),
+  /// A typed array whose buffer has been detached.
+  Detached(TypedArrayKind),
+  Map(Vec<(Value, Value)>),
+  Set(Vec<Value>),
+  Date(f64),
+  Function,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Key {
+  Name(String),
+  Index(u32),
+  Symbol(String),
+  Length,
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum TypedArrayKind {
+  Uint8,
+  Int8,
+  Uint16,
+  Int32,
+  Uint32,
+  Float32,
+  Float64,
+  BigInt64,
+}
+
+impl TypedArrayKind {
+  fn name(self) -> &'static str {
+    match self {
+      Self::Uint8 => "Uint8Array",
+      Self::Int8 => "Int8Array",
+      Self::Uint16 => "Uint16Array",
+      Self::Int32 => "Int32Array",
+      Self::Uint32 => "Uint32Array",
+      Self::Float32 => "Float32Array",
+      Self::Float64 => "Float64Array",
+      Self::BigInt64 => "BigInt64Array",
+    }
+  }
+}
+
+impl Value {
+  fn write(&self, out: &mut String, depth: usize) {
+    if depth > MAX_DEPTH {
+      out.push_str("undefined");
+      return;
+    }
+    let depth = depth + 1;
+    match self {
+      Value::Undefined => out.push_str("undefined"),
+      Value::Null => out.push_str("null"),
+      Value::Bool(b) => write!(out, "{b}").unwrap(),
+      Value::Int(i) => write!(out, "({i})").unwrap(),
+      Value::Number(n) => write_number(out, *n),
+      Value::BigInt(i) => write!(out, "({i}n)").unwrap(),
+      Value::String(s) => write_string(out, s),
+      Value::Symbol(s) => {
+        out.push_str("Symbol.for(");
+        write_string(out, s);
+        out.push(')');
+      }
+      Value::Array(values) => write_array(out, values, depth),
+      Value::Holey(values) => {
+        out.push('[');
+        for value in values {
+          if let Some(value) = value {
+            value.write(out, depth);
+          }
+          out.push(',');
+        }
+        out.push(']');
+      }
+      Value::ArrayWith(values, props) => {
+        out.push_str("Object.assign(");
+        write_array(out, values, depth);
+        out.push_str(", ");
+        write_object(out, props, depth);
+        out.push(')');
+      }
+      Value::Object(props) => write_object(out, props, depth),
+      Value::ThrowingGetter(key) => {
+        out.push_str("{ get ");
+        key.write(out);
+        out.push_str("() { throw new Error('getter'); } }");
+      }
+      Value::Proxy(target) => {
+        out.push_str("new Proxy(Object(");
+        target.write(out, depth);
+        out.push_str("), {})");
+      }
+      Value::ThrowingProxy(target) => {
+        out.push_str("new Proxy(Object(");
+        target.write(out, depth);
+        out.push_str(
+          "), new Proxy({}, { get() { return () => { throw new Error('trap'); }; } }))",
+        );
+      }
+      Value::TypedArray(kind, bytes) => {
+        // Round down to a whole number of elements
+        write!(
+          out,
+          "(() => {{ const bytes = new Uint8Array({bytes:?}); \
+           return new {name}(bytes.buffer, 0, Math.floor(bytes.length / {name}.BYTES_PER_ELEMENT)); }})()",
+          name = kind.name(),
+        )
+        .unwrap();
+      }
+      Value::Detached(kind) => {
+        write!(
+          out,
+          "(() => {{ const array = new {}(8); array.buffer.transfer(); return array; }})()",
+          kind.name()
+        )
+        .unwrap();
+      }
+      Value::Map(entries) => {
+        out.push_str("new Map([");
+        for (key, value) in entries {
+          out.push('[');
+          key.write(out, depth);
+          out.push(',');
+          value.write(out, depth);
+          out.push_str("],");
+        }
+        out.push_str("])");
+      }
+      Value::Set(values) => {
+        out.push_str("new Set(");
+        write_array(out, values, depth);
+        out.push(')');
+      }
+      Value::Date(time) => {
+        out.push_str("new Date(");
+        write_number(out, *time);
+        out.push(')');
+      }
+      Value::Function => out.push_str("(() => {})"),
+    }
+  }
+}
+
+impl Key {
+  /// Writes the key as it appears in an object literal.
+  fn write(&self, out: &mut String) {
+    match self {
+      Key::Name(name) => write_string(out, name),
+      Key::Index(index) => write!(out, "{index}").unwrap(),
+      Key::Symbol(name) => {
+        out.push_str("[Symbol.for(");
+        write_string(out, name);
+        out.push_str(")]");
+      }
+      Key::Length => out.push_str("length"),
+    }
+  }
+}
+
+fn write_array(out: &mut String, values: &[Value], depth: usize) {
+  out.push('[');
+  for value in values {
+    value.write(out, depth);
+    out.push(',');
+  }
+  out.push(']');
+}
+
+fn write_object(out: &mut String, props: &[(Key, Value)], depth: usize) {
+  out.push('{');
+  for (key, value) in props {
+    key.write(out);
+    out.push(':');
+    value.write(out, depth);
+    out.push(',');
+  }
+  out.push('}');
+}
+
+fn write_number(out: &mut String, n: f64) {
+  if n.is_nan() {
+    out.push_str("NaN");
+  } else if n.is_infinite() {
+    out.push_str(if n > 0.0 { "Infinity" } else { "-Infinity" });
+  } else {
+    write!(out, "({n:?})").unwrap();
+  }
+}
+
+fn write_string(out: &mut String, s: &str) {
+  out.push('"');
+  for c in s.chars() {
+    if c.is_ascii_alphanumeric() || c == ' ' {
+      out.push(c);
+    } else {
+      write!(out, "\\u{{{:x}}}", c as u32).unwrap();
+    }
+  }
+  out.push('"');
+}
+
+thread_local! {
+  static RUNTIME: RefCell<JsRuntime> = RefCell::new(JsRuntime::new(Default::default()));
+  static LIVE: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts the values that are alive, so that a conversion that leaks or drops twice the
+/// elements it already converted is caught when it fails partway through.
+struct Tracked<T>(#[allow(dead_code)] T);
+
+impl<'a, T: FromV8<'a>> FromV8<'a> for Tracked<T> {
+  type Error = T::Error;
+  fn from_v8(
+    scope: &mut v8::HandleScope<'a>,
+    value: v8::Local<'a, v8::Value>,
+  ) -> Result<Self, Self::Error> {
+    let value = T::from_v8(scope, value)?;
+    LIVE.with(|live| live.set(live.get() + 1));
+    Ok(Tracked(value))
+  }
+}
+
+impl<T> Drop for Tracked<T> {
+  fn drop(&mut self) {
+    LIVE.with(|live| {
+      let count = live.get();
+      assert!(count > 0, "a converted value was dropped twice");
+      live.set(count - 1);
+    });
+  }
+}
+
+/// Converts `value` as a `T`, checking that it either succeeds or fails with an error
+/// message, and that every value it converted is dropped exactly once.
+fn check<'a, T: FromV8<'a>>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) {
+  let scope = &mut v8::TryCatch::new(scope);
+  match T::from_v8(scope, value) {
+    Ok(converted) => drop(converted),
+    Err(err) => assert!(
+      !err.to_string().is_empty(),
+      "empty error converting to {}",
+      std::any::type_name::<T>()
+    ),
+  }
+  assert_eq!(
+    LIVE.with(Cell::get),
+    0,
+    "converting to {} leaked values",
+    std::any::type_name::<T>()
+  );
+  scope.reset();
+}
+
+fn check_all<'a>(
+  scope: &mut v8::HandleScope<'a>,
+  value: v8::Local<'a, v8::Value>,
+) {
+  // Scalars
+  check::<bool>(scope, value);
+  check::<String>(scope, value);
+  check::<char>(scope, value);
+  check::<Smi<u32>>(scope, value);
+  check::<CheckedSmi<i32>>(scope, value);
+  check::<Number<f64>>(scope, value);
+  check::<SafeNumber<u64>>(scope, value);
+  check::<BigInt<i64>>(scope, value);
+  check::<Clamp<u8, 0, 100>>(scope, value);
+  check::<Option<Tracked<String>>>(scope, value);
+  check::<Either<Tracked<Smi<u32>>, Tracked<String>>>(scope, value);
+
+  // Arrays, which fill their elements in place
+  check::<Vec<Tracked<String>>>(scope, value);
+  check::<Vec<Tracked<Smi<u32>>>>(scope, value);
+  check::<Vec<Vec<Tracked<String>>>>(scope, value);
+  check::<Vec<Option<Tracked<String>>>>(scope, value);
+  check::<[Tracked<String>; 3]>(scope, value);
+  check::<Box<[Tracked<String>]>>(scope, value);
+  check::<BoundedVec<Tracked<String>, 4>>(scope, value);
+  check::<SmallVec<[Tracked<String>; 2]>>(scope, value);
+  check::<(Tracked<String>, Tracked<Smi<u32>>)>(scope, value);
+  check::<Vec<u8>>(scope, value);
+  check::<Vec<u32>>(scope, value);
+  check::<Vec<f64>>(scope, value);
+
+  // Objects and collections
+  check::<HashMap<String, Tracked<String>>>(scope, value);
+  check::<RangeObject<Range<Smi<u32>>>>(scope, value);
+  check::<TextSpan>(scope, value);
+  check::<serde_json::Value>(scope, value);
+}
+
+fuzz_target!(|value: Value| {
+  // Parenthesized so that an object literal isn't parsed as a block
+  let mut source = String::from("(");
+  value.write(&mut source, 0);
+  source.push(')');
+  RUNTIME.with(|runtime| {
+    let mut runtime = runtime.borrow_mut();
+    // The generated source may throw, as for a detached buffer that can't be created
+    let Ok(value) = runtime.execute_script("[fuzz]", source) else {
+      return;
+    };
+    let scope = &mut runtime.handle_scope();
+    let value = v8::Local::new(scope, value);
+    check_all(scope, value);
+  });
+});
@@ -19,6 +19,18 @@ mod tests {
     t.pass(input);
   }
 
+  #[testing_macros::fixture("../conversion/test_cases/*.rs")]
+  fn compile_test_conversion(input: PathBuf) {
+    let t = trybuild::TestCases::new();
+    t.pass(input);
+  }
+
+  #[testing_macros::fixture("../conversion/test_cases_fail/*.rs")]
+  fn compile_test_conversion_fail(input: PathBuf) {
+    let t = trybuild::TestCases::new();
+    t.compile_fail(input);
+  }
+
   #[rustversion::nightly]
   #[test]
   fn compile_test_all() {
@@ -0,0 +1,324 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+use super::conversion_lifetime;
+use super::converted_fields;
+use super::discriminant_checks;
+use super::is_double_option;
+use super::is_maybe;
+use super::is_option;
+use super::symbol_key;
+use super::ConvertedData;
+use super::ConvertedField;
+use super::ConvertedStruct;
+use super::VariantShape;
+use proc_macro2::Literal;
+use proc_macro2::TokenStream;
+use quote::quote;
+use quote::quote_spanned;
+use syn::spanned::Spanned;
+use syn::Error;
+
+/// Generates a `FromV8` implementation for a struct or enum.
+///
+/// By default struct fields are read, in declaration order, from a JS array of exactly
+/// the right length. With `#[v8(object)]` they are instead read from the properties
+/// of an object, where a missing property is only allowed for `Option` fields.
+/// Skipped fields, including `PhantomData` markers, are initialized with
+/// `Default::default()`.
+///
+/// A `#[v8(flatten)]` field is read from the same object as the struct, so its fields
+/// sit alongside the struct's own.
+///
+/// `Option<Option<T>>` fields tell an absent value from `null`: `undefined` or a missing
+/// property is `None`, and `null` is `Some(None)`. A missing property is also allowed for
+/// `Maybe` fields, and is read as `Maybe::Undefined`.
+///
+/// Enums are read from the same representations that `#[derive(ToV8)]` produces: a
+/// bare string for unit variants, and otherwise either a `[tag, payload]` array or an
+/// object with a `#[v8(tag = "...")]` property. Unknown tags are an error.
+/// `#[v8(discriminant)]` enums are read from a smi, which must equal the discriminant
+/// of one of the variants.
+pub(crate) fn from_v8(item: TokenStream) -> Result<TokenStream, Error> {
+  let parsed = ConvertedStruct::parse(item, "FromV8")?;
+  let ident = &parsed.input.ident;
+  let lifetime = conversion_lifetime();
+  let (impl_generics, ty_generics, where_clause) =
+    parsed.split_generics("FromV8");
+
+  let body = match &parsed.data {
+    ConvertedData::Struct(fields) => {
+      let (read, initializers) = if parsed.attrs.object {
+        let names = object_names(None, [&fields[..]]);
+        let keys = object_keys(&names);
+        (
+          quote! {
+            let object = ::deno_core::_ops::field_object(value)?;
+            #keys
+          },
+          from_object(fields, &names),
+        )
+      } else {
+        let count = converted_fields(fields).count();
+        (
+          quote!(let array = ::deno_core::_ops::field_array(value, #count)?;),
+          from_array(fields),
+        )
+      };
+      quote! {
+        #read
+        Ok(Self {
+          #(#initializers),*
+        })
+      }
+    }
+    ConvertedData::Enum(variants) if parsed.attrs.discriminant => {
+      let checks = discriminant_checks(ident, variants);
+      let variant_idents = variants.iter().map(|variant| &variant.ident);
+      let names = variants.iter().map(|variant| &variant.name);
+      let valid_idents = variant_idents.clone();
+      quote! {
+        #checks
+        let discriminant = ::deno_core::_ops::discriminant_from_v8(scope, value)?;
+        #(
+          if discriminant == Self::#variant_idents as i32 {
+            return Ok(Self::#variant_idents);
+          }
+        )*
+        Err(::deno_core::_ops::unknown_discriminant(discriminant, &[#((#names, Self::#valid_idents as i32)),*]))
+      }
+    }
+    ConvertedData::Enum(variants) => {
+      let unit_arms = variants
+        .iter()
+        .filter(|variant| variant.shape == VariantShape::Unit)
+        .map(|variant| {
+          let variant_ident = &variant.ident;
+          let name = &variant.name;
+          quote!(#name => Ok(Self::#variant_ident {}),)
+        });
+      let unit_match = quote! {
+        let tag = ::deno_core::_ops::variant_tag(scope, value)?;
+        match tag.as_str() {
+          #(#unit_arms)*
+          _ => Err(::deno_core::_ops::unknown_variant(&tag)),
+        }
+      };
+
+      let mut variants = variants
+        .iter()
+        .filter(|variant| variant.shape != VariantShape::Unit)
+        .peekable();
+      if variants.peek().is_none() {
+        unit_match
+      } else {
+        // One set of keys covers the tag and the fields of every variant
+        let names = parsed.attrs.tag.as_ref().map(|key| {
+          object_names(
+            Some(key),
+            variants.clone().map(|variant| &variant.fields[..]),
+          )
+        });
+        let read = if let Some(names) = &names {
+          let keys = object_keys(names);
+          let tag = &names[0];
+          quote! {
+            let object = ::deno_core::_ops::field_object(value)?;
+            #keys
+            let tag = ::deno_core::_ops::field_property_at(scope, object, &keys, 0, #tag, false)?;
+            let tag = ::deno_core::_ops::variant_tag(scope, tag)?;
+          }
+        } else {
+          quote! {
+            let array = ::deno_core::_ops::field_array(value, 2)?;
+            let tag = ::deno_core::_ops::field_element(scope, array, 0, "tag")?;
+            let tag = ::deno_core::_ops::variant_tag(scope, tag)?;
+            let payload = ::deno_core::_ops::field_element(scope, array, 1, "payload")?;
+          }
+        };
+        let arms = variants.map(|variant| {
+          let variant_ident = &variant.ident;
+          let name = &variant.name;
+          let construct = match (variant.shape, &names) {
+            (_, Some(names)) => {
+              let initializers = from_object(&variant.fields, names);
+              quote!(Ok(Self::#variant_ident { #(#initializers),* }))
+            }
+            (VariantShape::Newtype, None) => {
+              let initializer = from_value(&variant.fields[0], quote!(payload));
+              quote!(Ok(Self::#variant_ident { #initializer }))
+            }
+            (_, None) => {
+              let count = converted_fields(&variant.fields).count();
+              let initializers = from_array(&variant.fields);
+              quote! {{
+                let array = ::deno_core::_ops::field_array(payload, #count)?;
+                Ok(Self::#variant_ident { #(#initializers),* })
+              }}
+            }
+          };
+          quote!(#name => #construct,)
+        });
+        quote! {
+          if value.is_string() {
+            #unit_match
+          } else {
+            #read
+            match tag.as_str() {
+              #(#arms)*
+              _ => Err(::deno_core::_ops::unknown_variant(&tag)),
+            }
+          }
+        }
+      }
+    }
+  };
+
+  Ok(quote! {
+    impl #impl_generics ::deno_core::convert::FromV8<#lifetime> for #ident #ty_generics #where_clause {
+      type Error = ::deno_core::error::StdAnyError;
+
+      fn from_v8(
+        scope: &mut ::deno_core::v8::HandleScope<#lifetime>,
+        value: ::deno_core::v8::Local<#lifetime, ::deno_core::v8::Value>,
+      ) -> Result<Self, Self::Error> {
+        #body
+      }
+    }
+  })
+}
+
+/// A field initializer converting the value produced by `get`, returning early
+/// with an error that names the field.
+fn from_value(field: &ConvertedField, get: TokenStream) -> TokenStream {
+  let member = &field.member;
+  let name = &field.name;
+  // Point errors about missing `FromV8` impls at the field type
+  let from_v8 =
+    quote_spanned!(field.ty.span()=> ::deno_core::convert::FromV8::from_v8);
+  if is_double_option(&field.ty) {
+    // `Option<T>` already reads `undefined` and `null` as `None`, so check for an absent
+    // value first to keep `null` as `Some(None)`
+    return quote! {
+      #member: {
+        let value = #get;
+        if value.is_undefined() {
+          None
+        } else {
+          match #from_v8(scope, value) {
+            Ok(value) => Some(value),
+            Err(err) => return Err(::deno_core::_ops::field_error(#name, err)),
+          }
+        }
+      }
+    };
+  }
+  quote! {
+    #member: {
+      let value = #get;
+      match #from_v8(scope, value) {
+        Ok(value) => value,
+        Err(err) => return Err(::deno_core::_ops::field_error(#name, err)),
+      }
+    }
+  }
+}
+
+/// A skipped field initializer.
+fn skipped(field: &ConvertedField) -> TokenStream {
+  let member = &field.member;
+  quote!(#member: ::std::default::Default::default())
+}
+
+/// A `#[v8(flatten)]` field initializer, reading the field from the same object as its
+/// parent.
+fn flattened(field: &ConvertedField) -> TokenStream {
+  let member = &field.member;
+  let from_v8 =
+    quote_spanned!(field.ty.span()=> ::deno_core::convert::FromV8::from_v8);
+  quote! {
+    #member: match #from_v8(scope, value) {
+      Ok(value) => value,
+      Err(err) => return Err(::deno_core::_ops::flatten_error(err)),
+    }
+  }
+}
+
+/// Field initializers reading from the elements of `array`.
+fn from_array(fields: &[ConvertedField]) -> Vec<TokenStream> {
+  let mut index = 0u32;
+  fields
+    .iter()
+    .map(|field| {
+      if field.attrs.skip {
+        return skipped(field);
+      }
+      let name = &field.name;
+      let get =
+        quote!(::deno_core::_ops::field_element(scope, array, #index, #name)?);
+      index += 1;
+      from_value(field, get)
+    })
+    .collect()
+}
+
+/// The string keys that the fields of one or more `#[v8(object)]` field lists are read
+/// from, after the tag if there is one, without duplicates.
+fn object_names<'f>(
+  tag: Option<&String>,
+  field_lists: impl IntoIterator<Item = &'f [ConvertedField]>,
+) -> Vec<String> {
+  let mut names: Vec<String> = tag.cloned().into_iter().collect();
+  for fields in field_lists {
+    for field in converted_fields(fields) {
+      if symbol_key(field).is_none()
+        && field.attrs.flatten.is_none()
+        && !names.contains(&field.name)
+      {
+        names.push(field.name.clone());
+      }
+    }
+  }
+  names
+}
+
+/// The keys for `names`, as a static `ObjectKeys` so that they are only created once per
+/// isolate, and their strings in this isolate as `keys`.
+fn object_keys(names: &[String]) -> TokenStream {
+  if names.is_empty() {
+    return quote!();
+  }
+  quote! {
+    static KEYS: ::deno_core::convert::ObjectKeys =
+      ::deno_core::convert::ObjectKeys::new(&[#(#names),*]);
+    let keys = ::deno_core::_ops::field_keys(scope, &KEYS)?;
+  }
+}
+
+/// Field initializers reading from the properties of `object`, with the keys in `keys`
+/// that [`object_keys`] created for `names`.
+fn from_object(
+  fields: &[ConvertedField],
+  names: &[String],
+) -> Vec<TokenStream> {
+  fields
+    .iter()
+    .map(|field| {
+      if field.attrs.skip {
+        return skipped(field);
+      }
+      if field.attrs.flatten.is_some() {
+        return flattened(field);
+      }
+      let name = &field.name;
+      let optional = is_option(&field.ty) || is_maybe(&field.ty);
+      let get = match symbol_key(field) {
+        Some(key) => quote!(::deno_core::_ops::field_symbol_property(scope, object, #key, #name, #optional)?),
+        None => {
+          let index = names.iter().position(|n| n == name).unwrap();
+          let index = Literal::usize_unsuffixed(index);
+          quote!(::deno_core::_ops::field_property_at(scope, object, &keys, #index, #name, #optional)?)
+        }
+      };
+      from_value(field, get)
+    })
+    .collect()
+}
@@ -0,0 +1,680 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+
+//! Derive macros for the `ToV8` and `FromV8` conversion traits in `deno_core::convert`.
+
+use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use quote::format_ident;
+use quote::quote;
+use quote::ToTokens;
+use syn::parse_quote;
+use syn::spanned::Spanned;
+use syn::Attribute;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Error;
+use syn::Fields;
+use syn::GenericParam;
+use syn::Ident;
+use syn::Member;
+use syn::Type;
+
+pub mod from_v8;
+pub mod to_v8;
+
+/// Options applied to the whole struct or enum via `#[v8(...)]`.
+#[derive(Default)]
+struct ContainerAttributes {
+  /// Serialize a struct as an object keyed by field name, rather than an array.
+  object: bool,
+  /// Serialize an enum as an object with the variant name stored under this key,
+  /// rather than a `[tag, payload]` array.
+  tag: Option<String>,
+  /// Serialize a fieldless enum as its numeric discriminant, rather than a string.
+  discriminant: bool,
+  /// Implement `EnumConstants` for a `#[v8(discriminant)]` enum, to install its
+  /// variants in JS as an object of named constants.
+  constants: bool,
+  /// Rename every named field that doesn't have its own `#[v8(rename)]` by this
+  /// casing policy.
+  rename_all: Option<RenameRule>,
+}
+
+impl ContainerAttributes {
+  fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+    let mut res = Self::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("v8")) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("object") {
+          res.object = true;
+          Ok(())
+        } else if meta.path.is_ident("tag") {
+          let tag: syn::LitStr = meta.value()?.parse()?;
+          res.tag = Some(tag.value());
+          Ok(())
+        } else if meta.path.is_ident("discriminant") {
+          res.discriminant = true;
+          Ok(())
+        } else if meta.path.is_ident("constants") {
+          res.constants = true;
+          Ok(())
+        } else if meta.path.is_ident("rename_all") {
+          let rule: syn::LitStr = meta.value()?.parse()?;
+          res.rename_all = Some(RenameRule::parse(&rule)?);
+          Ok(())
+        } else {
+          Err(meta.error("unknown #[v8] container attribute"))
+        }
+      })?;
+    }
+    Ok(res)
+  }
+}
+
+/// A casing policy for `#[v8(rename_all = "...")]`, which converts a field's Rust name,
+/// assumed to be `snake_case`, to its JS key.
+#[derive(Clone, Copy)]
+enum RenameRule {
+  Camel,
+  Snake,
+  Pascal,
+  Kebab,
+}
+
+impl RenameRule {
+  fn parse(rule: &syn::LitStr) -> Result<Self, Error> {
+    match rule.value().as_str() {
+      "camelCase" => Ok(Self::Camel),
+      "snake_case" => Ok(Self::Snake),
+      "PascalCase" => Ok(Self::Pascal),
+      "kebab-case" => Ok(Self::Kebab),
+      _ => Err(Error::new_spanned(
+        rule,
+        "unknown #[v8(rename_all)] rule, expected one of \"camelCase\", \"snake_case\", \"PascalCase\" or \"kebab-case\"",
+      )),
+    }
+  }
+
+  fn apply(self, name: &str) -> String {
+    match self {
+      Self::Snake => name.to_owned(),
+      Self::Kebab => name.replace('_', "-"),
+      Self::Pascal | Self::Camel => {
+        let mut res = String::with_capacity(name.len());
+        let mut capitalize = matches!(self, Self::Pascal);
+        for c in name.chars() {
+          if c == '_' {
+            capitalize = true;
+          } else if capitalize {
+            res.extend(c.to_uppercase());
+            capitalize = false;
+          } else {
+            res.push(c);
+          }
+        }
+        res
+      }
+    }
+  }
+}
+
+/// Options applied to a single field or variant via `#[v8(...)]`.
+#[derive(Default)]
+struct FieldAttributes {
+  /// Leave this field out of the JS representation.
+  skip: bool,
+  /// The name to use for this field or variant in JS, if different from the Rust name.
+  rename: Option<String>,
+  /// Store this object field under a symbol rather than its name: either a string
+  /// literal for a registered symbol, or an expression evaluating to a `SymbolKey`.
+  symbol: Option<syn::Expr>,
+  /// Merge the properties of this field's object into the parent object, rather than
+  /// nesting them under a key.
+  flatten: Option<Span>,
+}
+
+impl FieldAttributes {
+  fn parse(attrs: &[Attribute], allow_skip: bool) -> Result<Self, Error> {
+    let mut res = Self::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("v8")) {
+      attr.parse_nested_meta(|meta| {
+        if allow_skip && meta.path.is_ident("skip") {
+          res.skip = true;
+          Ok(())
+        } else if meta.path.is_ident("rename") {
+          let name: syn::LitStr = meta.value()?.parse()?;
+          res.rename = Some(name.value());
+          Ok(())
+        } else if allow_skip && meta.path.is_ident("symbol") {
+          res.symbol = Some(meta.value()?.parse()?);
+          Ok(())
+        } else if allow_skip && meta.path.is_ident("flatten") {
+          res.flatten = Some(meta.path.span());
+          Ok(())
+        } else if allow_skip {
+          Err(meta.error("unknown #[v8] field attribute"))
+        } else {
+          Err(meta.error("unknown #[v8] variant attribute"))
+        }
+      })?;
+    }
+    if let (Some(symbol), Some(_)) = (&res.symbol, &res.rename) {
+      return Err(Error::new_spanned(
+        symbol,
+        "#[v8(symbol)] and #[v8(rename)] cannot be combined",
+      ));
+    }
+    if let Some(flatten) = res.flatten {
+      let other = if res.skip {
+        Some("skip")
+      } else if res.rename.is_some() {
+        Some("rename")
+      } else if res.symbol.is_some() {
+        Some("symbol")
+      } else {
+        None
+      };
+      if let Some(other) = other {
+        return Err(Error::new(
+          flatten,
+          format!("#[v8(flatten)] and #[v8({other})] cannot be combined"),
+        ));
+      }
+    }
+    Ok(res)
+  }
+}
+
+/// A struct or variant field that takes part in the conversion.
+struct ConvertedField {
+  /// How to access the field, ie: `Self { #member: .. }`.
+  member: Member,
+  /// The name used in error messages and as the key in object mode.
+  name: String,
+  /// The local the field is bound to when destructuring.
+  binding: Ident,
+  ty: Type,
+  attrs: FieldAttributes,
+}
+
+impl ConvertedField {
+  fn parse_all(
+    fields: &Fields,
+    rename_all: Option<RenameRule>,
+  ) -> Result<Vec<Self>, Error> {
+    let mut res = vec![];
+    for (index, field) in fields.iter().enumerate() {
+      let (member, name) = match &field.ident {
+        Some(ident) => {
+          let name = unraw(ident);
+          let name = match rename_all {
+            Some(rule) => rule.apply(&name),
+            None => name,
+          };
+          (Member::Named(ident.clone()), name)
+        }
+        None => (Member::Unnamed(index.into()), index.to_string()),
+      };
+      let mut attrs = FieldAttributes::parse(&field.attrs, true)?;
+      // Markers have no value to convert, and `PhantomData` is always `Default`
+      if is_phantom_data(&field.ty) {
+        attrs.skip = true;
+      }
+      let name = attrs.rename.clone().unwrap_or(name);
+      res.push(ConvertedField {
+        member,
+        name,
+        binding: format_ident!("field{index}"),
+        ty: field.ty.clone(),
+        attrs,
+      });
+    }
+    Ok(res)
+  }
+}
+
+/// An enum variant that takes part in the conversion.
+struct ConvertedVariant {
+  ident: Ident,
+  /// The tag identifying this variant in JS.
+  name: String,
+  shape: VariantShape,
+  fields: Vec<ConvertedField>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VariantShape {
+  /// Converted as a bare string tag.
+  Unit,
+  /// A single unnamed field, converted as the payload directly.
+  Newtype,
+  /// Any other fields, converted like a struct.
+  Fields,
+}
+
+/// The body of the type we are deriving conversions for.
+enum ConvertedData {
+  Struct(Vec<ConvertedField>),
+  Enum(Vec<ConvertedVariant>),
+}
+
+/// The parsed shape of a struct or enum we are deriving conversions for.
+struct ConvertedStruct {
+  input: DeriveInput,
+  attrs: ContainerAttributes,
+  data: ConvertedData,
+}
+
+impl ConvertedStruct {
+  fn parse(item: TokenStream, derive: &str) -> Result<Self, Error> {
+    let input = syn::parse2::<DeriveInput>(item)?;
+    let attrs = ContainerAttributes::parse(&input.attrs)?;
+    if attrs.constants && !attrs.discriminant {
+      return Err(Error::new(
+        input.ident.span(),
+        "#[v8(constants)] requires #[v8(discriminant)]",
+      ));
+    }
+    let data = match &input.data {
+      Data::Struct(data) => {
+        if attrs.tag.is_some() {
+          return Err(Error::new(
+            input.ident.span(),
+            "#[v8(tag)] can only be used on enums",
+          ));
+        }
+        if attrs.discriminant {
+          return Err(Error::new(
+            input.ident.span(),
+            "#[v8(discriminant)] can only be used on enums",
+          ));
+        }
+        if attrs.object && !matches!(data.fields, Fields::Named(_)) {
+          return Err(Error::new(
+            input.ident.span(),
+            "#[v8(object)] requires a struct with named fields",
+          ));
+        }
+        if attrs.rename_all.is_some() && !attrs.object {
+          return Err(Error::new(
+            input.ident.span(),
+            "#[v8(rename_all)] requires #[v8(object)] on structs or #[v8(tag)] on enums",
+          ));
+        }
+        let fields = ConvertedField::parse_all(&data.fields, attrs.rename_all)?;
+        if attrs.object {
+          reject_duplicate_keys(&fields, None)?;
+        } else {
+          reject_object_attributes(&fields)?;
+        }
+        ConvertedData::Struct(fields)
+      }
+      Data::Enum(data) => {
+        if attrs.object {
+          return Err(Error::new(
+            input.ident.span(),
+            "#[v8(object)] can only be used on structs, use #[v8(tag = \"...\")] for enums",
+          ));
+        }
+        if attrs.discriminant && attrs.tag.is_some() {
+          return Err(Error::new(
+            input.ident.span(),
+            "#[v8(tag)] and #[v8(discriminant)] cannot be combined",
+          ));
+        }
+        if attrs.rename_all.is_some() && attrs.tag.is_none() {
+          return Err(Error::new(
+            input.ident.span(),
+            "#[v8(rename_all)] requires #[v8(object)] on structs or #[v8(tag)] on enums",
+          ));
+        }
+        let mut variants = vec![];
+        for variant in &data.variants {
+          let variant_attrs = FieldAttributes::parse(&variant.attrs, false)?;
+          let fields =
+            ConvertedField::parse_all(&variant.fields, attrs.rename_all)?;
+          match &attrs.tag {
+            Some(tag) => reject_duplicate_keys(&fields, Some(tag))?,
+            None => reject_object_attributes(&fields)?,
+          }
+          let shape = match &variant.fields {
+            Fields::Unit => VariantShape::Unit,
+            Fields::Unnamed(_)
+              if fields.len() == 1 && !fields[0].attrs.skip =>
+            {
+              VariantShape::Newtype
+            }
+            _ => VariantShape::Fields,
+          };
+          if attrs.discriminant && shape != VariantShape::Unit {
+            return Err(Error::new(
+              variant.ident.span(),
+              "#[v8(discriminant)] requires an enum with only unit variants",
+            ));
+          }
+          if attrs.tag.is_some()
+            && shape != VariantShape::Unit
+            && !matches!(variant.fields, Fields::Named(_))
+          {
+            return Err(Error::new(
+              variant.ident.span(),
+              "#[v8(tag)] requires unit variants or variants with named fields",
+            ));
+          }
+          variants.push(ConvertedVariant {
+            name: variant_attrs
+              .rename
+              .unwrap_or_else(|| unraw(&variant.ident)),
+            ident: variant.ident.clone(),
+            shape,
+            fields,
+          });
+        }
+        ConvertedData::Enum(variants)
+      }
+      Data::Union(_) => {
+        return Err(Error::new(
+          input.ident.span(),
+          format!("{derive} can only be derived for structs and enums"),
+        ));
+      }
+    };
+
+    Ok(Self { input, attrs, data })
+  }
+
+  /// Splits the type's generics for an impl of `trait_name`, adding the
+  /// conversion lifetime and requiring every type parameter used by a converted field
+  /// to implement the trait.
+  ///
+  /// Parameters only used by skipped fields, such as `PhantomData<T>` markers, aren't
+  /// required to implement it. For `FromV8`, the types of skipped fields that use a
+  /// type parameter are required to implement `Default` instead.
+  fn split_generics(
+    &self,
+    trait_name: &str,
+  ) -> (TokenStream, TokenStream, TokenStream) {
+    let lifetime = conversion_lifetime();
+    let from_v8 = trait_name == "FromV8";
+    let trait_name = format_ident!("{trait_name}");
+    let mut generics = self.input.generics.clone();
+    let type_params = generics
+      .type_params()
+      .map(|param| param.ident.clone())
+      .collect::<Vec<_>>();
+    let fields = match &self.data {
+      ConvertedData::Struct(fields) => fields.iter().collect::<Vec<_>>(),
+      ConvertedData::Enum(variants) => variants
+        .iter()
+        .flat_map(|variant| &variant.fields)
+        .collect(),
+    };
+    let (skipped, converted): (Vec<_>, Vec<_>) =
+      fields.into_iter().partition(|field| field.attrs.skip);
+    let where_clause = generics.make_where_clause();
+    for param in &type_params {
+      if converted.iter().any(|field| mentions(&field.ty, param)) {
+        where_clause.predicates.push(
+          parse_quote!(#param: ::deno_core::convert::#trait_name<#lifetime>),
+        );
+      }
+    }
+    if from_v8 {
+      for field in skipped {
+        let ty = &field.ty;
+        if !is_phantom_data(ty)
+          && type_params.iter().any(|param| mentions(ty, param))
+        {
+          where_clause
+            .predicates
+            .push(parse_quote!(#ty: ::std::default::Default));
+        }
+      }
+    }
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    let mut impl_generics = generics.clone();
+    impl_generics
+      .params
+      .insert(0, GenericParam::Lifetime(parse_quote!(#lifetime)));
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    (
+      impl_generics.into_token_stream(),
+      ty_generics.into_token_stream(),
+      where_clause.into_token_stream(),
+    )
+  }
+}
+
+/// Errors on `#[v8(symbol)]` and `#[v8(flatten)]` fields, for fields that aren't
+/// converted to object properties.
+fn reject_object_attributes(fields: &[ConvertedField]) -> Result<(), Error> {
+  if let Some(symbol) =
+    fields.iter().find_map(|field| field.attrs.symbol.as_ref())
+  {
+    return Err(Error::new_spanned(
+      symbol,
+      "#[v8(symbol)] requires #[v8(object)] on structs or #[v8(tag)] on enums",
+    ));
+  }
+  if let Some(flatten) = fields.iter().find_map(|field| field.attrs.flatten) {
+    return Err(Error::new(
+      flatten,
+      "#[v8(flatten)] requires #[v8(object)] on structs or #[v8(tag)] on enums",
+    ));
+  }
+  Ok(())
+}
+
+/// Errors on two object fields with the same string key, or a field with the same key
+/// as the enum tag. Collisions with the keys of flattened fields are checked by the
+/// generated code instead, since they depend on other types.
+fn reject_duplicate_keys(
+  fields: &[ConvertedField],
+  tag: Option<&String>,
+) -> Result<(), Error> {
+  let mut keys = tag.into_iter().collect::<Vec<_>>();
+  for field in converted_fields(fields) {
+    if field.attrs.symbol.is_some() || field.attrs.flatten.is_some() {
+      continue;
+    }
+    if keys.contains(&&field.name) {
+      return Err(Error::new_spanned(
+        &field.member,
+        format!("duplicate object key \"{}\"", field.name),
+      ));
+    }
+    keys.push(&field.name);
+  }
+  Ok(())
+}
+
+/// The `SymbolKey` for a `#[v8(symbol)]` field.
+fn symbol_key(field: &ConvertedField) -> Option<TokenStream> {
+  let symbol = field.attrs.symbol.as_ref()?;
+  Some(match symbol {
+    syn::Expr::Lit(syn::ExprLit {
+      lit: syn::Lit::Str(key),
+      ..
+    }) => quote!(::deno_core::convert::SymbolKey::Registered(#key)),
+    _ => symbol.to_token_stream(),
+  })
+}
+
+/// Compile-time checks that every discriminant of a `#[v8(discriminant)]` enum fits in a
+/// smi, since JS only sees them as `i32`s.
+fn discriminant_checks(
+  ident: &Ident,
+  variants: &[ConvertedVariant],
+) -> TokenStream {
+  let checks = variants.iter().map(|variant| {
+    let variant_ident = &variant.ident;
+    let message = format!("the discriminant of {ident}::{variant_ident} does not fit in an i32");
+    quote! {
+      assert!(#ident::#variant_ident as i128 == #ident::#variant_ident as i32 as i128, #message);
+    }
+  });
+  quote! {
+    const _: () = {
+      #(#checks)*
+    };
+  }
+}
+
+/// The fields that appear in the JS representation, in declaration order.
+fn converted_fields(
+  fields: &[ConvertedField],
+) -> impl Iterator<Item = &ConvertedField> {
+  fields.iter().filter(|field| !field.attrs.skip)
+}
+
+/// Returns the JS name for an identifier, without any `r#` prefix.
+fn unraw(ident: &Ident) -> String {
+  let name = ident.to_string();
+  name.strip_prefix("r#").unwrap_or(&name).to_owned()
+}
+
+/// Returns true if the type is syntactically an `Option<T>`.
+fn is_option(ty: &Type) -> bool {
+  let Type::Path(path) = ty else {
+    return false;
+  };
+  path
+    .path
+    .segments
+    .last()
+    .is_some_and(|segment| segment.ident == "Option")
+}
+
+/// Returns true if the type is syntactically a `Maybe<T>`.
+fn is_maybe(ty: &Type) -> bool {
+  let Type::Path(path) = ty else {
+    return false;
+  };
+  path
+    .path
+    .segments
+    .last()
+    .is_some_and(|segment| segment.ident == "Maybe")
+}
+
+/// Returns true if the type is syntactically a `PhantomData<T>`.
+fn is_phantom_data(ty: &Type) -> bool {
+  let Type::Path(path) = ty else {
+    return false;
+  };
+  path
+    .path
+    .segments
+    .last()
+    .is_some_and(|segment| segment.ident == "PhantomData")
+}
+
+/// Returns true if `ident` appears anywhere in the type, such as a type parameter in
+/// `Vec<T>`.
+fn mentions(ty: &Type, ident: &Ident) -> bool {
+  fn visit(tokens: TokenStream, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+      proc_macro2::TokenTree::Ident(i) => i == *ident,
+      proc_macro2::TokenTree::Group(group) => visit(group.stream(), ident),
+      _ => false,
+    })
+  }
+  visit(ty.to_token_stream(), ident)
+}
+
+/// Returns true if the type is syntactically an `Option<Option<T>>`.
+fn is_double_option(ty: &Type) -> bool {
+  let Type::Path(path) = ty else {
+    return false;
+  };
+  let Some(segment) = path.path.segments.last() else {
+    return false;
+  };
+  let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+    return false;
+  };
+  segment.ident == "Option"
+    && matches!(
+      args.args.first(),
+      Some(syn::GenericArgument::Type(inner)) if is_option(inner)
+    )
+}
+
+/// The lifetime used for the `ToV8<'a>`/`FromV8<'a>` trait parameter.
+fn conversion_lifetime() -> syn::Lifetime {
+  syn::Lifetime::new("'__v8", Span::call_site())
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use quote::ToTokens;
+  use std::path::PathBuf;
+  use syn::parse_str;
+  use syn::File;
+  use syn::Item;
+
+  #[test]
+  fn test_rename_rules() {
+    use super::RenameRule;
+    let cases = [
+      (RenameRule::Camel, "byte_length", "byteLength"),
+      (RenameRule::Snake, "byte_length", "byte_length"),
+      (RenameRule::Pascal, "byte_length", "ByteLength"),
+      (RenameRule::Kebab, "byte_length", "byte-length"),
+      (RenameRule::Camel, "value", "value"),
+      (RenameRule::Pascal, "value", "Value"),
+    ];
+    for (rule, name, expected) in cases {
+      assert_eq!(rule.apply(name), expected);
+    }
+  }
+
+  #[testing_macros::fixture("conversion/test_cases/*.rs")]
+  fn test_derive_output(input: PathBuf) {
+    let update_expected = std::env::var("UPDATE_EXPECTED").is_ok();
+
+    let source =
+      std::fs::read_to_string(&input).expect("Failed to read test file");
+    let file = parse_str::<File>(&source).expect("Failed to parse Rust file");
+    let mut expected_out = vec![];
+    for item in file.items {
+      let (attrs, tokens) = match item {
+        Item::Struct(item) => (item.attrs.clone(), item.to_token_stream()),
+        Item::Enum(item) => (item.attrs.clone(), item.to_token_stream()),
+        _ => continue,
+      };
+      let derives = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .map(|attr| attr.meta.to_token_stream().to_string())
+        .collect::<String>();
+      if derives.contains("FromV8") {
+        let output = super::from_v8::from_v8(tokens.clone())
+          .expect("Failed to derive FromV8");
+        let tree = syn::parse2(output).unwrap();
+        expected_out.push(prettyplease::unparse(&tree));
+      }
+      if derives.contains("ToV8") {
+        let output =
+          super::to_v8::to_v8(tokens.clone()).expect("Failed to derive ToV8");
+        let tree = syn::parse2(output).unwrap();
+        expected_out.push(prettyplease::unparse(&tree));
+      }
+    }
+
+    let expected_out = expected_out.join("\n");
+
+    if update_expected {
+      std::fs::write(input.with_extension("out"), expected_out)
+        .expect("Failed to write expectation file");
+    } else {
+      let expected = std::fs::read_to_string(input.with_extension("out"))
+        .expect("Failed to read expectation file");
+      assert_eq!(
+        expected, expected_out,
+        "Failed to match expectation. Use UPDATE_EXPECTED=1."
+      );
+    }
+  }
+}
@@ -0,0 +1,64 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+#![deny(warnings)]
+deno_ops_compile_test_runner::prelude!();
+use deno_core::convert::Smi;
+use deno_core::FromV8;
+use deno_core::ToV8;
+
+#[derive(FromV8, ToV8)]
+pub enum Array {
+  Unit,
+  Newtype(Smi<u32>),
+  Tuple(bool, #[v8(skip)] (), Smi<u8>),
+  #[v8(rename = "struct")]
+  Struct {
+    a: bool,
+  },
+}
+
+#[derive(FromV8, ToV8)]
+#[v8(tag = "type")]
+pub enum Tagged {
+  Unit,
+  Struct {
+    #[v8(rename = "camelCase")]
+    snake_case: bool,
+    optional: Option<bool>,
+  },
+  Flattened {
+    #[v8(flatten)]
+    inner: Inner,
+    extra: bool,
+  },
+}
+
+#[derive(FromV8, ToV8)]
+#[v8(object)]
+pub struct Inner {
+  pub value: bool,
+}
+
+#[derive(FromV8, ToV8)]
+pub enum UnitOnly {
+  A,
+  B,
+}
+
+#[derive(FromV8, ToV8)]
+#[v8(discriminant)]
+#[repr(i32)]
+pub enum Discriminant {
+  A = 1,
+  B = 5,
+  C,
+  D = -1,
+}
+
+#[derive(FromV8, ToV8)]
+#[v8(discriminant, constants)]
+pub enum Level {
+  Debug = 10,
+  #[v8(rename = "information")]
+  Info = 20,
+  Warn,
+}
@@ -0,0 +1,40 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+#![deny(warnings)]
+deno_ops_compile_test_runner::prelude!();
+use deno_core::convert::Maybe;
+use deno_core::convert::Smi;
+use deno_core::FromV8;
+use deno_core::ToV8;
+
+#[derive(FromV8, ToV8)]
+pub struct Tuple(Smi<u32>, #[v8(skip)] (), bool);
+
+#[derive(FromV8, ToV8)]
+#[v8(object)]
+pub struct Object<T> {
+  #[v8(rename = "camelCase")]
+  pub snake_case: T,
+  pub optional: Option<bool>,
+  pub nullable: Option<Option<bool>>,
+  pub patch: Maybe<bool>,
+  #[v8(symbol = "deno.internal")]
+  pub internal: Option<bool>,
+  #[v8(skip)]
+  pub skipped: u32,
+}
+
+#[derive(FromV8, ToV8)]
+#[v8(object)]
+pub struct Flattened {
+  pub id: Smi<u32>,
+  #[v8(flatten)]
+  pub object: Object<bool>,
+}
+
+#[derive(FromV8, ToV8)]
+pub struct Marked<T, U> {
+  pub id: Smi<u32>,
+  pub marker: std::marker::PhantomData<T>,
+  #[v8(skip)]
+  pub cache: Vec<U>,
+}
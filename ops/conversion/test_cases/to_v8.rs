@@ -0,0 +1,34 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+#![deny(warnings)]
+deno_ops_compile_test_runner::prelude!();
+use deno_core::convert::Smi;
+use deno_core::ToV8;
+
+#[derive(ToV8)]
+pub struct Tuple(Smi<u32>, bool, #[v8(skip)] ());
+
+#[derive(ToV8)]
+pub struct Named {
+  pub a: Smi<u8>,
+  #[v8(skip)]
+  pub skipped: (),
+  pub b: Option<bool>,
+}
+
+#[derive(ToV8)]
+#[v8(object)]
+pub struct Object<T> {
+  pub r#type: T,
+  pub value: bool,
+}
+
+#[derive(ToV8)]
+pub struct Unit;
+
+#[derive(ToV8)]
+#[v8(object, rename_all = "camelCase")]
+pub struct Renamed {
+  pub field_name: bool,
+  #[v8(rename = "kept_name")]
+  pub other_field: bool,
+}
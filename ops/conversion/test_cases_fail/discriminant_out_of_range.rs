@@ -0,0 +1,11 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+deno_ops_compile_test_runner::prelude!();
+use deno_core::ToV8;
+
+#[derive(ToV8)]
+#[v8(discriminant)]
+#[repr(i64)]
+pub enum Foo {
+  A = 1,
+  B = 1 << 40,
+}
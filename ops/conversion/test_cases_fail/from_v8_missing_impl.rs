@@ -0,0 +1,11 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+deno_ops_compile_test_runner::prelude!();
+use deno_core::FromV8;
+
+pub struct NotConvertible;
+
+#[derive(FromV8)]
+#[v8(object)]
+pub struct Foo {
+  pub field: NotConvertible,
+}
@@ -0,0 +1,9 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+deno_ops_compile_test_runner::prelude!();
+use deno_core::ToV8;
+
+#[derive(ToV8)]
+#[v8(object, rename_all = "lowercase")]
+pub struct Foo {
+  pub foo_bar: bool,
+}
@@ -0,0 +1,8 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+deno_ops_compile_test_runner::prelude!();
+use deno_core::ToV8;
+
+pub struct NotConvertible;
+
+#[derive(ToV8)]
+pub struct Foo(NotConvertible);
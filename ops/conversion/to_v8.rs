@@ -0,0 +1,348 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+use super::conversion_lifetime;
+use super::converted_fields;
+use super::discriminant_checks;
+use super::symbol_key;
+use super::unraw;
+use super::ConvertedData;
+use super::ConvertedField;
+use super::ConvertedStruct;
+use super::ConvertedVariant;
+use super::VariantShape;
+use proc_macro2::Literal;
+use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use quote::quote;
+use quote::quote_spanned;
+use syn::spanned::Spanned;
+use syn::Error;
+
+/// Generates a `ToV8` implementation for a struct or enum.
+///
+/// By default struct fields are converted, in declaration order, into a JS array. With
+/// `#[v8(object)]` they are instead converted into an object keyed by field name, or by
+/// a symbol for fields marked `#[v8(symbol = ...)]`. The fields of a `#[v8(flatten)]`
+/// field, which must itself be a `#[v8(object)]` struct, are merged into the object.
+/// Keys that collide, including with those of flattened fields, are a compile error,
+/// though collisions with flattened fields can't be checked for generic types.
+///
+/// Unit variants of an enum are converted to a bare string. Other variants are converted
+/// to a `[tag, payload]` array, where the payload is the value of a single unnamed field,
+/// or an array of the fields otherwise. With `#[v8(tag = "...")]`, variants are instead
+/// converted to an object with the tag stored under the given key alongside the fields.
+/// With `#[v8(discriminant)]`, an enum with only unit variants is instead converted to
+/// its discriminant as a smi, and adding `constants` also implements `EnumConstants`.
+pub(crate) fn to_v8(item: TokenStream) -> Result<TokenStream, Error> {
+  let parsed = ConvertedStruct::parse(item, "ToV8")?;
+  let ident = &parsed.input.ident;
+  let lifetime = conversion_lifetime();
+
+  let (impl_generics, ty_generics, where_clause) =
+    parsed.split_generics("ToV8");
+
+  let mut extra = vec![];
+  let body = match &parsed.data {
+    ConvertedData::Struct(fields) if parsed.attrs.object => {
+      let pattern = destructure(quote!(Self), fields);
+      let (keys, properties) = object_properties(fields, None);
+      let names = field_names(fields, None, &lifetime);
+      extra.push(quote! {
+        impl #impl_generics ::deno_core::convert::ObjectFields<#lifetime> for #ident #ty_generics #where_clause {
+          const NAMES: ::deno_core::convert::FieldNames = #names;
+
+          fn write_fields(
+            self,
+            scope: &mut ::deno_core::v8::HandleScope<#lifetime>,
+            object: ::deno_core::v8::Local<#lifetime, ::deno_core::v8::Object>,
+          ) -> Result<(), ::deno_core::error::StdAnyError> {
+            let #pattern = self;
+            #keys
+            let mut object = ::deno_core::convert::ObjectBuilder::from_object(scope, object, &KEYS)?;
+            #properties
+            Ok(())
+          }
+        }
+      });
+      if fields.iter().any(|field| field.attrs.flatten.is_some())
+        && parsed.input.generics.params.is_empty()
+      {
+        let message = format!(
+          "{ident} has two fields with the same key, counting the fields of #[v8(flatten)] fields"
+        );
+        extra.push(quote! {
+          const _: () = assert!(
+            !<#ident as ::deno_core::convert::ObjectFields<'static>>::NAMES.has_duplicates(),
+            #message
+          );
+        });
+      }
+      quote! {
+        let object = ::deno_core::v8::Object::new(scope);
+        ::deno_core::convert::ObjectFields::write_fields(self, scope, object)?;
+        Ok(object.into())
+      }
+    }
+    ConvertedData::Struct(fields) => {
+      let pattern = destructure(quote!(Self), fields);
+      let value = to_array(fields);
+      quote! {
+        let #pattern = self;
+        Ok(#value)
+      }
+    }
+    ConvertedData::Enum(variants) if parsed.attrs.discriminant => {
+      let checks = discriminant_checks(ident, variants);
+      if parsed.attrs.constants {
+        extra.push(enum_constants(&parsed, variants));
+      }
+      let variant_idents = variants.iter().map(|variant| &variant.ident);
+      quote! {
+        #checks
+        let discriminant = match self {
+          #(Self::#variant_idents => Self::#variant_idents as i32,)*
+        };
+        Ok(::deno_core::v8::Integer::new(scope, discriminant).into())
+      }
+    }
+    ConvertedData::Enum(variants) => {
+      let arms = variants.iter().map(|variant| {
+        let variant_ident = &variant.ident;
+        let pattern =
+          destructure(quote!(Self::#variant_ident), &variant.fields);
+        let name = &variant.name;
+        let tag = quote!(::deno_core::_ops::field_key(scope, #name)?);
+        let value = match (variant.shape, &parsed.attrs.tag) {
+          (VariantShape::Unit, _) => quote!(#tag.into()),
+          (_, Some(key)) => {
+            if variant
+              .fields
+              .iter()
+              .any(|field| field.attrs.flatten.is_some())
+              && parsed.input.generics.params.is_empty()
+            {
+              let names = field_names(
+                &variant.fields,
+                Some(key),
+                &syn::Lifetime::new("'static", Span::call_site()),
+              );
+              let message = format!(
+                "{ident}::{variant_ident} has two fields with the same key, counting the tag and the fields of #[v8(flatten)] fields"
+              );
+              extra.push(quote! {
+                const _: () = assert!(!#names.has_duplicates(), #message);
+              });
+            }
+            to_object(&variant.fields, Some((key, name)))
+          }
+          (VariantShape::Newtype, None) => {
+            let payload = convert_field(&variant.fields[0]);
+            tagged_array(tag, payload)
+          }
+          (VariantShape::Fields, None) => {
+            tagged_array(tag, to_array(&variant.fields))
+          }
+        };
+        quote!(#pattern => Ok(#value),)
+      });
+      quote! {
+        match self {
+          #(#arms)*
+        }
+      }
+    }
+  };
+
+  Ok(quote! {
+    impl #impl_generics ::deno_core::convert::ToV8<#lifetime> for #ident #ty_generics #where_clause {
+      type Error = ::deno_core::error::StdAnyError;
+
+      fn to_v8(
+        self,
+        scope: &mut ::deno_core::v8::HandleScope<#lifetime>,
+      ) -> Result<::deno_core::v8::Local<#lifetime, ::deno_core::v8::Value>, Self::Error> {
+        #body
+      }
+    }
+
+    #(#extra)*
+  })
+}
+
+/// An `EnumConstants` impl listing the name and discriminant of every variant.
+fn enum_constants(
+  parsed: &ConvertedStruct,
+  variants: &[ConvertedVariant],
+) -> TokenStream {
+  let ident = &parsed.input.ident;
+  let name = unraw(ident);
+  let (impl_generics, ty_generics, where_clause) =
+    parsed.input.generics.split_for_impl();
+  let variants = variants.iter().map(|variant| {
+    let variant_ident = &variant.ident;
+    let name = &variant.name;
+    quote!((#name, #ident::#variant_ident as i32))
+  });
+  quote! {
+    impl #impl_generics ::deno_core::convert::EnumConstants for #ident #ty_generics #where_clause {
+      const NAME: &'static str = #name;
+      const VARIANTS: &'static [(&'static str, i32)] = &[#(#variants),*];
+    }
+  }
+}
+
+/// A pattern binding each converted field to its local, ie: `Self { a: field0, .. }`.
+fn destructure(path: TokenStream, fields: &[ConvertedField]) -> TokenStream {
+  let members = converted_fields(fields).map(|field| &field.member);
+  let bindings = converted_fields(fields).map(|field| &field.binding);
+  let rest = if fields.iter().any(|field| field.attrs.skip) {
+    quote!(..)
+  } else {
+    quote!()
+  };
+  quote!(#path { #(#members: #bindings,)* #rest })
+}
+
+/// Converts a field's local, returning early with an error that names the field.
+fn convert_field(field: &ConvertedField) -> TokenStream {
+  let binding = &field.binding;
+  let name = &field.name;
+  // Point errors about missing `ToV8` impls at the field type
+  let to_v8 =
+    quote_spanned!(field.ty.span()=> ::deno_core::convert::ToV8::to_v8);
+  quote! {
+    match #to_v8(#binding, scope) {
+      Ok(value) => value,
+      Err(err) => return Err(::deno_core::_ops::field_error(#name, err)),
+    }
+  }
+}
+
+/// An expression converting the fields into a `v8::Array`, in declaration order.
+fn to_array(fields: &[ConvertedField]) -> TokenStream {
+  let lifetime = conversion_lifetime();
+  let count = converted_fields(fields).count();
+  let conversions = converted_fields(fields).map(convert_field);
+  quote! {{
+    let elements: [::deno_core::v8::Local<#lifetime, ::deno_core::v8::Value>; #count] = [#(#conversions),*];
+    ::deno_core::v8::Array::new_with_elements(scope, &elements).into()
+  }}
+}
+
+/// An expression converting the fields into a new `v8::Object` keyed by field name,
+/// storing the tag under the given key first.
+fn to_object(
+  fields: &[ConvertedField],
+  tag: Option<(&String, &String)>,
+) -> TokenStream {
+  let (keys, properties) = object_properties(fields, tag);
+  quote! {{
+    #keys
+    let mut object = ::deno_core::convert::ObjectBuilder::new(scope, &KEYS)?;
+    #properties
+    object.build().into()
+  }}
+}
+
+/// The string keys of the object's own fields, as a static `ObjectKeys` named `KEYS`
+/// so that they are only created once per isolate, and the statements defining the
+/// fields, and optionally a tag, on the `ObjectBuilder` named `object` by the index of
+/// their key.
+///
+/// `#[v8(flatten)]` fields write their own fields into the same object, with their own
+/// cached keys.
+fn object_properties(
+  fields: &[ConvertedField],
+  tag: Option<(&String, &String)>,
+) -> (TokenStream, TokenStream) {
+  let names = tag.iter().map(|(key, _)| *key).chain(
+    converted_fields(fields)
+      .filter(|field| {
+        symbol_key(field).is_none() && field.attrs.flatten.is_none()
+      })
+      .map(|field| &field.name),
+  );
+  let keys = quote! {
+    static KEYS: ::deno_core::convert::ObjectKeys =
+      ::deno_core::convert::ObjectKeys::new(&[#(#names),*]);
+  };
+  let tag = tag.map(|(_, name)| {
+    quote! {
+      let value = ::deno_core::_ops::field_key(scope, #name)?;
+      object.property_at(scope, 0, value.into());
+    }
+  });
+  // The tag, if any, takes the first key
+  let mut index = tag.iter().count();
+  let properties = converted_fields(fields).map(|field| {
+    if field.attrs.flatten.is_some() {
+      let binding = &field.binding;
+      // Point errors about missing `ObjectFields` impls at the field type
+      let write_fields = quote_spanned!(field.ty.span()=> ::deno_core::convert::ObjectFields::write_fields);
+      return quote! {
+        #write_fields(#binding, scope, object.object())?;
+      };
+    }
+    let conversion = convert_field(field);
+    let set = match symbol_key(field) {
+      Some(key) => quote! {
+        let key = ::deno_core::convert::SymbolKey::symbol(#key, scope)?;
+        object.set(scope, key.into(), value);
+      },
+      None => {
+        let key = Literal::usize_unsuffixed(index);
+        let set = quote!(object.property_at(scope, #key, value););
+        index += 1;
+        set
+      }
+    };
+    quote! {
+      let value = #conversion;
+      #set
+    }
+  });
+  (
+    keys,
+    quote! {
+      #tag
+      #(#properties)*
+    },
+  )
+}
+
+/// A `FieldNames` listing the string keys of the fields and, optionally, the tag, along
+/// with those of any flattened fields.
+fn field_names(
+  fields: &[ConvertedField],
+  tag: Option<&String>,
+  lifetime: &syn::Lifetime,
+) -> TokenStream {
+  let names = tag.into_iter().chain(
+    converted_fields(fields)
+      .filter(|field| {
+        symbol_key(field).is_none() && field.attrs.flatten.is_none()
+      })
+      .map(|field| &field.name),
+  );
+  let flattened = converted_fields(fields)
+    .filter(|field| field.attrs.flatten.is_some())
+    .map(|field| {
+      let ty = &field.ty;
+      quote!(&<#ty as ::deno_core::convert::ObjectFields<#lifetime>>::NAMES)
+    });
+  quote! {
+    ::deno_core::convert::FieldNames {
+      names: &[#(#names),*],
+      flattened: &[#(#flattened),*],
+    }
+  }
+}
+
+/// An expression creating a `[tag, payload]` array.
+fn tagged_array(tag: TokenStream, payload: TokenStream) -> TokenStream {
+  let lifetime = conversion_lifetime();
+  quote! {{
+    let tag = #tag;
+    let payload: ::deno_core::v8::Local<#lifetime, ::deno_core::v8::Value> = #payload;
+    ::deno_core::v8::Array::new_with_elements(scope, &[tag.into(), payload]).into()
+  }}
+}
@@ -4,6 +4,7 @@
 use proc_macro::TokenStream;
 use std::error::Error;
 
+mod conversion;
 mod op2;
 
 /// A macro designed to provide an extremely fast V8->Rust interface layer.
@@ -31,3 +32,77 @@ fn op2_macro(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
   }
 }
+
+/// Derives `deno_core::convert::ToV8` for a struct or enum.
+///
+/// Fields are converted in declaration order into a JS array, which is the fastest
+/// representation. Use `#[v8(object)]` on the struct to convert to an object keyed
+/// by field name instead, `#[v8(rename = "name")]` on a field to change its key,
+/// and `#[v8(skip)]` on a field to leave it out. `PhantomData` fields are always left
+/// out, and type parameters that are only used by fields that are left out don't need
+/// to implement `ToV8`.
+///
+/// `#[v8(rename_all = "camelCase")]` on a `#[v8(object)]` struct or a `#[v8(tag)]`
+/// enum renames every field from its `snake_case` Rust name by that policy, like
+/// serde's `rename_all`, so the keys are idiomatic in JS without a `#[v8(rename)]` on
+/// each field. The supported policies are `camelCase`, `snake_case`, `PascalCase` and
+/// `kebab-case`, and a field's own `#[v8(rename)]` takes precedence.
+///
+/// An object field marked `#[v8(symbol = "key")]` is stored under the registered
+/// symbol `Symbol.for("key")` instead of its name. The value can also be any
+/// expression evaluating to a `deno_core::convert::SymbolKey`, such as
+/// `SymbolKey::ToStringTag`.
+///
+/// An object field marked `#[v8(flatten)]` has its own fields merged into the object
+/// rather than nested under its key, like serde's `flatten`. Its type must also derive
+/// `ToV8` with `#[v8(object)]`. Two fields with the same key are a compile error,
+/// including keys that come from flattened fields, unless the struct is generic.
+///
+/// Unit enum variants are converted to their name as a string. Other variants are
+/// converted to a `[tag, payload]` array, or with `#[v8(tag = "type")]` on the enum,
+/// to an object with the variant name stored under `type` alongside its fields.
+///
+/// An enum with only unit variants can be marked `#[v8(discriminant)]` to convert to
+/// its numeric discriminant as a smi instead, including explicitly assigned values
+/// such as `A = 1`. Every discriminant must fit in an `i32`, which is checked at
+/// compile time. With `#[v8(discriminant, constants)]`, the enum also implements
+/// `deno_core::convert::EnumConstants`, whose `install_constants` defines a frozen
+/// object mapping each variant name to its discriminant and back, like a TypeScript
+/// `enum`, so JS can use the values by name.
+#[proc_macro_derive(ToV8, attributes(v8))]
+pub fn to_v8(item: TokenStream) -> TokenStream {
+  match crate::conversion::to_v8::to_v8(item.into()) {
+    Ok(output) => output.into(),
+    Err(err) => err.into_compile_error().into(),
+  }
+}
+
+/// Derives `deno_core::convert::FromV8` for a struct or enum.
+///
+/// By default the struct is read from a JS array with one element per field, in
+/// declaration order. Use `#[v8(object)]` on the struct to read it from an object
+/// keyed by field name instead, and `#[v8(rename = "name")]` on a field to change
+/// its key, or `#[v8(symbol = "key")]` to read it from a symbol-keyed property as
+/// with `#[derive(ToV8)]`. `#[v8(rename_all = "...")]` renames the keys as it does
+/// for `#[derive(ToV8)]`. Fields marked `#[v8(skip)]` are initialized with
+/// `Default::default()`, as are `PhantomData` fields, which are always skipped, and
+/// `#[v8(flatten)]` fields are read from the same object as the struct. Type
+/// parameters that are only used by skipped fields don't need to implement `FromV8`,
+/// but the types of those fields must implement `Default`.
+///
+/// `Option` fields are `None` when the value is `null`, `undefined`, or, in object
+/// mode, a missing property. An `Option<Option<T>>` field tells these apart: a missing
+/// property or `undefined` is `None`, and `null` is `Some(None)`. A `Maybe` field
+/// reads a missing property as `Maybe::Undefined`.
+///
+/// Enums are read from the representations produced by `#[derive(ToV8)]`, and
+/// an unknown variant tag is an error. A `#[v8(discriminant)]` enum is read from an
+/// integer, and any value that isn't the discriminant of a variant, including one
+/// in a gap between assigned values, is an error listing the valid variants.
+#[proc_macro_derive(FromV8, attributes(v8))]
+pub fn from_v8(item: TokenStream) -> TokenStream {
+  match crate::conversion::from_v8::from_v8(item.into()) {
+    Ok(output) => output.into(),
+    Err(err) => err.into_compile_error().into(),
+  }
+}
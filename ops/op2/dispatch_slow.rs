@@ -785,6 +785,26 @@ pub fn return_value_infallible(
     ArgSlowRetval::V8LocalNoScope => {
       gs_quote!(generator_state(retval) => (#retval.set(deno_core::_ops::RustToV8NoScope::to_v8(#result))))
     }
+    ArgSlowRetval::V8LocalFalliable if ret_type.marker() == ArgMarker::ToV8 => {
+      generator_state.needs_scope = true;
+      let err = format_ident!("{}_err", generator_state.retval);
+      let throw_exception = throw_type_error_string(generator_state, &err)?;
+
+      // Dispatch on the `ToV8::Error` type so that the error branch is compiled out
+      // when it is `Infallible`
+      gs_quote!(generator_state(scope, retval) => (match {
+        #[allow(unused_imports)]
+        use deno_core::_ops::{ToV8FallibleKind as _, ToV8InfallibleKind as _};
+        let value = #result;
+        (&&value).to_v8_kind().to_v8(value, &mut #scope)
+      } {
+        Ok(v) => #retval.set(v),
+        #[allow(unreachable_patterns)]
+        Err(#err) => {
+          #throw_exception
+        },
+      }))
+    }
     ArgSlowRetval::V8LocalFalliable => {
       generator_state.needs_scope = true;
       let err = format_ident!("{}_err", generator_state.retval);
@@ -523,6 +523,7 @@ pub enum ArgSlowRetval {
 }
 
 /// Specifies an ArgMarker wrapper for a type used for trait-based serialization.
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ArgMarker {
   None,
   /// This type should be serialized with serde_v8.
@@ -1535,6 +1536,7 @@ pub(crate) fn parse_type(
         };
         match ty {
           Type::Tuple(of) => return Ok(make_arg(stringify_token(of))),
+          Type::Array(of) => return Ok(make_arg(stringify_token(of))),
           Type::Path(of) => {
             if better_alternative_exists(position, of) {
               return Err(ArgError::InvalidAttributeType(
@@ -1872,6 +1874,11 @@ mod tests {
     #[serde] fn op_serde_tuple(#[serde] input: (A, B)) -> (A, B);
     (SerdeV8((A, B))) -> Infallible(SerdeV8((A, B)))
   );
+  // The attribute applies to the whole container type, not just the element
+  test!(
+    #[to_v8] fn op_to_v8_container(#[from_v8] foos: Vec<Foo>) -> Option<Foo>;
+    (FromV8(Vec::<Foo>)) -> Infallible(ToV8(Option::<Foo>))
+  );
   test!(
     fn op_local(input: v8::Local<v8::String>) -> Result<v8::Local<v8::String>, Error>;
     (V8Local(String)) -> Result(V8Local(String))
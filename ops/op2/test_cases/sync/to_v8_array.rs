@@ -0,0 +1,10 @@
+// Copyright 2018-2024 the Deno authors. All rights reserved. MIT license.
+#![deny(warnings)]
+deno_ops_compile_test_runner::prelude!();
+use deno_core::convert::Smi;
+
+#[op2]
+#[to_v8]
+pub fn op_to_v8_array(#[from_v8] rgba: [Smi<u8>; 4]) -> [Smi<u8>; 4] {
+  rgba
+}